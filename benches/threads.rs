@@ -0,0 +1,128 @@
+//! Compares the allocations and deallocations of several threads, locking the heap for every value
+//! against recording the values through the caches in batches: with a [`Memory`] per thread,
+//! whose cache is part of the memory itself, and with a [`SharedMemory`] whose threads allocate through [`ThreadCache`]s.
+//!
+//! Run with `cargo bench --bench threads`.
+
+use std::hint::black_box;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use halloc::{Allocatable, Memory, SharedMemory};
+
+/// Count of values allocated by each thread
+const VALUES: usize = 200_000;
+
+/// Count of values that each thread keeps alive at once
+const LIVE: usize = 64;
+
+/// A value whose blocks are kept by the caches
+#[allow(dead_code)]
+struct Small([u64; 4]);
+
+/// A value of the same size, which is too aligned for the caches to keep its blocks, so the heap is locked for each of them
+#[allow(dead_code)]
+#[repr(align(32))]
+struct Aligned([u64; 4]);
+
+impl Allocatable for Small {}
+impl Allocatable for Aligned {}
+
+/// Runs the provided workload on `threads` threads at once, returning the time it took.
+fn run(threads: usize, workload: impl Fn() + Sync) -> Duration {
+	let start = Instant::now();
+
+	thread::scope(|s| {
+		for _ in 0..threads {
+			s.spawn(&workload);
+		}
+	});
+
+	start.elapsed()
+}
+
+/// Allocates and drops the values on the same thread, keeping up to [`LIVE`] of them alive.
+fn local<T>(alloc: impl Fn(u64) -> T) {
+	let mut live = Vec::with_capacity(LIVE);
+
+	for i in 0..VALUES {
+		if live.len() == LIVE {
+			live.clear();
+		}
+
+		live.push(black_box(alloc(i as u64)));
+	}
+}
+
+/// Allocates the values on the current thread, and drops them on a new one.
+fn remote<T: Send>(alloc: impl Fn(u64) -> T) {
+	let (sender, receiver) = mpsc::sync_channel::<Vec<T>>(4);
+
+	thread::scope(|s| {
+		s.spawn(move || {
+			for values in receiver {
+				drop(black_box(values));
+			}
+		});
+
+		for batch in 0..VALUES / LIVE {
+			let values = (0..LIVE)
+				.map(|i| alloc((batch * LIVE + i) as u64))
+				.collect();
+			sender.send(values).unwrap();
+		}
+
+		drop(sender);
+	});
+}
+
+fn main() {
+	println!(
+		"{:<8} {:<12} {:>14} {:>14} {:>14} {:>14}",
+		"threads", "workload", "memory locked", "memory cached", "shared locked", "shared cached"
+	);
+
+	for threads in [1, 2, 4, 8] {
+		let memory = SharedMemory::new();
+
+		let own_locked = run(threads, || {
+			let memory = Memory::new();
+			local(|i| memory.alloc(Aligned([i; 4])));
+		});
+		let own_cached = run(threads, || {
+			let memory = Memory::new();
+			local(|i| memory.alloc(Small([i; 4])));
+		});
+		let locked = run(threads, || {
+			let cache = memory.cache();
+			local(|i| cache.alloc(Aligned([i; 4])));
+		});
+		let cached = run(threads, || {
+			let cache = memory.cache();
+			local(|i| cache.alloc(Small([i; 4])));
+		});
+
+		assert_eq!(memory.count(), 0);
+		println!(
+			"{threads:<8} {:<12} {own_locked:>14.2?} {own_cached:>14.2?} {locked:>14.2?} {cached:>14.2?}",
+			"local"
+		);
+
+		// The mutators of a `Memory` cannot be sent to other threads
+		let locked = run(threads, || {
+			let cache = memory.cache();
+			remote(|i| cache.alloc(Aligned([i; 4])));
+		});
+		let cached = run(threads, || {
+			let cache = memory.cache();
+			remote(|i| cache.alloc(Small([i; 4])));
+		});
+
+		assert_eq!(memory.count(), 0);
+		println!(
+			"{threads:<8} {:<12} {:>14} {:>14} {locked:>14.2?} {cached:>14.2?}",
+			"remote", "-", "-"
+		);
+	}
+}
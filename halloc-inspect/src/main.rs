@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::ExitCode;
+
+use halloc::{DiffGroup, DumpEntry, DumpError, HeapDiff, HeapDump};
+
+/// Count of entries shown in the top lists by default
+const DEFAULT_TOP: usize = 10;
+
+/// Count of bytes shown per line of the hex view
+const HEX_LINE: usize = 16;
+
+const USAGE: &str = "Usage: halloc-inspect <dump> [--top <count>] [--no-hex] [--diff <earlier dump>]
+
+Prints a summary of a heap dump written by `Memory::dump_to`, the largest allocations and types,
+and a hex view of every allocation.
+
+Options:
+  --top <count>          Count of entries shown in the top lists (default: 10)
+  --no-hex               Do not print the hex view of the allocations
+  --diff <earlier dump>  Print the growth since an earlier dump instead, grouped by type and by allocation site";
+
+/// Options passed on the command line
+struct Options {
+	/// Path of the dump file
+	path: String,
+
+	/// Count of entries shown in the top lists
+	top: usize,
+
+	/// Indicates whether the hex view of the allocations is printed
+	hex: bool,
+
+	/// Path of the earlier dump to compare with, if any
+	diff: Option<String>
+}
+
+impl Options {
+	/// Parses the command line arguments.
+	fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+		let mut path = None;
+		let mut top = DEFAULT_TOP;
+		let mut hex = true;
+		let mut diff = None;
+
+		while let Some(arg) = args.next() {
+			match arg.as_str() {
+				"--top" => {
+					let count = args.next().ok_or("Missing count after `--top`")?;
+					top = count
+						.parse()
+						.map_err(|_| format!("Invalid count `{count}`"))?;
+				}
+				"--no-hex" => hex = false,
+				"--diff" => diff = Some(args.next().ok_or("Missing path after `--diff`")?),
+				_ if arg.starts_with("--") => return Err(format!("Unknown option `{arg}`")),
+				_ if path.is_none() => path = Some(arg),
+				_ => return Err(format!("Unexpected argument `{arg}`"))
+			}
+		}
+
+		Ok(Self {
+			path: path.ok_or("Missing dump path")?,
+			top,
+			hex,
+			diff
+		})
+	}
+}
+
+fn main() -> ExitCode {
+	let options = match Options::parse(std::env::args().skip(1)) {
+		Ok(options) => options,
+		Err(error) => {
+			eprintln!("{error}\n\n{USAGE}");
+			return ExitCode::FAILURE;
+		}
+	};
+
+	let Some(dump) = load(&options.path) else {
+		return ExitCode::FAILURE;
+	};
+
+	print_summary(&dump);
+
+	if let Some(path) = &options.diff {
+		let Some(earlier) = load(path) else {
+			return ExitCode::FAILURE;
+		};
+
+		print_diff(&HeapDiff::between(&earlier, &dump), options.top);
+		return ExitCode::SUCCESS;
+	}
+
+	print_top_allocations(&dump, options.top);
+	print_top_types(&dump, options.top);
+
+	if options.hex {
+		print_hex(&dump);
+	}
+
+	ExitCode::SUCCESS
+}
+
+/// Reads the dump at the provided path, reporting the error if that fails.
+fn load(path: &str) -> Option<HeapDump> {
+	let dump = File::open(path)
+		.map_err(DumpError::from)
+		.and_then(|file| HeapDump::read_from(BufReader::new(file)));
+
+	let dump = dump
+		.inspect_err(|error| match error {
+			DumpError::UnsupportedVersion { .. } => eprintln!(
+				"{path}: {error} (this build of halloc-inspect reads version {})",
+				HeapDump::VERSION
+			),
+			_ => eprintln!("{path}: {error}")
+		})
+		.ok()?;
+
+	if dump.version() != HeapDump::VERSION {
+		eprintln!(
+			"warning: {path} was written with format version {}, while this build of halloc-inspect reads version {}",
+			dump.version(),
+			HeapDump::VERSION
+		);
+	}
+
+	Some(dump)
+}
+
+/// Gets the printable name of the entry's type.
+fn type_name(entry: &DumpEntry) -> &str { entry.type_name().unwrap_or("<unknown>") }
+
+fn print_summary(dump: &HeapDump) {
+	let plain = dump.entries().iter().filter(|e| e.is_plain()).count();
+	let types = dump
+		.entries()
+		.iter()
+		.map(type_name)
+		.collect::<std::collections::HashSet<_>>()
+		.len();
+
+	println!("Heap dump (format version {})", dump.version());
+	println!("  allocations: {}", dump.entries().len());
+	println!("  bytes:       {}", dump.size());
+	println!("  types:       {types}");
+	println!("  plain data:  {plain}");
+}
+
+fn print_top_allocations(dump: &HeapDump, top: usize) {
+	let mut entries: Vec<_> = dump.entries().iter().collect();
+	entries.sort_by_key(|e| std::cmp::Reverse(e.layout().size()));
+
+	println!("\nTop {top} allocations by size:");
+	println!("  {:>10}  {:>18}  {:<32}  SITE", "BYTES", "ADDRESS", "TYPE");
+
+	for entry in entries.into_iter().take(top) {
+		println!(
+			"  {:>10}  {:>#18x}  {:<32}  {}",
+			entry.layout().size(),
+			entry.address(),
+			type_name(entry),
+			entry.site()
+		);
+	}
+}
+
+fn print_top_types(dump: &HeapDump, top: usize) {
+	// Totals of the allocation count and bytes per type
+	let mut totals: HashMap<&str, (usize, usize)> = HashMap::new();
+
+	for entry in dump.entries() {
+		let total = totals.entry(type_name(entry)).or_default();
+		total.0 += 1;
+		total.1 += entry.layout().size();
+	}
+
+	let mut totals: Vec<_> = totals.into_iter().collect();
+	totals.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then(a.0.cmp(b.0)));
+
+	println!("\nTop {top} types by size:");
+	println!("  {:>10}  {:>8}  TYPE", "BYTES", "COUNT");
+
+	for (name, (count, bytes)) in totals.into_iter().take(top) {
+		println!("  {bytes:>10}  {count:>8}  {name}");
+	}
+}
+
+fn print_diff(diff: &HeapDiff, top: usize) {
+	println!("\nChanges since the earlier dump:");
+	println!("  added:      {}", diff.added().len());
+	println!("  freed:      {}", diff.freed().len());
+	println!("  changed:    {}", diff.changed().len());
+	println!("  byte delta: {:+}", diff.byte_delta());
+
+	let types = diff
+		.by_type()
+		.into_iter()
+		.map(|(name, group)| (name.unwrap_or("<unknown>").to_owned(), group));
+
+	let sites = diff
+		.by_site()
+		.into_iter()
+		.map(|(site, group)| (site.to_string(), group));
+
+	print_diff_groups("types", types, top);
+	print_diff_groups("allocation sites", sites, top);
+}
+
+fn print_diff_groups(name: &str, groups: impl Iterator<Item = (String, DiffGroup)>, top: usize) {
+	println!("\nTop {top} {name} by growth:");
+	println!(
+		"  {:>10}  {:>8}  {:>8}  {:>8}  KEY",
+		"BYTES", "ADDED", "FREED", "CHANGED"
+	);
+
+	for (key, group) in groups.take(top) {
+		println!(
+			"  {:>+10}  {:>8}  {:>8}  {:>8}  {key}",
+			group.byte_delta, group.added, group.freed, group.changed
+		);
+	}
+}
+
+fn print_hex(dump: &HeapDump) {
+	for entry in dump.entries() {
+		let layout = entry.layout();
+
+		println!(
+			"\n{:#x} ({} bytes, align {}) {} at {}",
+			entry.address(),
+			layout.size(),
+			layout.align(),
+			type_name(entry),
+			entry.site()
+		);
+
+		for (index, line) in entry.bytes().chunks(HEX_LINE).enumerate() {
+			let hex: Vec<_> = line.iter().map(|byte| format!("{byte:02x}")).collect();
+			let ascii: String = line
+				.iter()
+				.map(|&byte| {
+					if byte.is_ascii_graphic() || byte == b' ' {
+						byte as char
+					} else {
+						'.'
+					}
+				})
+				.collect();
+
+			println!(
+				"  {:08x}  {:<width$}  |{ascii}|",
+				index * HEX_LINE,
+				hex.join(" "),
+				width = HEX_LINE * 3 - 1
+			);
+		}
+	}
+}
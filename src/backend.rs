@@ -0,0 +1,152 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::ptr::NonNull;
+
+#[cfg(feature = "alloc")]
+use crate::Chunk;
+
+/// The source of the raw memory handed out by a [`Heap`](crate::Heap).
+///
+/// The heap keeps all of the bookkeeping (records, statistics, budgets, observers, etc.) itself,
+/// and only asks the backend for memory. The records of the allocations are stored in the memory of the backend
+/// along with their values, so the backend is never asked for zero-sized allocations.
+///
+/// # Safety
+///
+/// Memory returned by [`allocate`](RawBackend::allocate) and [`reallocate`](RawBackend::reallocate) must be valid
+/// for reads and writes of the requested layout, and must not overlap any other live allocation of the backend,
+/// until it is passed to [`deallocate`](RawBackend::deallocate) or [`reallocate`](RawBackend::reallocate).
+///
+/// # Examples
+///
+/// A backend that fails once the provided count of allocations has been made:
+///
+/// ```
+/// # use halloc::{AllocError, Memory, RawBackend, SystemBackend};
+/// # use std::alloc::Layout;
+/// # use std::ptr::NonNull;
+/// struct Failing {
+///     remaining: usize
+/// }
+///
+/// unsafe impl RawBackend for Failing {
+///     fn allocate(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+///         self.remaining = self.remaining.checked_sub(1)?;
+///         SystemBackend.allocate(layout)
+///     }
+///
+///     unsafe fn deallocate(&mut self, ptr: NonNull<u8>, layout: Layout) {
+///         unsafe { SystemBackend.deallocate(ptr, layout) }
+///     }
+/// }
+///
+/// // Each value takes two allocations: its block, and the state shared with its mutators
+/// let memory = Memory::with_backend(Failing { remaining: 2 });
+///
+/// let value = memory.try_alloc(5u32).unwrap();
+/// assert_eq!(
+///     memory.try_alloc(6u32).err(),
+///     Some(AllocError::OutOfMemory { requested: 4 })
+/// );
+///
+/// assert_eq!(*value, 5);
+/// assert_eq!(memory.stats().total_allocs, 1);
+/// ```
+pub unsafe trait RawBackend: Send + 'static {
+	/// Indicates whether memory allocated by one instance of the backend can be deallocated by any other instance,
+	/// in which case values can be moved between heaps without copying them (see [`HeapMutator::transfer_to`](crate::HeapMutator::transfer_to))
+	const SHARED: bool = false;
+
+	/// Allocates memory for the provided non-zero-sized [`Layout`], returning [`None`] if the backend is exhausted.
+	fn allocate(&mut self, layout: Layout) -> Option<NonNull<u8>>;
+
+	/// Deallocates the memory at the provided pointer.
+	///
+	/// # Safety
+	///
+	/// The pointer must have been allocated by this backend with the provided [`Layout`], and must not be used afterwards.
+	unsafe fn deallocate(&mut self, ptr: NonNull<u8>, layout: Layout);
+
+	/// Shrinks or grows the memory at the provided pointer to `new_size` bytes (which is never zero), keeping its alignment.
+	///
+	/// Returns [`None`] if the backend is exhausted, in which case the memory is left untouched.
+	/// By default, the memory is moved to a new allocation.
+	///
+	/// # Safety
+	///
+	/// The pointer must have been allocated by this backend with the provided [`Layout`], and must not be used afterwards
+	/// unless [`None`] is returned.
+	unsafe fn reallocate(
+		&mut self,
+		ptr: NonNull<u8>,
+		layout: Layout,
+		new_size: usize
+	) -> Option<NonNull<u8>> {
+		let new_layout = Layout::from_size_align(new_size, layout.align()).ok()?;
+		let new_ptr = self.allocate(new_layout)?;
+
+		unsafe {
+			core::ptr::copy_nonoverlapping(
+				ptr.as_ptr(),
+				new_ptr.as_ptr(),
+				core::cmp::min(layout.size(), new_size)
+			);
+			self.deallocate(ptr, layout);
+		}
+
+		Some(new_ptr)
+	}
+
+	/// Gets the count of bytes that the backend reserves for an allocation of the provided non-zero-sized [`Layout`],
+	/// which may be more than its size (e.g., when the size of its blocks is rounded up).
+	///
+	/// Returns the size of the layout by default, for backends that do not know how much they reserve (e.g., [`SystemBackend`]).
+	/// See [`HeapStats::alignment_waste`](crate::HeapStats::alignment_waste).
+	fn reserved_size(&self, layout: Layout) -> usize { layout.size() }
+
+	/// Describes the chunks of memory that the backend hands out allocations from, along with their free blocks.
+	///
+	/// Returns [`None`] by default, for backends that do not manage chunks of their own (e.g., [`SystemBackend`]).
+	/// See [`Memory::fragmentation`](crate::Memory::fragmentation).
+	#[cfg(feature = "alloc")]
+	fn chunks(&self) -> Option<Vec<Chunk>> { None }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The default [`RawBackend`], which uses the global allocator.
+///
+/// It can only be used with the `alloc` feature.
+pub struct SystemBackend;
+
+#[cfg(feature = "alloc")]
+/// The [`RawBackend`] that is used when none is specified: [`SystemBackend`] with the `alloc` feature,
+/// and [`StaticBuffer`](crate::StaticBuffer) without it.
+pub type DefaultBackend = SystemBackend;
+
+#[cfg(not(feature = "alloc"))]
+/// The [`RawBackend`] that is used when none is specified: [`SystemBackend`] with the `alloc` feature,
+/// and [`StaticBuffer`](crate::StaticBuffer) without it.
+pub type DefaultBackend = crate::StaticBuffer;
+
+#[cfg(feature = "alloc")]
+unsafe impl RawBackend for SystemBackend {
+	const SHARED: bool = true;
+
+	fn allocate(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+		NonNull::new(unsafe { alloc::alloc::alloc(layout) })
+	}
+
+	unsafe fn deallocate(&mut self, ptr: NonNull<u8>, layout: Layout) {
+		unsafe { alloc::alloc::dealloc(ptr.as_ptr(), layout) }
+	}
+
+	unsafe fn reallocate(
+		&mut self,
+		ptr: NonNull<u8>,
+		layout: Layout,
+		new_size: usize
+	) -> Option<NonNull<u8>> {
+		NonNull::new(unsafe { alloc::alloc::realloc(ptr.as_ptr(), layout, new_size) })
+	}
+}
@@ -0,0 +1,267 @@
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(feature = "alloc")]
+use core::fmt::{self, Debug, Formatter};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "alloc")]
+use crate::sync::Mutex;
+use crate::AllocError;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The memory usage reported to the callbacks registered with [`Memory::on_pressure`](crate::Memory::on_pressure).
+pub struct Pressure {
+	/// Fraction of the limit that has been crossed
+	pub threshold: f64,
+
+	/// Count of bytes that are currently allocated (including the child memories)
+	pub usage: usize,
+
+	/// Byte limit of the memory
+	pub limit: usize
+}
+
+#[cfg(feature = "alloc")]
+/// A callback that is called when the memory usage crosses a threshold.
+pub(crate) type PressureCallback = Arc<dyn Fn(&Pressure) + Send + Sync>;
+
+#[cfg(feature = "alloc")]
+/// A registered pressure callback along with its threshold.
+struct Threshold {
+	/// Fraction of the limit at which the callback is called
+	ratio: f64,
+
+	/// Callback to call
+	callback: PressureCallback,
+
+	/// Indicates whether the usage has been below the threshold since the last call
+	armed: bool
+}
+
+#[derive(Debug)]
+/// The accounting of a [`Heap`](crate::Heap) and of all the heaps of its child memories,
+/// along with their byte limit and pressure thresholds.
+///
+/// The budgets of child memories are linked to the budget of their parent, so that their allocations are
+/// counted (and limited) by all of their ancestors.
+pub(crate) struct Budget {
+	#[cfg(feature = "alloc")]
+	/// Budget of the parent memory, if any
+	parent: Option<Arc<Budget>>,
+
+	/// Hard limit of the allocated bytes, if any
+	pub(crate) limit: Option<usize>,
+
+	/// Count of allocated bytes, including the child memories
+	bytes: AtomicUsize,
+
+	/// Count of live allocations, including the child memories
+	count: AtomicUsize,
+
+	#[cfg(feature = "alloc")]
+	/// Registered pressure thresholds
+	thresholds: Mutex<Vec<Threshold>>
+}
+
+#[cfg(feature = "alloc")]
+/// The budget as it is held by a [`Heap`](crate::Heap), which is shared with its child memories and pools.
+pub(crate) type HeapBudget = Arc<Budget>;
+
+#[cfg(not(feature = "alloc"))]
+/// The budget as it is held by a [`Heap`](crate::Heap), which has no child memories nor pools without `alloc`.
+pub(crate) type HeapBudget = Budget;
+
+#[must_use]
+#[derive(Default)]
+/// The pressure callbacks whose thresholds have been crossed, which have to be called once no lock is held.
+pub(crate) struct Crossed {
+	#[cfg(feature = "alloc")]
+	/// Callbacks to call, along with the usage to report to them
+	callbacks: Vec<(PressureCallback, Pressure)>
+}
+
+impl Crossed {
+	/// Calls the crossed callbacks.
+	pub(crate) fn call(self) {
+		#[cfg(feature = "alloc")]
+		for (callback, pressure) in self.callbacks {
+			callback(&pressure);
+		}
+	}
+}
+
+impl Budget {
+	/// Creates a new budget with the provided byte limit.
+	pub(crate) fn new(limit: Option<usize>) -> Self {
+		Self {
+			#[cfg(feature = "alloc")]
+			parent: None,
+			limit,
+			bytes: AtomicUsize::new(0),
+			count: AtomicUsize::new(0),
+			#[cfg(feature = "alloc")]
+			thresholds: Mutex::new(vec![])
+		}
+	}
+
+	/// Creates a new budget with the provided byte limit, linked to the provided parent budget.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn with_parent(parent: Arc<Budget>, limit: Option<usize>) -> Self {
+		Self {
+			parent: Some(parent),
+			..Self::new(limit)
+		}
+	}
+
+	/// Turns the budget into the one held by a heap.
+	pub(crate) fn into_heap(self) -> HeapBudget {
+		#[cfg(feature = "alloc")]
+		return Arc::new(self);
+
+		#[cfg(not(feature = "alloc"))]
+		self
+	}
+
+	/// Gets the budget of the parent memory, if any.
+	fn parent(&self) -> Option<&Budget> {
+		#[cfg(feature = "alloc")]
+		return self.parent.as_deref();
+
+		#[cfg(not(feature = "alloc"))]
+		None
+	}
+
+	/// Shows whether the allocations are neither limited by the budget nor counted by a parent one.
+	pub(crate) fn is_unlimited(&self) -> bool { self.limit.is_none() && self.parent().is_none() }
+
+	/// Gets the count of allocated bytes, including the child memories.
+	pub(crate) fn bytes(&self) -> usize { self.bytes.load(Ordering::Relaxed) }
+
+	/// Gets the count of live allocations, including the child memories.
+	pub(crate) fn count(&self) -> usize { self.count.load(Ordering::Relaxed) }
+
+	/// Counts `bytes` more bytes and `count` more allocations, ignoring the limits.
+	pub(crate) fn charge(&self, bytes: usize, count: usize) {
+		self.bytes.fetch_add(bytes, Ordering::Relaxed);
+		self.count.fetch_add(count, Ordering::Relaxed);
+
+		if let Some(parent) = self.parent() {
+			parent.charge(bytes, count);
+		}
+	}
+
+	/// Counts `bytes` more bytes and `count` more allocations,
+	/// failing if that would exceed the limit of the budget or of any of its ancestors.
+	pub(crate) fn try_charge(&self, bytes: usize, count: usize) -> Result<(), AllocError> {
+		// Reserving the bytes atomically, so that concurrent child memories cannot exceed the limit together
+		let reserved = self
+			.bytes
+			.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |usage| {
+				let new_usage = usage.checked_add(bytes)?;
+				let within_limit = self.limit.is_none_or(|limit| new_usage <= limit);
+
+				within_limit.then_some(new_usage)
+			});
+
+		if let Err(usage) = reserved {
+			return Err(AllocError::LimitExceeded {
+				requested: bytes,
+				available: self.limit.unwrap_or(usize::MAX).saturating_sub(usage)
+			});
+		}
+
+		if let Some(parent) = self.parent() {
+			if let Err(error) = parent.try_charge(bytes, count) {
+				// Undoing the reservation
+				self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+				return Err(error);
+			}
+		}
+
+		self.count.fetch_add(count, Ordering::Relaxed);
+		Ok(())
+	}
+
+	/// Stops counting `bytes` bytes and `count` allocations.
+	pub(crate) fn release(&self, bytes: usize, count: usize) {
+		self.bytes.fetch_sub(bytes, Ordering::Relaxed);
+		self.count.fetch_sub(count, Ordering::Relaxed);
+
+		if let Some(parent) = self.parent() {
+			parent.release(bytes, count);
+		}
+	}
+
+	/// Registers a callback that is called when the usage rises to or above the provided fraction of the limit.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn add_threshold(&self, ratio: f64, callback: PressureCallback) {
+		self.thresholds
+			.lock()
+			.expect("Threshold lock failed")
+			.push(Threshold {
+				ratio,
+				callback,
+				armed: true
+			});
+	}
+
+	/// Gets the callbacks of the thresholds of the budget and its ancestors that have been crossed since the last check.
+	///
+	/// The callbacks are returned instead of being called, so that the caller decides when it is safe to call them.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn crossed(&self) -> Crossed {
+		let mut crossed = self
+			.parent()
+			.map(|parent| parent.crossed())
+			.unwrap_or_default();
+
+		let Some(limit) = self.limit else {
+			return crossed;
+		};
+
+		let usage = self.bytes();
+		let mut thresholds = self.thresholds.lock().expect("Threshold lock failed");
+
+		for threshold in thresholds.iter_mut() {
+			let bytes = (limit as f64 * threshold.ratio) as usize;
+
+			if usage < bytes {
+				// Re-arming the threshold, so that it fires once the usage rises again
+				threshold.armed = true;
+			} else if threshold.armed {
+				threshold.armed = false;
+
+				crossed.callbacks.push((
+					Arc::clone(&threshold.callback),
+					Pressure {
+						threshold: threshold.ratio,
+						usage,
+						limit
+					}
+				));
+			}
+		}
+
+		crossed
+	}
+
+	/// Gets the callbacks of the crossed thresholds, of which there are none without `alloc`.
+	#[cfg(not(feature = "alloc"))]
+	pub(crate) fn crossed(&self) -> Crossed { Crossed {} }
+}
+
+impl Default for Budget {
+	fn default() -> Self { Self::new(None) }
+}
+
+#[cfg(feature = "alloc")]
+impl Debug for Threshold {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Threshold")
+			.field("ratio", &self.ratio)
+			.field("armed", &self.armed)
+			.finish_non_exhaustive()
+	}
+}
@@ -0,0 +1,260 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::mem::{align_of, size_of};
+use core::ptr::NonNull;
+
+#[cfg(feature = "alloc")]
+use crate::Chunk;
+use crate::RawBackend;
+
+/// Smallest size (and the size granularity) of the blocks handed out by a [`StaticBuffer`],
+/// so that every block can hold a [`Hole`] once it is freed
+const BLOCK: usize = size_of::<Hole>();
+
+/// Alignment of the blocks handed out by a [`StaticBuffer`]
+const BLOCK_ALIGN: usize = align_of::<Hole>();
+
+/// A free block of a [`StaticBuffer`], stored within the block itself.
+struct Hole {
+	/// Size of the block
+	size: usize,
+
+	/// Next free block, which is always at a higher address
+	next: Option<NonNull<Hole>>
+}
+
+#[derive(Debug)]
+/// A [`RawBackend`] that hands out the memory of a fixed buffer, without ever using the global allocator.
+///
+/// The [`Heap`](crate::Heap) allocating from it keeps its bookkeeping within the buffer as well, so the crate can be used
+/// without a global allocator at all, see [`Memory::from_static_buffer`](crate::Memory::from_static_buffer).
+///
+/// Free blocks are kept in a list stored within the buffer itself, sorted by their addresses, and adjacent free blocks
+/// are merged once deallocated. Allocations take the first free block that fits them.
+///
+/// See [`Memory::from_static_buffer`](crate::Memory::from_static_buffer) for examples.
+pub struct StaticBuffer {
+	/// First free block, if any
+	holes: Option<NonNull<Hole>>,
+
+	#[cfg(feature = "alloc")]
+	/// Address of the first byte that can be handed out
+	start: usize,
+
+	/// Count of bytes that can be handed out
+	capacity: usize,
+
+	/// Count of bytes that are currently handed out, including the padding of the blocks
+	used: usize
+}
+
+// The buffer is borrowed exclusively, so it can be used from any thread
+unsafe impl Send for StaticBuffer {}
+
+impl StaticBuffer {
+	/// Creates a backend that hands out the memory of the provided buffer.
+	///
+	/// A few bytes at the start and at the end of the buffer may be left unused to align the blocks.
+	pub fn new(buffer: &'static mut [u8]) -> Self {
+		let offset = buffer.as_ptr().align_offset(BLOCK_ALIGN);
+		let capacity = buffer.len().saturating_sub(offset) / BLOCK_ALIGN * BLOCK_ALIGN;
+
+		if capacity < BLOCK {
+			return Self {
+				holes: None,
+				#[cfg(feature = "alloc")]
+				start: buffer.as_ptr() as usize,
+				capacity: 0,
+				used: 0
+			};
+		}
+
+		// The whole buffer starts out as a single free block
+		let hole =
+			unsafe { NonNull::new_unchecked(buffer.as_mut_ptr().add(offset)).cast::<Hole>() };
+		unsafe {
+			hole.write(Hole {
+				size: capacity,
+				next: None
+			})
+		};
+
+		Self {
+			holes: Some(hole),
+			#[cfg(feature = "alloc")]
+			start: hole.as_ptr() as usize,
+			capacity,
+			used: 0
+		}
+	}
+
+	/// Gets the count of bytes that the buffer can hand out in total.
+	pub fn capacity(&self) -> usize { self.capacity }
+
+	/// Gets the count of bytes that are not handed out.
+	///
+	/// Since the free bytes may be spread over several blocks, an allocation of that size does not necessarily succeed.
+	pub fn available(&self) -> usize { self.capacity - self.used }
+
+	/// Makes the provided hole follow the previous one (or start the list).
+	fn link(&mut self, previous: Option<NonNull<Hole>>, hole: Option<NonNull<Hole>>) {
+		match previous {
+			Some(mut previous) => unsafe { previous.as_mut().next = hole },
+			None => self.holes = hole
+		}
+	}
+}
+
+unsafe impl RawBackend for StaticBuffer {
+	fn allocate(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+		let size = block_size(layout)?;
+		let align = core::cmp::max(layout.align(), BLOCK_ALIGN);
+
+		let mut previous: Option<NonNull<Hole>> = None;
+		let mut current = self.holes;
+
+		while let Some(hole) = current {
+			let Hole {
+				size: hole_size,
+				next
+			} = unsafe { hole.read() };
+
+			let hole_start = hole.as_ptr() as usize;
+			let hole_end = hole_start + hole_size;
+
+			// The padding before the block has to fit a hole of its own
+			let mut start = align_up(hole_start, align)?;
+			if start != hole_start && start - hole_start < BLOCK {
+				start = align_up(hole_start + BLOCK, align)?;
+			}
+
+			let fits = start.checked_add(size).filter(|&end| {
+				// The rest of the hole has to fit a hole of its own as well
+				end <= hole_end && (end == hole_end || hole_end - end >= BLOCK)
+			});
+
+			let Some(end) = fits else {
+				previous = Some(hole);
+				current = next;
+				continue;
+			};
+
+			let base = hole.cast::<u8>();
+
+			// Splitting off the rest of the hole
+			let after = if end < hole_end {
+				let rest = unsafe { base.add(end - hole_start).cast::<Hole>() };
+				unsafe {
+					rest.write(Hole {
+						size: hole_end - end,
+						next
+					})
+				};
+
+				Some(rest)
+			} else {
+				next
+			};
+
+			if start > hole_start {
+				// Keeping the padding before the block as a smaller hole
+				unsafe {
+					hole.write(Hole {
+						size: start - hole_start,
+						next: after
+					})
+				};
+			} else {
+				self.link(previous, after);
+			}
+
+			self.used += size;
+			return Some(unsafe { base.add(start - hole_start) });
+		}
+
+		None
+	}
+
+	unsafe fn deallocate(&mut self, ptr: NonNull<u8>, layout: Layout) {
+		let size = block_size(layout).expect("Layout of the allocation is invalid");
+		let start = ptr.as_ptr() as usize;
+
+		self.used -= size;
+
+		// Finding the holes right before and right after the block
+		let mut previous: Option<NonNull<Hole>> = None;
+		let mut current = self.holes;
+
+		while let Some(hole) = current {
+			if hole.as_ptr() as usize > start {
+				break;
+			}
+
+			previous = Some(hole);
+			current = unsafe { hole.read() }.next;
+		}
+
+		let hole = ptr.cast::<Hole>();
+		let mut freed = Hole {
+			size,
+			next: current
+		};
+
+		// Merging with the next hole
+		if let Some(next) = current {
+			if start + size == next.as_ptr() as usize {
+				let next = unsafe { next.read() };
+				freed.size += next.size;
+				freed.next = next.next;
+			}
+		}
+
+		// Merging with the previous hole
+		if let Some(mut previous) = previous {
+			let previous = unsafe { previous.as_mut() };
+
+			if previous.size + previous as *mut Hole as usize == start {
+				previous.size += freed.size;
+				previous.next = freed.next;
+				return;
+			}
+		}
+
+		unsafe { hole.write(freed) };
+		self.link(previous, Some(hole));
+	}
+
+	fn reserved_size(&self, layout: Layout) -> usize { block_size(layout).unwrap_or(layout.size()) }
+
+	#[cfg(feature = "alloc")]
+	fn chunks(&self) -> Option<Vec<Chunk>> {
+		let mut chunk = Chunk {
+			start: self.start,
+			size: self.capacity,
+			free: vec![]
+		};
+
+		let mut current = self.holes;
+
+		while let Some(hole) = current {
+			let Hole { size, next } = unsafe { hole.read() };
+			let offset = hole.as_ptr() as usize - self.start;
+
+			chunk.free.push(offset..offset + size);
+			current = next;
+		}
+
+		Some(vec![chunk])
+	}
+}
+
+/// Gets the size of the block that holds an allocation with the provided [`Layout`].
+fn block_size(layout: Layout) -> Option<usize> {
+	align_up(core::cmp::max(layout.size(), BLOCK), BLOCK_ALIGN)
+}
+
+/// Rounds the provided value up to a multiple of `align`, which is a power of two.
+fn align_up(value: usize, align: usize) -> Option<usize> {
+	Some(value.checked_add(align - 1)? & !(align - 1))
+}
@@ -0,0 +1,439 @@
+//! The caches that allocate values and take back the memory of the dropped ones without locking the [`Heap`] for each of them.
+
+use core::alloc::Layout;
+use core::cell::{Cell, UnsafeCell};
+use core::panic::Location;
+use core::ptr::NonNull;
+
+use crate::heap::{header, memory, write_record, Allocation, DropGlue, List, Slot, TypeTag};
+use crate::sync::{Mutex, MutexGuard};
+use crate::{Heap, RawBackend};
+
+/// Count of values that a cache allocates or drops before recording them in the heap,
+/// which is also the count of free blocks (or slots) that it allocates from the backend at once
+pub(crate) const BATCH: usize = 32;
+
+/// Count of the layouts that the free blocks of a cache are kept for
+const BINS: usize = 8;
+
+/// Count of free blocks of a single layout (and of free slots) that a cache keeps at most,
+/// beyond which the memory of the dropped values is returned to the backend
+const CAPACITY: usize = 2 * BATCH;
+
+/// Largest size of the values whose blocks are kept by the caches
+const MAX_SIZE: usize = 1024;
+
+/// Largest alignment of the values whose blocks are kept by the caches
+const MAX_ALIGN: usize = 16;
+
+/// A free block (or slot), which stores the link to the next one of its list within itself.
+///
+/// The links of the free blocks are stored where the records of their allocations go.
+struct Free {
+	/// Next free block of the list
+	next: Option<NonNull<Free>>
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+/// A list of free blocks that hold values of the same layout.
+struct Bin {
+	/// Layout of the values, if the bin is in use
+	layout: Option<Layout>,
+
+	/// First free block of the list
+	head: Option<NonNull<Free>>,
+
+	/// Count of free blocks within the list
+	len: usize
+}
+
+impl Bin {
+	/// Adds the block of the value at the provided pointer to the list.
+	fn push(&mut self, ptr: NonNull<u8>) {
+		let block = header(ptr).cast::<Free>();
+		unsafe { block.write(Free { next: self.head }) }
+
+		self.head = Some(block);
+		self.len += 1;
+	}
+
+	/// Removes a block from the list, returning the pointer to the memory of its value.
+	fn pop(&mut self) -> Option<NonNull<u8>> {
+		let block = self.head?;
+		self.head = unsafe { block.as_ref() }.next;
+		self.len -= 1;
+
+		Some(memory(block.cast::<Allocation>()))
+	}
+
+	/// Returns all of the blocks of the list to the backend of the provided heap.
+	fn clear<B: RawBackend>(&mut self, heap: &mut Heap<B>) {
+		let Some(layout) = self.layout.take() else {
+			return;
+		};
+
+		while let Some(ptr) = self.pop() {
+			unsafe { heap.deallocate_block(ptr, layout) }
+		}
+	}
+}
+
+#[derive(Debug, Default)]
+/// The free blocks of a cache, grouped by the layout of their values.
+///
+/// Blocks are only reused for values of the exact same layout, so that the records of the values keep their actual layout
+/// (and are counted with their actual size).
+pub(crate) struct Bins {
+	/// Lists of free blocks, by layout
+	bins: [Bin; BINS]
+}
+
+impl Bins {
+	/// Shows whether the blocks of values of the provided layout are kept by the caches.
+	///
+	/// Large values are allocated too rarely to be worth keeping their blocks around, and over-aligned ones
+	/// would take up a bin of their own for each of their alignments.
+	pub(crate) fn fits(layout: Layout) -> bool {
+		layout.size() <= MAX_SIZE && layout.align() <= MAX_ALIGN
+	}
+
+	/// Shows whether there is a free block for a value of the provided layout.
+	pub(crate) fn has(&self, layout: Layout) -> bool {
+		self.bins
+			.iter()
+			.any(|bin| bin.layout == Some(layout) && bin.len != 0)
+	}
+
+	/// Takes a free block for a value of the provided layout, returning the pointer to the memory of the value.
+	pub(crate) fn take(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+		self.bins
+			.iter_mut()
+			.find(|bin| bin.layout == Some(layout))?
+			.pop()
+	}
+
+	/// Keeps the block of a dropped value of the provided layout, whose record has been removed from the heap,
+	/// or returns it to the backend of the heap if enough blocks are kept already.
+	///
+	/// # Safety
+	///
+	/// The block must have been allocated from the heap for a value of the provided layout, and must not be used afterwards.
+	pub(crate) unsafe fn put<B: RawBackend>(
+		&mut self,
+		heap: &mut Heap<B>,
+		ptr: NonNull<u8>,
+		layout: Layout
+	) {
+		let bin = if Self::fits(layout) {
+			self.bin(layout)
+		} else {
+			None
+		};
+
+		match bin {
+			Some(bin) if bin.len < CAPACITY => bin.push(ptr),
+			_ => unsafe { heap.deallocate_block(ptr, layout) }
+		}
+	}
+
+	/// Allocates a batch of free blocks for values of the provided layout from the heap,
+	/// returning whether any of them could be allocated.
+	pub(crate) fn refill<B: RawBackend>(&mut self, heap: &mut Heap<B>, layout: Layout) -> bool {
+		if self.bin(layout).is_none() {
+			// Making room for the layout by returning the blocks of the bin that keeps the fewest of them
+			let bin = self
+				.bins
+				.iter_mut()
+				.min_by_key(|bin| bin.len)
+				.expect("Cache has no bins");
+			bin.clear(heap);
+		}
+
+		let bin = self.bin(layout).expect("Cache has no free bin");
+
+		for _ in 0..BATCH {
+			match heap.allocate_block(layout) {
+				Ok(ptr) => bin.push(ptr),
+				Err(_) => break
+			}
+		}
+
+		bin.len != 0
+	}
+
+	/// Returns all of the free blocks to the backend of the heap.
+	pub(crate) fn trim<B: RawBackend>(&mut self, heap: &mut Heap<B>) {
+		for bin in &mut self.bins {
+			bin.clear(heap);
+		}
+	}
+
+	/// Gets the bin of the provided layout, claiming an empty one for it if there is none.
+	fn bin(&mut self, layout: Layout) -> Option<&mut Bin> {
+		let index = self
+			.bins
+			.iter()
+			.position(|bin| bin.layout == Some(layout))
+			.or_else(|| self.bins.iter().position(|bin| bin.len == 0))?;
+
+		let bin = &mut self.bins[index];
+		bin.layout = Some(layout);
+
+		Some(bin)
+	}
+}
+
+#[derive(Debug)]
+/// The cache of a [`Memory`](crate::Memory), which allocates its values and takes back the memory of the dropped ones
+/// without locking the heap.
+///
+/// A memory belongs to a single thread (it is neither [`Send`] nor [`Sync`]), so its cache is the cache of that thread,
+/// and it needs no synchronization of its own. The values allocated and dropped through the cache are recorded by the heap in batches,
+/// and every lock of the heap through the memory or its mutators (see [`lock`]) records them first, so that the heap is exact
+/// whenever it is looked at.
+///
+/// The cache is only used while the heap does not have to see every allocation right away (see [`Heap::is_cacheable`]).
+pub(crate) struct Cache {
+	/// Indicates whether the values are allocated and dropped through the cache, as of the last lock of the heap
+	enabled: Cell<bool>,
+
+	/// Count of values allocated or dropped since the cache was last settled
+	ops: Cell<usize>,
+
+	/// Free blocks for the values
+	bins: UnsafeCell<Bins>,
+
+	/// Free slots for the values
+	slots: Cell<Option<NonNull<Free>>>,
+
+	/// Count of the free slots
+	free_slots: Cell<usize>,
+
+	/// Records of the values allocated since the cache was last settled, which the heap does not know about yet
+	pending: UnsafeCell<List>,
+
+	/// Records of the dropped values, linked through their `link`, which are yet to be removed from the heap
+	released: Cell<Option<NonNull<Allocation>>>
+}
+
+impl Cache {
+	/// Creates an empty cache for the provided heap.
+	pub(crate) fn new<B: RawBackend>(heap: &Heap<B>) -> Self {
+		Self {
+			enabled: Cell::new(heap.is_cacheable()),
+			ops: Cell::new(0),
+			bins: UnsafeCell::new(Bins::default()),
+			slots: Cell::new(None),
+			free_slots: Cell::new(0),
+			pending: UnsafeCell::new(List::default()),
+			released: Cell::new(None)
+		}
+	}
+
+	/// Shows whether the values are allocated and dropped through the cache.
+	pub(crate) fn is_enabled(&self) -> bool { self.enabled.get() }
+
+	/// Enables or disables the cache, depending on whether the provided heap has to see every allocation right away.
+	///
+	/// The cache must be settled.
+	pub(crate) fn update<B: RawBackend>(&self, heap: &Heap<B>) {
+		self.enabled.set(heap.is_cacheable())
+	}
+
+	/// Allocates the block and the slot of a value of the provided layout, and records the value as pending,
+	/// returning the pointer to its memory along with its slot.
+	///
+	/// Returns [`None`] if the value has to be allocated by the heap instead: if the cache is disabled,
+	/// if it does not keep blocks of the layout, or if the backend is exhausted.
+	pub(crate) fn alloc<B: RawBackend>(
+		&self,
+		heap: &Mutex<Heap<B>>,
+		layout: Layout,
+		type_tag: TypeTag,
+		drop_glue: Option<DropGlue>,
+		scope: Option<usize>,
+		site: &'static Location<'static>
+	) -> Option<(NonNull<u8>, NonNull<Slot>)> {
+		if !self.is_enabled() || !Bins::fits(layout) {
+			return None;
+		}
+
+		if !self.has(layout) {
+			// Taking back the memory of the dropped values, or a batch of blocks from the backend at once
+			let mut heap = lock(heap, Some(self))?;
+
+			if !self.is_enabled() || !self.refill(&mut heap, layout) {
+				return None;
+			}
+		}
+
+		let ptr = unsafe { &mut *self.bins.get() }
+			.take(layout)
+			.expect("Cache has no free block");
+		let slot = self.take_slot().expect("Cache has no free slot");
+
+		let allocation = unsafe { write_record(ptr, layout, Some(type_tag), site) };
+		let record = unsafe { &mut *allocation.as_ptr() };
+		record.drop_glue = drop_glue;
+		record.scope = scope;
+		record.slot = Some(slot);
+
+		unsafe { slot.write(Slot::new(ptr)) }
+		unsafe { (*self.pending.get()).push(allocation) }
+		self.tick(heap);
+
+		Some((ptr, slot))
+	}
+
+	/// Hands the record of a live allocation whose value has been dropped over to the cache,
+	/// which removes it from the heap and reuses its memory once it is settled.
+	///
+	/// # Safety
+	///
+	/// The allocation must have been made by the heap (or the cache) of the memory, and its slot must no longer be referenced by any mutator.
+	pub(crate) unsafe fn release<B: RawBackend>(
+		&self,
+		heap: &Mutex<Heap<B>>,
+		allocation: NonNull<Allocation>
+	) {
+		unsafe { allocation.as_ref() }.set_link(self.released.take());
+		self.released.set(Some(allocation));
+		self.tick(heap);
+	}
+
+	/// Records the pending values in the provided heap, and removes the records of the dropped ones,
+	/// keeping their blocks and slots for the values allocated next.
+	pub(crate) fn settle<B: RawBackend>(&self, heap: &mut Heap<B>) {
+		let mut pending = core::mem::take(unsafe { &mut *self.pending.get() });
+
+		while let Some(allocation) = pending.pop() {
+			heap.budget
+				.charge(unsafe { allocation.as_ref() }.layout.size(), 1);
+			unsafe { heap.track(allocation) };
+		}
+
+		let mut released = self.released.take();
+
+		while let Some(allocation) = released {
+			let (ptr, layout, slot) = {
+				let record = unsafe { allocation.as_ref() };
+				released = record.link();
+
+				(record.ptr, record.layout, record.slot)
+			};
+
+			heap.budget.release(layout.size(), 1);
+			unsafe { heap.forget(ptr) };
+
+			if let Some(slot) = slot {
+				self.put_slot(heap, slot);
+			}
+
+			unsafe { (*self.bins.get()).put(heap, ptr, layout) }
+		}
+
+		self.ops.set(0);
+		self.update(heap);
+	}
+
+	/// Returns all of the free blocks and slots of the cache to the backend of the provided heap.
+	///
+	/// The cache must be settled.
+	pub(crate) fn trim<B: RawBackend>(&self, heap: &mut Heap<B>) {
+		unsafe { &mut *self.bins.get() }.trim(heap);
+
+		while let Some(slot) = self.take_slot() {
+			unsafe { heap.deallocate_slot(slot) }
+		}
+	}
+
+	/// Shows whether there are a free block for a value of the provided layout and a free slot.
+	fn has(&self, layout: Layout) -> bool {
+		self.free_slots.get() != 0 && unsafe { &*self.bins.get() }.has(layout)
+	}
+
+	/// Allocates free blocks for values of the provided layout and free slots from the provided heap,
+	/// returning whether there are both afterwards.
+	///
+	/// The cache is trimmed if the backend is exhausted, so that the value can be allocated by the heap instead.
+	fn refill<B: RawBackend>(&self, heap: &mut Heap<B>, layout: Layout) -> bool {
+		let bins = unsafe { &mut *self.bins.get() };
+
+		if !bins.has(layout) && !bins.refill(heap, layout) {
+			self.trim(heap);
+			return false;
+		}
+
+		if self.free_slots.get() == 0 {
+			for _ in 0..BATCH {
+				let Some(slot) = heap.backend.allocate(Layout::new::<Slot>()) else {
+					break;
+				};
+
+				self.put_slot(heap, slot.cast::<Slot>());
+			}
+		}
+
+		if self.free_slots.get() == 0 {
+			self.trim(heap);
+			return false;
+		}
+
+		true
+	}
+
+	/// Takes a free slot.
+	fn take_slot(&self) -> Option<NonNull<Slot>> {
+		let slot = self.slots.get()?;
+		self.slots.set(unsafe { slot.as_ref() }.next);
+		self.free_slots.set(self.free_slots.get() - 1);
+
+		Some(slot.cast::<Slot>())
+	}
+
+	/// Keeps the provided slot, which is no longer referenced, or returns it to the backend of the provided heap
+	/// if enough slots are kept already.
+	fn put_slot<B: RawBackend>(&self, heap: &mut Heap<B>, slot: NonNull<Slot>) {
+		if self.free_slots.get() >= CAPACITY {
+			unsafe { heap.deallocate_slot(slot) }
+			return;
+		}
+
+		let slot = slot.cast::<Free>();
+		unsafe {
+			slot.write(Free {
+				next: self.slots.get()
+			})
+		}
+
+		self.slots.set(Some(slot));
+		self.free_slots.set(self.free_slots.get() + 1);
+	}
+
+	/// Counts a value allocated or dropped through the cache, settling it once there is a batch of them.
+	fn tick<B: RawBackend>(&self, heap: &Mutex<Heap<B>>) {
+		let ops = self.ops.get() + 1;
+		self.ops.set(ops);
+
+		if ops >= BATCH {
+			// Settling while the heap is locked
+			drop(lock(heap, Some(self)));
+		}
+	}
+}
+
+/// Acquires the lock of the provided heap, settling the provided cache of its memory first (see [`Cache`]).
+///
+/// Returns [`None`] if the lock is poisoned.
+pub(crate) fn lock<'heap, B: RawBackend>(
+	heap: &'heap Mutex<Heap<B>>,
+	cache: Option<&Cache>
+) -> Option<MutexGuard<'heap, Heap<B>>> {
+	let mut heap = heap.lock().ok()?;
+
+	if let Some(cache) = cache {
+		cache.settle(&mut heap);
+	}
+
+	Some(heap)
+}
@@ -0,0 +1,202 @@
+use core::ptr::NonNull;
+
+use crate::heap::{block_layout, header, Allocation};
+use crate::{AllocationInfo, Heap, RawBackend};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The results of compacting a heap with [`Memory::compact`](crate::Memory::compact).
+pub struct CompactionReport {
+	/// Count of values that were moved
+	pub moved: usize,
+
+	/// Count of bytes that were moved
+	pub moved_bytes: usize,
+
+	/// Count of values that could have been moved, but were pinned or borrowed
+	/// (see [`HeapMutator::is_pinned`](crate::HeapMutator::is_pinned))
+	pub skipped: usize,
+
+	/// Count of bytes by which the address range spanned by the live allocations shrank
+	pub reclaimed: usize
+}
+
+impl<B: RawBackend> Heap<B> {
+	/// Moves the values of the mutators to lower addresses where the backend has free memory,
+	/// and updates the mutators to point to the new addresses.
+	///
+	/// Only the allocations that are referenced by mutators are moved, since raw pointers (e.g., from [`Heap::alloc`])
+	/// cannot be updated. Pinned values are only moved if `ignore_pins` is set.
+	pub(crate) fn compact(&mut self, ignore_pins: bool) -> CompactionReport {
+		let mut report = CompactionReport::default();
+		let span_before = self.span();
+
+		// Linking the candidates through their records, since the memory of the heap might not fit anything else
+		let mut candidates = None;
+
+		for allocation in self.records.iter() {
+			let record = unsafe { &mut *allocation.as_ptr() };
+
+			if record.layout.size() == 0 || !record.is_referenced() {
+				continue;
+			}
+
+			match record.slot() {
+				Some(slot) if slot.is_fixed(ignore_pins) => report.skipped += 1,
+				_ => {
+					record.set_link(candidates);
+					candidates = Some(allocation);
+				}
+			}
+		}
+
+		// Moving the values from the lowest address up, so that the freed memory can be reused by the next ones
+		let mut candidates = sort(candidates);
+
+		while let Some(allocation) = candidates {
+			let (ptr, layout) = {
+				let record = unsafe { allocation.as_ref() };
+				candidates = record.link();
+
+				(record.ptr, record.layout)
+			};
+
+			// The record is moved along with the value
+			let Some((block, offset)) = block_layout(layout) else {
+				continue;
+			};
+			let Some(new_block) = self.backend.allocate(block) else {
+				continue;
+			};
+			let old_block = unsafe { ptr.sub(offset) };
+
+			// The value is only moved if that makes the heap denser
+			if new_block >= old_block {
+				unsafe { self.backend.deallocate(new_block, block) };
+				continue;
+			}
+
+			unsafe {
+				core::ptr::copy_nonoverlapping(
+					old_block.as_ptr(),
+					new_block.as_ptr(),
+					block.size()
+				);
+				self.backend.deallocate(old_block, block);
+			}
+
+			let new_ptr = unsafe { new_block.add(offset) };
+			let allocation = header(new_ptr);
+
+			let (old, new) = {
+				let record = unsafe { &mut *allocation.as_ptr() };
+				let old = AllocationInfo::of(record);
+				record.ptr = new_ptr;
+
+				(old, AllocationInfo::of(record))
+			};
+
+			self.records.relink(allocation);
+
+			if let Some(slot) = unsafe { allocation.as_ref() }.slot() {
+				slot.relocate(new_ptr);
+
+				if ignore_pins {
+					slot.unpin();
+				}
+			}
+
+			self.notify(|observer| observer.on_realloc(&old, &new));
+
+			report.moved += 1;
+			report.moved_bytes += layout.size();
+		}
+
+		report.reclaimed = span_before.saturating_sub(self.span());
+		report
+	}
+
+	/// Gets the count of bytes between the start of the lowest block and the end of the highest one.
+	fn span(&self) -> usize {
+		let ranges = self
+			.records()
+			.filter(|a| a.layout.size() != 0)
+			.filter_map(|a| {
+				let (_, offset) = block_layout(a.layout)?;
+				let start = a.ptr.as_ptr() as usize;
+
+				Some((start - offset, start + a.layout.size()))
+			});
+
+		let (start, end) = ranges.fold((usize::MAX, 0), |(start, end), (from, to)| {
+			(core::cmp::min(start, from), core::cmp::max(end, to))
+		});
+
+		end.saturating_sub(start)
+	}
+}
+
+/// Sorts the records linked through [`Allocation::link`] by the addresses of their allocations, with a merge sort.
+fn sort(list: Option<NonNull<Allocation>>) -> Option<NonNull<Allocation>> {
+	let head = list?;
+
+	// Finding the middle of the list, with a cursor that moves twice as fast as the other one
+	let mut middle = head;
+	let mut end = link(head);
+
+	while let Some(next) = end.and_then(link) {
+		middle = link(middle)?;
+		end = link(next);
+	}
+
+	let Some(second) = link(middle) else {
+		return Some(head);
+	};
+	unsafe { middle.as_ref() }.set_link(None);
+
+	merge(sort(Some(head)), sort(Some(second)))
+}
+
+/// Merges two sorted lists of records linked through [`Allocation::link`].
+fn merge(
+	mut left: Option<NonNull<Allocation>>,
+	mut right: Option<NonNull<Allocation>>
+) -> Option<NonNull<Allocation>> {
+	let mut head = None;
+	let mut tail: Option<NonNull<Allocation>> = None;
+
+	loop {
+		let next = match (left, right) {
+			(Some(first), Some(second)) => {
+				if address(first) <= address(second) {
+					left = link(first);
+					first
+				} else {
+					right = link(second);
+					second
+				}
+			}
+			(Some(rest), None) | (None, Some(rest)) => {
+				// The rest of the list is already sorted
+				left = None;
+				right = None;
+				rest
+			}
+			(None, None) => return head
+		};
+
+		match tail {
+			Some(tail) => unsafe { tail.as_ref() }.set_link(Some(next)),
+			None => head = Some(next)
+		}
+
+		tail = Some(next);
+	}
+}
+
+/// Gets the next record of a list linked through [`Allocation::link`].
+fn link(allocation: NonNull<Allocation>) -> Option<NonNull<Allocation>> {
+	unsafe { allocation.as_ref() }.link()
+}
+
+/// Gets the address of the allocation of a record.
+fn address(allocation: NonNull<Allocation>) -> NonNull<u8> { unsafe { allocation.as_ref() }.ptr }
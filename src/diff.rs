@@ -0,0 +1,172 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::{AllocationSite, DumpEntry, HeapDump};
+
+#[derive(Debug, Clone)]
+/// The differences between two [`HeapDump`]s of the same [`Memory`](crate::Memory), taken at different checkpoints.
+///
+/// Allocations are matched by their identifiers (see [`DumpEntry::id`]), so an allocation that was freed
+/// and replaced by another one at the same address is reported as both freed and added.
+pub struct HeapDiff<'dump> {
+	/// Allocations that are only present in the later dump
+	added: Vec<&'dump DumpEntry>,
+
+	/// Allocations that are only present in the earlier dump
+	freed: Vec<&'dump DumpEntry>,
+
+	/// Allocations whose contents or layouts differ between the dumps, as pairs of the earlier and the later entry
+	changed: Vec<(&'dump DumpEntry, &'dump DumpEntry)>
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// The differences of a group of allocations (e.g., of the same type) within a [`HeapDiff`].
+pub struct DiffGroup {
+	/// Count of added allocations
+	pub added: usize,
+
+	/// Count of freed allocations
+	pub freed: usize,
+
+	/// Count of changed allocations
+	pub changed: usize,
+
+	/// Change of the count of allocated bytes
+	pub byte_delta: isize
+}
+
+impl<'dump> HeapDiff<'dump> {
+	/// Compares the dump taken at an earlier checkpoint (`before`) with the dump taken at a later one (`after`).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{HeapDiff, Memory};
+	/// let memory = Memory::new();
+	/// let mut cache = vec![];
+	///
+	/// let before = memory.dump();
+	///
+	/// for i in 0..3u64 {
+	///     let _temporary = memory.alloc(i);
+	///     cache.push(memory.alloc([i; 4])); // Leaking on purpose
+	/// }
+	///
+	/// let after = memory.dump();
+	/// let diff = HeapDiff::between(&before, &after);
+	///
+	/// assert_eq!(diff.added().len(), 3);
+	/// assert_eq!(diff.byte_delta(), 96);
+	///
+	/// // Every leaked allocation was made at the same place
+	/// let (site, group) = diff.by_site()[0];
+	/// assert_eq!(site.line, line!() - 11);
+	/// assert_eq!(group.added, 3);
+	///
+	/// let (type_name, group) = diff.by_type()[0];
+	/// assert_eq!(type_name, Some("[u64; 4]"));
+	/// assert_eq!(group.byte_delta, 96);
+	/// ```
+	pub fn between(before: &'dump HeapDump, after: &'dump HeapDump) -> Self {
+		let earlier: HashMap<_, _> = before.entries().iter().map(|e| (e.id(), e)).collect();
+		let later: HashMap<_, _> = after.entries().iter().map(|e| (e.id(), e)).collect();
+
+		let mut added = vec![];
+		let mut changed = vec![];
+
+		for entry in after.entries() {
+			match earlier.get(&entry.id()) {
+				None => added.push(entry),
+				Some(previous) => {
+					if previous.layout() != entry.layout() || previous.bytes() != entry.bytes() {
+						changed.push((*previous, entry));
+					}
+				}
+			}
+		}
+
+		let freed = before
+			.entries()
+			.iter()
+			.filter(|e| !later.contains_key(&e.id()))
+			.collect();
+
+		Self {
+			added,
+			freed,
+			changed
+		}
+	}
+
+	/// Gets the allocations that are only present in the later dump.
+	pub fn added(&self) -> &[&'dump DumpEntry] { &self.added }
+
+	/// Gets the allocations that are only present in the earlier dump.
+	pub fn freed(&self) -> &[&'dump DumpEntry] { &self.freed }
+
+	/// Gets the allocations whose contents or layouts differ between the dumps, as pairs of the earlier and the later entry.
+	pub fn changed(&self) -> &[(&'dump DumpEntry, &'dump DumpEntry)] { &self.changed }
+
+	/// Gets the change of the count of allocated bytes between the dumps.
+	pub fn byte_delta(&self) -> isize {
+		let added: usize = self.added.iter().map(|e| e.layout().size()).sum();
+		let freed: usize = self.freed.iter().map(|e| e.layout().size()).sum();
+		let resized: isize = self.changed.iter().map(|(a, b)| size_delta(a, b)).sum();
+
+		added as isize - freed as isize + resized
+	}
+
+	/// Groups the differences by the types of the values, starting with the largest growth.
+	pub fn by_type(&self) -> Vec<(Option<&'dump str>, DiffGroup)> {
+		self.group_by(DumpEntry::type_name)
+	}
+
+	/// Groups the differences by the allocation sites, starting with the largest growth.
+	///
+	/// Changed allocations are grouped by their sites within the later dump.
+	pub fn by_site(&self) -> Vec<(&'dump AllocationSite, DiffGroup)> {
+		self.group_by(DumpEntry::site)
+	}
+
+	/// Groups the differences by the provided key of the entries.
+	fn group_by<K: Eq + Hash + Ord>(
+		&self,
+		key: impl Fn(&'dump DumpEntry) -> K
+	) -> Vec<(K, DiffGroup)> {
+		let mut groups: HashMap<K, DiffGroup> = HashMap::new();
+
+		for entry in &self.added {
+			let group = groups.entry(key(entry)).or_default();
+			group.added += 1;
+			group.byte_delta += entry.layout().size() as isize;
+		}
+
+		for entry in &self.freed {
+			let group = groups.entry(key(entry)).or_default();
+			group.freed += 1;
+			group.byte_delta -= entry.layout().size() as isize;
+		}
+
+		for (previous, entry) in &self.changed {
+			let group = groups.entry(key(entry)).or_default();
+			group.changed += 1;
+			group.byte_delta += size_delta(previous, entry);
+		}
+
+		let mut groups: Vec<_> = groups.into_iter().collect();
+
+		// Sorting by the keys as well, so that the order is deterministic
+		groups.sort_by(|(a_key, a), (b_key, b)| {
+			b.byte_delta
+				.cmp(&a.byte_delta)
+				.then_with(|| a_key.cmp(b_key))
+		});
+
+		groups
+	}
+}
+
+/// Gets the change of the size of an allocation between two entries.
+fn size_delta(previous: &DumpEntry, entry: &DumpEntry) -> isize {
+	entry.layout().size() as isize - previous.layout().size() as isize
+}
@@ -0,0 +1,295 @@
+use std::alloc::Layout;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, Read, Write};
+
+use crate::heap::Heap;
+use crate::{DumpError, RawBackend};
+
+/// Bytes that every heap dump starts with
+const MAGIC: &[u8; 8] = b"HALLOCDM";
+
+#[derive(Debug, Clone)]
+/// A structured copy of all the allocations of a [`Memory`](crate::Memory), taken with [`Memory::dump`](crate::Memory::dump).
+///
+/// Dumps are meant for offline inspection (e.g., with the `halloc-inspect` binary): they can be written to
+/// and read from a versioned binary format, in which all of the integers are stored in little-endian byte order.
+pub struct HeapDump {
+	/// Version of the format that the dump was read from, or [`HeapDump::VERSION`] for captured dumps
+	version: u32,
+
+	/// Copies of the dumped allocations
+	entries: Vec<DumpEntry>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A copy of a single allocation within a [`HeapDump`].
+pub struct DumpEntry {
+	/// Unique identifier of the allocation
+	id: u64,
+
+	/// Address of the allocation at the time of the dump
+	address: u64,
+
+	/// Layout of the allocation
+	layout: Layout,
+
+	/// Name of the value's type, if it is known
+	type_name: Option<String>,
+
+	/// Indicates whether the value is plain data
+	plain: bool,
+
+	/// Location of the code that requested the allocation
+	site: AllocationSite,
+
+	/// Copy of the allocated bytes
+	bytes: Vec<u8>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// The location of the code that requested an allocation.
+pub struct AllocationSite {
+	/// Path of the source file
+	pub file: String,
+
+	/// Line within the source file
+	pub line: u32,
+
+	/// Column within the line
+	pub column: u32
+}
+
+impl HeapDump {
+	/// Version of the format written by [`write_to`](HeapDump::write_to).
+	pub const VERSION: u32 = 1;
+
+	/// Copies all of the live allocations of the provided heap.
+	pub(crate) fn capture<B: RawBackend>(heap: &Heap<B>) -> Self {
+		let entries = heap
+			.records()
+			.map(|allocation| DumpEntry {
+				id: allocation.id,
+				address: allocation.ptr.as_ptr() as u64,
+				layout: allocation.layout,
+				type_name: allocation.type_tag.map(|tag| tag.name.to_owned()),
+				plain: allocation.type_tag.is_some_and(|tag| tag.plain),
+				site: AllocationSite {
+					file: allocation.site.file().to_owned(),
+					line: allocation.site.line(),
+					column: allocation.site.column()
+				},
+				bytes: allocation.contents()
+			})
+			.collect();
+
+		Self {
+			version: Self::VERSION,
+			entries
+		}
+	}
+
+	/// Writes the dump to the provided writer.
+	///
+	/// The dump is always written with the current [`VERSION`](HeapDump::VERSION) of the format.
+	pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+		writer.write_all(MAGIC)?;
+		writer.write_all(&Self::VERSION.to_le_bytes())?;
+		writer.write_all(&(self.entries.len() as u64).to_le_bytes())?;
+
+		for entry in &self.entries {
+			writer.write_all(&entry.id.to_le_bytes())?;
+			writer.write_all(&entry.address.to_le_bytes())?;
+			writer.write_all(&(entry.layout.size() as u64).to_le_bytes())?;
+			writer.write_all(&(entry.layout.align() as u64).to_le_bytes())?;
+
+			match &entry.type_name {
+				Some(name) => {
+					writer.write_all(&[1])?;
+					write_string(&mut writer, name)?;
+				}
+				None => writer.write_all(&[0])?
+			}
+
+			writer.write_all(&[entry.plain as u8])?;
+
+			write_string(&mut writer, &entry.site.file)?;
+			writer.write_all(&entry.site.line.to_le_bytes())?;
+			writer.write_all(&entry.site.column.to_le_bytes())?;
+
+			writer.write_all(&entry.bytes)?;
+		}
+
+		Ok(())
+	}
+
+	/// Reads a dump written by [`write_to`](HeapDump::write_to) (or [`Memory::dump_to`](crate::Memory::dump_to)).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{HeapDump, Memory};
+	/// let memory = Memory::new();
+	/// let _name = memory.alloc_str("player");
+	///
+	/// let mut file = vec![];
+	/// memory.dump_to(&mut file).unwrap();
+	///
+	/// let dump = HeapDump::read_from(file.as_slice()).unwrap();
+	/// assert_eq!(dump.entries()[0].bytes(), b"player");
+	/// assert_eq!(dump.entries()[0].type_name(), Some("str"));
+	/// assert_eq!(dump.version(), HeapDump::VERSION);
+	///
+	/// // Anything else is rejected
+	/// assert!(HeapDump::read_from(&b"not a dump"[..]).is_err());
+	/// ```
+	pub fn read_from(mut reader: impl Read) -> Result<Self, DumpError> {
+		let mut magic = [0; 8];
+		reader.read_exact(&mut magic)?;
+
+		if &magic != MAGIC {
+			return Err(DumpError::InvalidFormat);
+		}
+
+		let version = read_u32(&mut reader)?;
+
+		if version != Self::VERSION {
+			return Err(DumpError::UnsupportedVersion { version });
+		}
+
+		let count = read_u64(&mut reader)?;
+		let mut entries = vec![];
+
+		for _ in 0..count {
+			let id = read_u64(&mut reader)?;
+			let address = read_u64(&mut reader)?;
+			let size = read_usize(&mut reader)?;
+			let align = read_usize(&mut reader)?;
+			let layout =
+				Layout::from_size_align(size, align).map_err(|_| DumpError::InvalidFormat)?;
+
+			let type_name = match read_u8(&mut reader)? {
+				0 => None,
+				1 => Some(read_string(&mut reader)?),
+				_ => return Err(DumpError::InvalidFormat)
+			};
+
+			let plain = match read_u8(&mut reader)? {
+				0 => false,
+				1 => true,
+				_ => return Err(DumpError::InvalidFormat)
+			};
+
+			let site = AllocationSite {
+				file: read_string(&mut reader)?,
+				line: read_u32(&mut reader)?,
+				column: read_u32(&mut reader)?
+			};
+
+			let bytes = read_bytes(&mut reader, size)?;
+
+			entries.push(DumpEntry {
+				id,
+				address,
+				layout,
+				type_name,
+				plain,
+				site,
+				bytes
+			});
+		}
+
+		Ok(Self { version, entries })
+	}
+
+	/// Gets the version of the format that the dump was read from (see [`read_from`](HeapDump::read_from)),
+	/// which is the current [`VERSION`](HeapDump::VERSION) for the dumps captured by [`Memory::dump`](crate::Memory::dump).
+	pub fn version(&self) -> u32 { self.version }
+
+	/// Gets the dumped allocations.
+	pub fn entries(&self) -> &[DumpEntry] { &self.entries }
+
+	/// Gets the count of bytes of all the dumped allocations.
+	pub fn size(&self) -> usize { self.entries.iter().map(|e| e.bytes.len()).sum() }
+}
+
+impl DumpEntry {
+	/// Gets the unique identifier of the allocation, which stays the same for as long as the allocation is live
+	/// (unlike its address, which might be reused by later allocations).
+	pub fn id(&self) -> u64 { self.id }
+
+	/// Gets the address of the allocation at the time of the dump.
+	pub fn address(&self) -> u64 { self.address }
+
+	/// Gets the layout of the allocation.
+	pub fn layout(&self) -> Layout { self.layout }
+
+	/// Gets the name of the value's type, if it is known.
+	pub fn type_name(&self) -> Option<&str> { self.type_name.as_deref() }
+
+	/// Shows whether the value is plain data (see [`PlainData`](crate::PlainData)).
+	pub fn is_plain(&self) -> bool { self.plain }
+
+	/// Gets the location of the code that requested the allocation.
+	pub fn site(&self) -> &AllocationSite { &self.site }
+
+	/// Gets the dumped bytes of the allocation.
+	///
+	/// The bytes of values that are not plain data (see [`is_plain`](DumpEntry::is_plain)) are all zeros,
+	/// since they may be uninitialized.
+	pub fn bytes(&self) -> &[u8] { &self.bytes }
+}
+
+impl Display for AllocationSite {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:{}:{}", self.file, self.line, self.column)
+	}
+}
+
+/// Writes a length-prefixed UTF-8 string.
+fn write_string(writer: &mut impl Write, string: &str) -> io::Result<()> {
+	writer.write_all(&(string.len() as u32).to_le_bytes())?;
+	writer.write_all(string.as_bytes())
+}
+
+/// Reads exactly `len` bytes, without trusting `len` enough to allocate all of them upfront.
+fn read_bytes(reader: &mut impl Read, len: usize) -> Result<Vec<u8>, DumpError> {
+	let mut bytes = vec![];
+	reader.take(len as u64).read_to_end(&mut bytes)?;
+
+	if bytes.len() != len {
+		return Err(DumpError::InvalidFormat);
+	}
+
+	Ok(bytes)
+}
+
+/// Reads a length-prefixed UTF-8 string.
+fn read_string(reader: &mut impl Read) -> Result<String, DumpError> {
+	let len = read_u32(reader)? as usize;
+	String::from_utf8(read_bytes(reader, len)?).map_err(|_| DumpError::InvalidFormat)
+}
+
+fn read_u8(reader: &mut impl Read) -> Result<u8, DumpError> {
+	let mut bytes = [0; 1];
+	reader.read_exact(&mut bytes)?;
+
+	Ok(bytes[0])
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32, DumpError> {
+	let mut bytes = [0; 4];
+	reader.read_exact(&mut bytes)?;
+
+	Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64, DumpError> {
+	let mut bytes = [0; 8];
+	reader.read_exact(&mut bytes)?;
+
+	Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_usize(reader: &mut impl Read) -> Result<usize, DumpError> {
+	usize::try_from(read_u64(reader)?).map_err(|_| DumpError::InvalidFormat)
+}
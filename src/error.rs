@@ -0,0 +1,135 @@
+use core::fmt::{self, Display, Formatter};
+#[cfg(feature = "std")]
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An error returned by the fallible allocation methods, such as [`Memory::try_alloc`](crate::Memory::try_alloc).
+pub enum AllocError {
+	/// The allocation would exceed the byte limit of the memory (see [`Memory::with_limit`](crate::Memory::with_limit))
+	LimitExceeded {
+		/// Count of bytes that were requested
+		requested: usize,
+
+		/// Count of bytes that were still available within the limit
+		available: usize
+	},
+
+	/// The [`RawBackend`](crate::RawBackend) of the heap could not provide the memory
+	OutOfMemory {
+		/// Count of bytes that were requested
+		requested: usize
+	}
+}
+
+impl Display for AllocError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::LimitExceeded {
+				requested,
+				available
+			} => write!(
+				f,
+				"Memory limit exceeded: {requested} bytes requested, but only {available} bytes available"
+			),
+			Self::OutOfMemory { requested } => {
+				write!(f, "Out of memory: {requested} bytes requested")
+			}
+		}
+	}
+}
+
+impl core::error::Error for AllocError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// An error returned by [`Memory::restore`](crate::Memory::restore).
+pub enum RestoreError {
+	/// The allocation of a snapshot entry is no longer live, or its layout or type has changed
+	Missing {
+		/// Address of the allocation
+		address: usize
+	},
+
+	/// The value of a snapshot entry is not marked as plain data (see [`PlainData`](crate::PlainData))
+	NotPlainData {
+		/// Name of the value's type, if it is known
+		type_name: Option<&'static str>
+	},
+
+	/// The value of a snapshot entry may still be referenced, since it is being accessed
+	/// or a reference to it has been handed out (see [`HeapMutator::is_pinned`](crate::HeapMutator::is_pinned))
+	Borrowed {
+		/// Address of the allocation
+		address: usize
+	}
+}
+
+impl Display for RestoreError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Missing { address } => {
+				write!(
+					f,
+					"Allocation at {address:#x} is no longer live or has changed"
+				)
+			}
+			Self::NotPlainData { type_name } => write!(
+				f,
+				"Value of type `{}` is not plain data and cannot be restored",
+				type_name.unwrap_or("unknown")
+			),
+			Self::Borrowed { address } => {
+				write!(
+					f,
+					"Value at {address:#x} may still be referenced and cannot be overwritten"
+				)
+			}
+		}
+	}
+}
+
+impl core::error::Error for RestoreError {}
+
+#[cfg(feature = "std")]
+#[derive(Debug)]
+/// An error returned by [`HeapDump::read_from`](crate::HeapDump::read_from) and [`Trace::read_from`](crate::Trace::read_from).
+pub enum DumpError {
+	/// Reading the data failed
+	Io(io::Error),
+
+	/// The data is not a valid heap dump (or trace)
+	InvalidFormat,
+
+	/// The data was written with an unsupported version of the format (see [`HeapDump::VERSION`](crate::HeapDump::VERSION))
+	UnsupportedVersion {
+		/// Version of the format that the data was written with
+		version: u32
+	}
+}
+
+#[cfg(feature = "std")]
+impl Display for DumpError {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Io(error) => write!(f, "Failed to read the data: {error}"),
+			Self::InvalidFormat => write!(f, "Data is not in a valid format"),
+			Self::UnsupportedVersion { version } => {
+				write!(f, "Format version {version} is not supported")
+			}
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl core::error::Error for DumpError {
+	fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+		match self {
+			Self::Io(error) => Some(error),
+			_ => None
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for DumpError {
+	fn from(error: io::Error) -> Self { Self::Io(error) }
+}
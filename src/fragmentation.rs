@@ -0,0 +1,126 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A chunk of memory that a [`RawBackend`](crate::RawBackend) hands out allocations from, see [`RawBackend::chunks`](crate::RawBackend::chunks).
+pub struct Chunk {
+	/// Address of the first byte of the chunk
+	pub start: usize,
+
+	/// Count of bytes within the chunk
+	pub size: usize,
+
+	/// Free blocks of the chunk, as byte ranges relative to its start, sorted and not overlapping
+	pub free: Vec<Range<usize>>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// The utilization of the chunks that a heap allocates from, gathered with [`Memory::fragmentation`](crate::Memory::fragmentation).
+///
+/// Bytes are counted as the backend hands them out, so the bytes in use include the padding of the blocks,
+/// unlike [`Memory::size`](crate::Memory::size).
+pub struct Fragmentation {
+	/// Count of bytes within all of the chunks
+	pub reserved: usize,
+
+	/// Count of bytes that are handed out
+	pub in_use: usize,
+
+	/// Size of the largest free block, which is the largest allocation that is guaranteed to succeed
+	pub largest_free: usize,
+
+	/// Count of free blocks
+	pub free_blocks: usize,
+
+	/// Fraction of the free bytes that are not part of the largest free block
+	/// (`0.0` means that all of the free bytes can be allocated at once)
+	pub ratio: f64,
+
+	/// Chunks that the statistics were gathered from
+	chunks: Vec<Chunk>
+}
+
+impl Fragmentation {
+	/// Gathers the statistics of the provided chunks.
+	pub(crate) fn of(chunks: Vec<Chunk>) -> Self {
+		let reserved = chunks.iter().map(|c| c.size).sum();
+		let blocks = chunks.iter().flat_map(|c| &c.free).map(Range::len);
+
+		let (free, largest_free, free_blocks) = blocks
+			.fold((0, 0, 0), |(free, largest, count), len| {
+				(free + len, core::cmp::max(largest, len), count + 1)
+			});
+
+		let ratio = match free {
+			0 => 0.0,
+			_ => (free - largest_free) as f64 / free as f64
+		};
+
+		Self {
+			reserved,
+			in_use: reserved - free,
+			largest_free,
+			free_blocks,
+			ratio,
+			chunks
+		}
+	}
+
+	/// Gets the chunks that the statistics were gathered from.
+	pub fn chunks(&self) -> &[Chunk] { &self.chunks }
+
+	/// Renders a map of each chunk on its own line, made of `width` cells.
+	///
+	/// Cells are drawn as `#` if all of their bytes are in use, as `.` if all of them are free, and as `+` otherwise.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// # #[repr(align(16))]
+	/// # struct Aligned([u8; 2048]);
+	/// let buffer: &'static mut [u8] = &mut Box::leak(Box::new(Aligned([0; 2048]))).0;
+	/// let memory = Memory::from_static_buffer(buffer);
+	///
+	/// let _first = memory.alloc([0u8; 256]);
+	/// let second = memory.alloc([0u8; 256]);
+	/// let _third = memory.alloc([0u8; 64]);
+	/// drop(second);
+	///
+	/// // The bookkeeping of the values is stored in the buffer along with them
+	/// let fragmentation = memory.fragmentation().unwrap();
+	/// assert!(fragmentation.map(8).ends_with(" [#+.++...] 688/2048 bytes\n"));
+	/// ```
+	pub fn map(&self, width: usize) -> String {
+		let mut map = String::new();
+
+		for chunk in &self.chunks {
+			let free: usize = chunk.free.iter().map(Range::len).sum();
+			let _ = write!(map, "{:#x} [", chunk.start);
+
+			for cell in 0..width {
+				let cell = chunk.size * cell / width..chunk.size * (cell + 1) / width;
+				let free = chunk
+					.free
+					.iter()
+					.map(|block| {
+						core::cmp::min(block.end, cell.end)
+							.saturating_sub(core::cmp::max(block.start, cell.start))
+					})
+					.sum::<usize>();
+
+				map.push(match free {
+					0 => '#',
+					free if free == cell.len() => '.',
+					_ => '+'
+				});
+			}
+
+			let _ = writeln!(map, "] {}/{} bytes", chunk.size - free, chunk.size);
+		}
+
+		map
+	}
+}
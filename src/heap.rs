@@ -1,77 +1,1520 @@
-use std::alloc::Layout;
-use std::ptr::NonNull;
-use std::sync::{Arc, Mutex};
+#[cfg(feature = "alloc")]
+use alloc::borrow::ToOwned;
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeSet;
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::any::TypeId;
+use core::mem::{size_of, ManuallyDrop, MaybeUninit};
+use core::panic::Location;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
 
-use crate::Allocatable;
+use crate::budget::HeapBudget;
+use crate::cache::{self, Cache};
+#[cfg(feature = "alloc")]
+use crate::observer::Observer;
+#[cfg(feature = "profiling")]
+use crate::profile::Profile;
+use crate::sync::{Mutex, MutexGuard};
+#[cfg(feature = "alloc")]
+use crate::transaction::Journal;
+use crate::{
+	AllocError, Allocatable, AllocationInfo, DefaultBackend, HeapObserver, HeapStats, Memory,
+	PlainData, RawBackend
+};
+#[cfg(feature = "alloc")]
+use crate::{Fragmentation, SystemBackend};
+
+#[derive(Debug, Clone, Copy)]
+/// A type-erased destructor of the value stored within an allocation.
+pub(crate) struct DropGlue {
+	/// Function that drops the provided count of values in place
+	drop: unsafe fn(*mut u8, usize),
+
+	/// Count of the stored values
+	len: usize
+}
+
+impl DropGlue {
+	/// Gets the destructor of a single value of type `T`, if it needs to be dropped at all.
+	pub(crate) fn of<T>() -> Option<Self> { Self::of_slice::<T>(1) }
+
+	/// Gets the destructor of a slice of `len` values of type `T`, if it needs to be dropped at all.
+	pub(crate) fn of_slice<T>(len: usize) -> Option<Self> {
+		core::mem::needs_drop::<T>().then_some(Self {
+			drop: drop_slice::<T>,
+			len
+		})
+	}
+
+	/// Drops the value stored at the provided pointer.
+	///
+	/// # Safety
+	///
+	/// The pointer must point to an initialized value of the type that the destructor was created for.
+	pub(crate) unsafe fn drop_value(&self, ptr: NonNull<u8>) {
+		unsafe { (self.drop)(ptr.as_ptr(), self.len) }
+	}
+}
+
+/// Drops `len` values of type `T` in place.
+unsafe fn drop_slice<T>(ptr: *mut u8, len: usize) {
+	unsafe { core::ptr::slice_from_raw_parts_mut(ptr.cast::<T>(), len).drop_in_place() }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The type of the value stored within an allocation.
+pub(crate) struct TypeTag {
+	/// Identifier of the type
+	pub(crate) id: TypeId,
+
+	/// Name of the type, used for diagnostics
+	pub(crate) name: &'static str,
+
+	/// Indicates whether the type is marked as [`PlainData`]
+	pub(crate) plain: bool
+}
+
+impl TypeTag {
+	/// Gets the tag of the type `T`.
+	pub(crate) fn of<T: ?Sized + 'static>() -> Self {
+		Self {
+			id: TypeId::of::<T>(),
+			name: core::any::type_name::<T>(),
+			plain: false
+		}
+	}
+
+	/// Gets the tag of the plain data type `T`.
+	pub(crate) fn plain<T: PlainData>() -> Self {
+		Self {
+			plain: true,
+			..Self::of::<T>()
+		}
+	}
+}
+
+#[derive(Debug)]
+/// The state of an allocation that is shared between the [`Heap`] and the mutators pointing to it.
+///
+/// Mutators resolve the address of their value through the slot, so that the heap can move the value
+/// (see [`Memory::compact`]) without them noticing.
+///
+/// Slots are allocated from the backend of the heap. They are deallocated once neither the record of their allocation
+/// nor any mutator references them anymore.
+pub(crate) struct Slot {
+	/// Current address of the value
+	address: AtomicPtr<u8>,
+
+	/// Count of the mutators (and other handles) referencing the slot, besides the record of the allocation
+	references: AtomicUsize,
+
+	/// Count of the accesses to the value that are in progress (see [`HeapMutator::with`]), which prevent it from being moved
+	borrows: AtomicUsize,
+
+	/// Indicates whether a reference to the value has been handed out, so that it can no longer be moved safely
+	pinned: AtomicBool,
+
+	/// Indicates whether the allocation has been invalidated (e.g., by [`Memory::reset`](crate::Memory::reset))
+	invalidated: AtomicBool,
+
+	#[cfg(feature = "alloc")]
+	/// Indicates whether the mutations of the value are watched by the active transaction
+	watched: AtomicBool,
+
+	#[cfg(feature = "alloc")]
+	/// Indicates whether the value has yet to be recorded in the undo journal of the active transaction before it is mutated in place
+	journaled: AtomicBool,
+
+	/// Indicates whether the heap has been dropped while the allocation was still referenced, leaking it
+	orphaned: AtomicBool,
+
+	/// Indicates whether the allocation has been deallocated by the heap, so that only its mutators reference the slot
+	released: AtomicBool
+}
+
+impl Slot {
+	/// Creates the state of an allocation at the provided address.
+	pub(crate) fn new(address: NonNull<u8>) -> Self {
+		Self {
+			address: AtomicPtr::new(address.as_ptr()),
+			references: AtomicUsize::new(0),
+			borrows: AtomicUsize::new(0),
+			pinned: AtomicBool::new(false),
+			invalidated: AtomicBool::new(false),
+			#[cfg(feature = "alloc")]
+			watched: AtomicBool::new(false),
+			#[cfg(feature = "alloc")]
+			journaled: AtomicBool::new(false),
+			orphaned: AtomicBool::new(false),
+			released: AtomicBool::new(false)
+		}
+	}
+
+	/// Gets the current address of the value.
+	pub(crate) fn address(&self) -> NonNull<u8> {
+		unsafe { NonNull::new_unchecked(self.address.load(Ordering::Acquire)) }
+	}
+
+	/// Updates the address of the value after it has been moved.
+	pub(crate) fn relocate(&self, address: NonNull<u8>) {
+		self.address.store(address.as_ptr(), Ordering::Release)
+	}
+
+	/// Adds a reference to the slot, which has to be removed with [`remove_reference`](Slot::remove_reference).
+	pub(crate) fn add_reference(&self) { self.references.fetch_add(1, Ordering::AcqRel); }
+
+	/// Removes a reference to the slot, returning whether it was the last one and the slot has to be deallocated.
+	///
+	/// Slots of leaked allocations (see [`orphan`](Slot::orphan)) are never deallocated, since their heap is gone.
+	#[must_use]
+	pub(crate) fn remove_reference(&self) -> bool {
+		self.references.fetch_sub(1, Ordering::AcqRel) == 1
+			&& self.released.load(Ordering::Acquire)
+			&& !self.is_orphaned()
+	}
+
+	/// Gets the count of the references to the slot, besides the record of the allocation.
+	pub(crate) fn references(&self) -> usize { self.references.load(Ordering::Acquire) }
+
+	/// Stops referencing the slot from the record of the allocation, which has been deallocated,
+	/// returning whether nothing else references it and the slot has to be deallocated.
+	#[must_use]
+	fn release_record(&self) -> bool {
+		self.released.store(true, Ordering::Release);
+		self.references() == 0
+	}
+
+	/// Shows whether the allocation has been deallocated by the heap.
+	pub(crate) fn is_released(&self) -> bool { self.released.load(Ordering::Acquire) }
+
+	/// Starts an access to the value, which prevents it from being moved until [`release`](Slot::release) is called.
+	pub(crate) fn borrow(&self) { self.borrows.fetch_add(1, Ordering::AcqRel); }
+
+	/// Ends an access to the value started with [`borrow`](Slot::borrow).
+	pub(crate) fn release(&self) { self.borrows.fetch_sub(1, Ordering::AcqRel); }
+
+	/// Pins the value in place, since a reference to it has been handed out.
+	pub(crate) fn pin(&self) {
+		if !self.is_pinned() {
+			self.pinned.store(true, Ordering::Release)
+		}
+	}
+
+	/// Allows the value to be moved again.
+	pub(crate) fn unpin(&self) { self.pinned.store(false, Ordering::Release) }
+
+	/// Shows whether a reference to the value has been handed out.
+	pub(crate) fn is_pinned(&self) -> bool { self.pinned.load(Ordering::Acquire) }
+
+	/// Shows whether the value cannot be moved, optionally ignoring the pin.
+	pub(crate) fn is_fixed(&self, ignore_pin: bool) -> bool {
+		self.borrows.load(Ordering::Acquire) != 0 || (!ignore_pin && self.is_pinned())
+	}
+
+	/// Marks the allocation as invalidated.
+	pub(crate) fn invalidate(&self) { self.invalidated.store(true, Ordering::Release) }
+
+	/// Shows whether the allocation has been invalidated.
+	pub(crate) fn is_invalidated(&self) -> bool { self.invalidated.load(Ordering::Acquire) }
+
+	/// Marks the allocation as leaked by its dropped heap.
+	pub(crate) fn orphan(&self) { self.orphaned.store(true, Ordering::Release) }
+
+	/// Shows whether the allocation has been leaked by its dropped heap.
+	pub(crate) fn is_orphaned(&self) -> bool { self.orphaned.load(Ordering::Acquire) }
+
+	/// Starts watching the mutations of the value for a transaction.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn watch(&self) {
+		self.watched.store(true, Ordering::Release);
+		self.journaled.store(true, Ordering::Release);
+	}
+
+	/// Stops watching the mutations of the value.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn unwatch(&self) {
+		self.watched.store(false, Ordering::Release);
+		self.journaled.store(false, Ordering::Release);
+	}
+
+	/// Shows whether the mutations of the value are watched by a transaction.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn is_watched(&self) -> bool { self.watched.load(Ordering::Acquire) }
+
+	/// Shows whether the value has yet to be recorded in the undo journal, and marks it as recorded.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn take_journaled(&self) -> bool { self.journaled.swap(false, Ordering::AcqRel) }
+}
+
+/// Identifier of the next allocation, shared between all heaps so that allocations can be moved between them
+static NEXT_ALLOCATION_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The list of the [`Heap`] that the record of an allocation belongs to.
+pub(crate) enum State {
+	/// The allocation is live
+	Live,
+
+	/// The allocation has been cleared from the heap while still being referenced by mutators,
+	/// and is deallocated once the last of those mutators is dropped
+	Detached,
+
+	/// The allocation has been removed from the heap, and is about to be deallocated
+	Taken
+}
+
+#[derive(Debug)]
+/// A record of a single allocation made within the [`Heap`].
+///
+/// The record is stored in the memory of the backend, right before the allocated memory (see [`block_layout`]),
+/// so that the heap does not need any memory of its own to keep track of the allocations.
+pub(crate) struct Allocation {
+	/// Unique identifier of the allocation
+	pub(crate) id: u64,
+
+	/// Pointer to the allocated memory
+	pub(crate) ptr: NonNull<u8>,
+
+	/// Layout that the memory was allocated with
+	pub(crate) layout: Layout,
+
+	/// Destructor of the stored value, if it has to be dropped by the heap
+	pub(crate) drop_glue: Option<DropGlue>,
+
+	/// Type of the stored value, if it is known
+	pub(crate) type_tag: Option<TypeTag>,
+
+	/// Identifier of the [`Scope`](crate::Scope) that owns the allocation, if any
+	pub(crate) scope: Option<usize>,
+
+	/// Location of the code that requested the allocation
+	pub(crate) site: &'static Location<'static>,
+
+	/// State shared with the mutators pointing to the allocation, if there ever were any
+	pub(crate) slot: Option<NonNull<Slot>>,
+
+	/// List of the heap that the record belongs to
+	pub(crate) state: State,
+
+	/// Previous record of the list
+	previous: Option<NonNull<Allocation>>,
+
+	/// Next record of the list
+	next: Option<NonNull<Allocation>>,
+
+	/// Next record of a temporary list, such as the candidates of a compaction
+	///
+	/// The link is atomic, since the values dropped on other threads are handed back through it (see [`SharedMemory`](crate::SharedMemory))
+	link: AtomicPtr<Allocation>
+}
+
+/// Size of the record that precedes every allocation
+const HEADER: usize = size_of::<Allocation>();
+
+/// Gets the layout of the block that holds an allocation of the provided [`Layout`] along with its record,
+/// and the offset of the allocated memory within that block.
+///
+/// The record always ends right where the allocated memory starts, so that it can be found from the pointer alone.
+pub(crate) fn block_layout(layout: Layout) -> Option<(Layout, usize)> {
+	Layout::new::<Allocation>().extend(layout).ok()
+}
+
+/// Gets the record of the allocation with the provided pointer.
+pub(crate) fn header(ptr: NonNull<u8>) -> NonNull<Allocation> { unsafe { ptr.sub(HEADER).cast() } }
+
+/// Gets the pointer to the allocated memory that the provided record precedes.
+pub(crate) fn memory(allocation: NonNull<Allocation>) -> NonNull<u8> {
+	unsafe { allocation.cast::<u8>().add(HEADER) }
+}
+
+/// Writes the record of a new live allocation in front of the memory at the provided pointer, without adding it to any list.
+///
+/// # Safety
+///
+/// The memory must have been allocated with [`Heap::allocate_block`] for the provided [`Layout`], and must not be recorded already.
+pub(crate) unsafe fn write_record(
+	ptr: NonNull<u8>,
+	layout: Layout,
+	type_tag: Option<TypeTag>,
+	site: &'static Location<'static>
+) -> NonNull<Allocation> {
+	let allocation = header(ptr);
+
+	unsafe {
+		allocation.write(Allocation {
+			id: NEXT_ALLOCATION_ID.fetch_add(1, Ordering::Relaxed),
+			ptr,
+			layout,
+			drop_glue: None,
+			type_tag,
+			scope: None,
+			site,
+			slot: None,
+			state: State::Live,
+			previous: None,
+			next: None,
+			link: AtomicPtr::new(ptr::null_mut())
+		})
+	}
+
+	allocation
+}
+
+impl Allocation {
+	/// Gets the next record of the temporary list that the record belongs to.
+	pub(crate) fn link(&self) -> Option<NonNull<Allocation>> {
+		NonNull::new(self.link.load(Ordering::Acquire))
+	}
+
+	/// Links the record to the provided one within a temporary list.
+	pub(crate) fn set_link(&self, link: Option<NonNull<Allocation>>) {
+		self.link.store(
+			link.map_or(ptr::null_mut(), NonNull::as_ptr),
+			Ordering::Release
+		)
+	}
+
+	/// Gets the link of the record at the provided pointer, without referencing the rest of the record.
+	///
+	/// # Safety
+	///
+	/// The record must have been written, and must not be deallocated while the link is used.
+	#[cfg(feature = "alloc")]
+	pub(crate) unsafe fn link_of<'record>(
+		allocation: NonNull<Allocation>
+	) -> &'record AtomicPtr<Allocation> {
+		unsafe { &(*allocation.as_ptr()).link }
+	}
+
+	/// Gets the [`Slot`] of the allocation, if it has one.
+	pub(crate) fn slot(&self) -> Option<&Slot> { self.slot.map(|slot| unsafe { &*slot.as_ptr() }) }
+
+	/// Shows whether there are any mutators pointing to the allocation.
+	pub(crate) fn is_referenced(&self) -> bool {
+		self.slot().is_some_and(|slot| slot.references() > 0)
+	}
+
+	/// Copies the bytes of the stored value.
+	///
+	/// Values that are not plain data may be uninitialized (e.g., [`MaybeUninit`]) or hold padding bytes,
+	/// which cannot be read, so their bytes are all zeros instead. Memory allocated without a type
+	/// (e.g., with [`Heap::alloc`]) is copied as is.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn contents(&self) -> Vec<u8> {
+		if self.type_tag.is_some_and(|tag| !tag.plain) {
+			return vec![0; self.layout.size()];
+		}
+
+		unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.layout.size()) }.to_vec()
+	}
+}
+
+#[derive(Debug, Default)]
+/// A list of allocations, linked through their records.
+pub(crate) struct List {
+	/// First record of the list
+	head: Option<NonNull<Allocation>>,
+
+	/// Last record of the list
+	tail: Option<NonNull<Allocation>>,
+
+	/// Count of the records within the list
+	len: usize
+}
+
+impl List {
+	/// Gets the count of the records within the list.
+	pub(crate) fn len(&self) -> usize { self.len }
+
+	/// Gets an iterator over the records of the list.
+	///
+	/// The records must not be removed from the list (nor deallocated) while iterating.
+	pub(crate) fn iter(&self) -> impl Iterator<Item = NonNull<Allocation>> + '_ {
+		core::iter::successors(self.head, |allocation| unsafe { allocation.as_ref() }.next)
+	}
+
+	/// Appends the provided record, which must not belong to any list, to the end of the list.
+	pub(crate) fn push(&mut self, allocation: NonNull<Allocation>) {
+		// The links are written through the pointers alone, since other threads may hand the records back meanwhile
+		unsafe {
+			(*allocation.as_ptr()).previous = self.tail;
+			(*allocation.as_ptr()).next = None;
+		}
+
+		match self.tail {
+			Some(tail) => unsafe { (*tail.as_ptr()).next = Some(allocation) },
+			None => self.head = Some(allocation)
+		}
+
+		self.tail = Some(allocation);
+		self.len += 1;
+	}
+
+	/// Removes and returns the first record of the list.
+	pub(crate) fn pop(&mut self) -> Option<NonNull<Allocation>> {
+		let allocation = self.head?;
+		self.remove(allocation);
+
+		Some(allocation)
+	}
+
+	/// Removes the provided record, which must belong to the list.
+	fn remove(&mut self, allocation: NonNull<Allocation>) {
+		let (previous, next) = {
+			let record = unsafe { allocation.as_ref() };
+			(record.previous, record.next)
+		};
+
+		match previous {
+			Some(previous) => unsafe { (*previous.as_ptr()).next = next },
+			None => self.head = next
+		}
+
+		match next {
+			Some(next) => unsafe { (*next.as_ptr()).previous = previous },
+			None => self.tail = previous
+		}
+
+		self.len -= 1;
+	}
+
+	/// Points the neighbours of a record of the list to its new address, after it has been moved there.
+	pub(crate) fn relink(&mut self, allocation: NonNull<Allocation>) {
+		let (previous, next) = {
+			let record = unsafe { allocation.as_ref() };
+			(record.previous, record.next)
+		};
+
+		match previous {
+			Some(previous) => unsafe { (*previous.as_ptr()).next = Some(allocation) },
+			None => self.head = Some(allocation)
+		}
+
+		match next {
+			Some(next) => unsafe { (*next.as_ptr()).previous = Some(allocation) },
+			None => self.tail = Some(allocation)
+		}
+	}
+
+	/// Moves all of the records of the provided list to the end of the list.
+	fn append(&mut self, other: &mut List) {
+		let other = core::mem::take(other);
+
+		let Some(head) = other.head else {
+			return;
+		};
+
+		unsafe { (*head.as_ptr()).previous = self.tail };
+
+		match self.tail {
+			Some(tail) => unsafe { (*tail.as_ptr()).next = Some(head) },
+			None => self.head = Some(head)
+		}
+
+		self.tail = other.tail;
+		self.len += other.len;
+	}
+
+	/// Moves the records for which the provided function returns `true` to a new list, keeping their order.
+	fn extract(&mut self, mut f: impl FnMut(&Allocation) -> bool) -> List {
+		let mut extracted = List::default();
+		let mut current = self.head;
+
+		while let Some(allocation) = current {
+			let record = unsafe { allocation.as_ref() };
+			current = record.next;
+
+			if f(record) {
+				self.remove(allocation);
+				extracted.push(allocation);
+			}
+		}
+
+		extracted
+	}
+}
 
 #[derive(Debug)]
 /// A memory management struct that allows for allocation and deallocation of raw pointers.
 /// It is best to use [`Memory`] to operate on values.
 ///
+/// The memory itself is provided by a [`RawBackend`], which is the global allocator by default (see [`SystemBackend`]).
+/// The records of the allocations are stored within that memory as well, right before the memory of each allocation.
+///
 /// See methods on [`Heap`] for documentation.
-pub struct Heap {
-	/// Vector of currently allocated pointers with their corresponding layouts
-	pub(crate) ptrs: Vec<(NonNull<u8>, Layout)>
+pub struct Heap<B: RawBackend = DefaultBackend> {
+	/// Records of the currently allocated pointers with their corresponding layouts and metadata
+	pub(crate) records: List,
+
+	/// Allocations that have been cleared from the heap while still being referenced by mutators.
+	/// They are deallocated once the last of those mutators is dropped
+	pub(crate) detached: List,
+
+	/// Identifier of the next [`Scope`](crate::Scope) to be opened
+	pub(crate) next_scope: usize,
+
+	#[cfg(feature = "alloc")]
+	/// Undo journal of the active [`Transaction`](crate::Transaction), if any
+	pub(crate) journal: Option<Journal>,
+
+	/// Statistics of the allocations made within the heap
+	stats: HeapStats,
+
+	#[cfg(feature = "profiling")]
+	/// Lifetimes of the allocations made within the heap
+	pub(crate) profile: Profile,
+
+	#[cfg(feature = "alloc")]
+	/// Observers notified of the allocation events
+	observers: Vec<Observer>,
+
+	/// Accounting shared with the parent memories, along with the byte limit enforced by [`Memory`](crate::Memory)
+	pub(crate) budget: HeapBudget,
+
+	/// Source of the allocated memory
+	pub(crate) backend: B
+}
+
+#[cfg(feature = "alloc")]
+impl Heap {
+	/// Initializes the [`Heap`] with a provided initial size (count of pointers).
+	///
+	/// The initial size is only kept for compatibility: the records of the allocations are stored along with their memory,
+	/// so there is nothing to reserve up front.
+	pub fn new(initial_size: usize) -> Self { Self::with_backend(initial_size, SystemBackend) }
 }
 
-impl Heap {
-	/// Initializes the [`Heap`] with a provided initial size (count of pointers).
-	pub fn new(initial_size: usize) -> Self {
-		Self {
-			ptrs: Vec::with_capacity(initial_size)
+impl<B: RawBackend> Heap<B> {
+	/// Initializes the [`Heap`] with a provided initial size (count of pointers), allocating the memory from the provided [`RawBackend`].
+	///
+	/// Like in [`new`](Heap::new), the initial size is only kept for compatibility.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Heap, SystemBackend};
+	/// # use std::alloc::Layout;
+	/// let mut heap = Heap::with_backend(1, SystemBackend);
+	///
+	/// let ptr = heap.alloc(Layout::new::<u32>());
+	/// assert_eq!(heap.size(), 4);
+	///
+	/// heap.dealloc(ptr, Layout::new::<u32>());
+	/// ```
+	pub fn with_backend(_initial_size: usize, backend: B) -> Self {
+		Self::with_budget(backend, HeapBudget::default())
+	}
+
+	/// Initializes the [`Heap`] with a provided [`RawBackend`] and budget.
+	pub(crate) fn with_budget(backend: B, budget: HeapBudget) -> Self {
+		Self {
+			records: List::default(),
+			detached: List::default(),
+			next_scope: 0,
+			#[cfg(feature = "alloc")]
+			journal: None,
+			stats: HeapStats::default(),
+			#[cfg(feature = "profiling")]
+			profile: Profile::new(),
+			#[cfg(feature = "alloc")]
+			observers: vec![],
+			budget,
+			backend
+		}
+	}
+
+	/// Gets the [`RawBackend`] that the memory is allocated from.
+	pub fn backend(&self) -> &B { &self.backend }
+
+	/// Allocates memory for a given [`Layout`].
+	///
+	/// It is important to deallocate the memory after usage using [`dealloc`](Heap::dealloc). Use [`Memory`] for automatic deallocation.
+	///
+	/// **Note:** the allocated memory is **not zero-initialized**. For that, use [`alloc_zeroed`](Heap::alloc_zeroed).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Heap;
+	/// # use std::alloc::Layout;
+	/// # use std::ptr::NonNull;
+	/// let mut heap = Heap::new(1);
+	///
+	/// let layout = Layout::new::<bool>();
+	/// let ptr: NonNull<u8> = heap.alloc(layout);
+	/// unsafe { ptr.as_ptr().write_bytes(0, layout.size()) } // Zero-initializing the memory first
+	///
+	/// // Casting the allocated pointer to the desired type
+	/// let as_bool_ptr: *mut bool = ptr.cast::<bool>().as_ptr();
+	///
+	/// // Memory is zero-initialized and has a value of `0`, which is `false`
+	/// assert_eq!(unsafe { *as_bool_ptr }, false);
+	///
+	/// unsafe { *as_bool_ptr = true }
+	/// assert_eq!(unsafe { *as_bool_ptr }, true);
+	/// ```
+	#[track_caller]
+	pub fn alloc(&mut self, layout: Layout) -> NonNull<u8> {
+		self.budget.charge(layout.size(), 1);
+
+		let Ok(ptr) = self.alloc_uncharged(layout, None) else {
+			self.budget.release(layout.size(), 1);
+			alloc_failed(layout)
+		};
+
+		self.relieve_pressure();
+		ptr
+	}
+
+	/// Allocates memory for a given [`Layout`], recording the type of the value that it is allocated for.
+	///
+	/// The [`Slot`] of the allocation is allocated right away, since a mutator is handed out for every typed allocation.
+	/// Fails if the allocation would exceed the byte limit, or if the backend is exhausted.
+	#[track_caller]
+	pub(crate) fn try_alloc_tagged(
+		&mut self,
+		layout: Layout,
+		type_tag: Option<TypeTag>
+	) -> Result<NonNull<u8>, AllocError> {
+		self.budget.try_charge(layout.size(), 1)?;
+
+		let allocated = self
+			.allocate_block(layout)
+			.and_then(|ptr| match self.allocate_slot(ptr) {
+				Some(slot) => Ok((ptr, slot)),
+				None => {
+					// Undoing the allocation, since no mutator could be handed out for it
+					unsafe { self.deallocate_block(ptr, layout) }
+					Err(AllocError::OutOfMemory {
+						requested: layout.size()
+					})
+				}
+			});
+
+		let (ptr, slot) = allocated.inspect_err(|_| self.budget.release(layout.size(), 1))?;
+
+		unsafe { self.record(ptr, layout, type_tag, Location::caller()) };
+		unsafe { self.record_mut(ptr) }.slot = Some(slot);
+
+		Ok(ptr)
+	}
+
+	/// Allocates memory for a given [`Layout`] and records it, without counting it towards the budget.
+	#[track_caller]
+	fn alloc_uncharged(
+		&mut self,
+		layout: Layout,
+		type_tag: Option<TypeTag>
+	) -> Result<NonNull<u8>, AllocError> {
+		let ptr = self.allocate_block(layout)?;
+		unsafe { self.record(ptr, layout, type_tag, Location::caller()) };
+
+		Ok(ptr)
+	}
+
+	/// Records memory allocated with [`allocate_block`](Heap::allocate_block), without counting it towards the budget.
+	///
+	/// # Safety
+	///
+	/// The memory must have been allocated with the provided [`Layout`], and must not be recorded already.
+	pub(crate) unsafe fn record(
+		&mut self,
+		ptr: NonNull<u8>,
+		layout: Layout,
+		type_tag: Option<TypeTag>,
+		site: &'static Location<'static>
+	) {
+		let allocation = unsafe { write_record(ptr, layout, type_tag, site) };
+		unsafe { self.track(allocation) };
+	}
+
+	/// Records a new allocation whose record has been written with [`write_record`] already (e.g., by a [`Cache`](crate::cache::Cache)),
+	/// without counting it towards the budget.
+	///
+	/// # Safety
+	///
+	/// The record must not belong to any list.
+	pub(crate) unsafe fn track(&mut self, allocation: NonNull<Allocation>) {
+		let record = unsafe { allocation.as_ref() };
+		let layout = record.layout;
+
+		self.stats.record_alloc(layout, self.waste(layout));
+		#[cfg(feature = "profiling")]
+		self.profile.record_insertion(record);
+
+		self.notify(|observer| observer.on_alloc(&AllocationInfo::of(record)));
+
+		// Allocations made within a transaction are deallocated if it fails
+		#[cfg(feature = "alloc")]
+		if let Some(journal) = &mut self.journal {
+			journal.record_alloc(record.id);
+		}
+
+		// Saving that pointer
+		self.records.push(allocation);
+	}
+
+	/// Removes the record of the live allocation with the provided pointer as if it was deallocated,
+	/// without releasing it from the budget nor returning its memory (or its [`Slot`]) to the backend.
+	///
+	/// # Safety
+	///
+	/// The pointer must belong to a live allocation of the heap, whose memory and slot are taken over by the caller.
+	pub(crate) unsafe fn forget(&mut self, ptr: NonNull<u8>) {
+		let allocation = header(ptr);
+		self.records.remove(allocation);
+
+		let record = unsafe { allocation.as_ref() };
+
+		self.stats
+			.record_removal(record.layout, self.waste(record.layout));
+		self.stats.record_dealloc();
+		#[cfg(feature = "profiling")]
+		self.profile.record_removal(record);
+
+		self.notify(|observer| observer.on_dealloc(&AllocationInfo::of(record)));
+	}
+
+	/// Allocates memory for a given [`Layout`] from the backend without recording it,
+	/// for containers that manage the memory themselves (e.g., [`Pool`](crate::Pool)).
+	#[cfg(feature = "alloc")]
+	pub(crate) fn allocate_unrecorded(
+		&mut self,
+		layout: Layout
+	) -> Result<NonNull<u8>, AllocError> {
+		self.allocate(layout)
+	}
+
+	/// Returns memory allocated with [`allocate_unrecorded`](Heap::allocate_unrecorded) to the backend.
+	///
+	/// # Safety
+	///
+	/// The memory must have been allocated with the provided [`Layout`], and must not be used afterwards.
+	pub(crate) unsafe fn deallocate_unrecorded(&mut self, ptr: NonNull<u8>, layout: Layout) {
+		if layout.size() != 0 {
+			unsafe { self.backend.deallocate(ptr, layout) }
+		}
+	}
+
+	/// Shrinks or grows memory allocated with [`allocate_unrecorded`](Heap::allocate_unrecorded) to `new_size` bytes,
+	/// keeping the alignment of the original layout.
+	///
+	/// # Safety
+	///
+	/// The memory must have been allocated with the provided [`Layout`], and must not be used afterwards unless an error is returned.
+	pub(crate) unsafe fn reallocate_unrecorded(
+		&mut self,
+		ptr: NonNull<u8>,
+		layout: Layout,
+		new_size: usize
+	) -> Result<NonNull<u8>, AllocError> {
+		unsafe { self.reallocate(ptr, layout, new_size) }
+	}
+
+	/// Allocates memory for a given [`Layout`] from the backend.
+	fn allocate(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+		// Zero-sized layouts must not be passed to the backend
+		if layout.size() == 0 {
+			return Ok(unsafe {
+				NonNull::new_unchecked(core::ptr::without_provenance_mut(layout.align()))
+			});
+		}
+
+		self.backend
+			.allocate(layout)
+			.ok_or(AllocError::OutOfMemory {
+				requested: layout.size()
+			})
+	}
+
+	/// Resizes memory allocated from the backend, leaving it untouched if the backend is exhausted.
+	///
+	/// # Safety
+	///
+	/// The memory must have been allocated with the provided [`Layout`], and must not be used afterwards unless an error is returned.
+	unsafe fn reallocate(
+		&mut self,
+		ptr: NonNull<u8>,
+		layout: Layout,
+		new_size: usize
+	) -> Result<NonNull<u8>, AllocError> {
+		if layout.size() != 0 && new_size != 0 {
+			// Resizing the memory within the backend
+			return unsafe { self.backend.reallocate(ptr, layout, new_size) }.ok_or(
+				AllocError::OutOfMemory {
+					requested: new_size
+				}
+			);
+		}
+
+		// The backend cannot resize zero-sized allocations, so they are moved manually
+		let new_layout =
+			Layout::from_size_align(new_size, layout.align()).expect("Layout creation failed");
+		let new_ptr = self.allocate(new_layout)?;
+
+		unsafe {
+			core::ptr::copy_nonoverlapping(
+				ptr.as_ptr(),
+				new_ptr.as_ptr(),
+				core::cmp::min(layout.size(), new_size)
+			);
+
+			if layout.size() != 0 {
+				self.backend.deallocate(ptr, layout);
+			}
+		}
+
+		Ok(new_ptr)
+	}
+
+	/// Allocates a block for memory of the provided [`Layout`] along with its record from the backend,
+	/// returning the pointer to the memory.
+	///
+	/// The blocks are never zero-sized, since they always hold a record.
+	pub(crate) fn allocate_block(&mut self, layout: Layout) -> Result<NonNull<u8>, AllocError> {
+		let error = AllocError::OutOfMemory {
+			requested: layout.size()
+		};
+		let (block, offset) = block_layout(layout).ok_or(error)?;
+		let block = self.backend.allocate(block).ok_or(error)?;
+
+		Ok(unsafe { block.add(offset) })
+	}
+
+	/// Returns the block of memory allocated with [`allocate_block`](Heap::allocate_block) to the backend.
+	///
+	/// # Safety
+	///
+	/// The memory must have been allocated with the provided [`Layout`], and must not be used afterwards.
+	pub(crate) unsafe fn deallocate_block(&mut self, ptr: NonNull<u8>, layout: Layout) {
+		let (block, offset) = block_layout(layout).expect("Layout creation failed");
+		unsafe { self.backend.deallocate(ptr.sub(offset), block) }
+	}
+
+	/// Allocates the [`Slot`] of an allocation at the provided address from the backend.
+	fn allocate_slot(&mut self, address: NonNull<u8>) -> Option<NonNull<Slot>> {
+		let slot = self.backend.allocate(Layout::new::<Slot>())?.cast::<Slot>();
+		unsafe { slot.write(Slot::new(address)) }
+
+		Some(slot)
+	}
+
+	/// Returns the memory of a [`Slot`] that is no longer referenced to the backend.
+	///
+	/// # Safety
+	///
+	/// The slot must have been allocated by the heap (or by one sharing its backend), and must not be used afterwards.
+	pub(crate) unsafe fn deallocate_slot(&mut self, slot: NonNull<Slot>) {
+		unsafe {
+			self.backend
+				.deallocate(slot.cast::<u8>(), Layout::new::<Slot>())
+		}
+	}
+
+	/// Stops referencing the provided [`Slot`] from the record of its allocation, which is being deallocated.
+	///
+	/// The slot is deallocated along with the allocation, unless mutators still reference it.
+	fn release_slot(&mut self, slot: NonNull<Slot>) {
+		if unsafe { slot.as_ref() }.release_record() {
+			unsafe { self.deallocate_slot(slot) }
+		}
+	}
+
+	/// Allocates memory for a given [`Layout`].
+	///
+	/// It is important to deallocate the memory after usage using [`dealloc`](Heap::dealloc). Use [`Memory`] for automatic deallocation.
+	///
+	/// Unlike [`alloc`](Heap::alloc), the allocated memory is guaranteed to be zero-initialized.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Heap;
+	/// # use std::alloc::Layout;
+	/// # use std::ptr::NonNull;
+	/// let mut heap = Heap::new(1);
+	///
+	/// let layout = Layout::new::<bool>();
+	/// let ptr: NonNull<u8> = heap.alloc_zeroed(layout);
+	/// // The memory is already zero-initialized, so there's no need to overwrite it
+	/// // unsafe { ptr.as_ptr().write_bytes(0, layout.size()) }
+	///
+	/// // Cast the allocated pointer to the desired type
+	/// let as_bool_ptr: *mut bool = ptr.cast::<bool>().as_ptr();
+	///
+	/// // Memory is zero-initialized and has a value of `0`, which is `false`
+	/// assert_eq!(unsafe { *as_bool_ptr }, false);
+	///
+	/// unsafe { *as_bool_ptr = true }
+	/// assert_eq!(unsafe { *as_bool_ptr }, true);
+	/// ```
+	#[track_caller]
+	pub fn alloc_zeroed(&mut self, layout: Layout) -> NonNull<u8> {
+		// Allocating non-zeroed memory on the heap
+		let ptr = self.alloc(layout);
+
+		// Overwriting it with zeros
+		unsafe { ptr.as_ptr().write_bytes(0, layout.size()) }
+
+		ptr
+	}
+
+	/// Deallocates memory for the provided pointer and [`Layout`].
+	///
+	/// It is important to note that after the memory for a provided pointer has been deallocated, it is **no longer safe to use**.
+	/// The pointer must have been allocated by the heap, with the provided layout.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Heap;
+	/// # use std::alloc::Layout;
+	/// # use std::ptr::NonNull;
+	/// # use std::panic::catch_unwind;
+	/// let mut heap = Heap::new(1);
+	///
+	/// let layout = Layout::new::<u8>();
+	/// let ptr: NonNull<u8> = heap.alloc(layout);
+	///
+	/// // Do some operations on the data...
+	/// // e.g., *ptr.as_ptr() = 5;
+	///
+	/// heap.dealloc(ptr, layout);
+	///
+	/// // unsafe { *ptr.as_ptr() = 42 } // We no longer own this memory location, so accessing it is a big no-no!
+	/// ```
+	pub fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
+		let allocation = header(ptr);
+		debug_assert_eq!(unsafe { allocation.as_ref() }.layout.size(), layout.size());
+
+		match unsafe { allocation.as_ref() }.state {
+			State::Live => {
+				unsafe { self.remove_record(ptr) };
+			}
+			State::Detached => self.detached.remove(allocation),
+			// The allocation is about to be deallocated by the heap itself
+			State::Taken => return
+		}
+
+		self.free_allocation(allocation);
+	}
+
+	/// Drops all of the values with known destructors and deallocates all the memory within the [`Heap`].
+	///
+	/// Values allocated through [`Memory`](crate::Memory) have their destructors recorded by the heap,
+	/// while memory allocated with [`alloc`](Heap::alloc) is only deallocated.
+	///
+	/// Allocations that are still referenced by mutators are invalidated instead: any further use of those mutators panics,
+	/// and their memory is deallocated once they are dropped.
+	///
+	/// **Note:** the destructors are called while the heap is borrowed, so they must not access it.
+	/// [`Memory::reset`](crate::Memory::reset) does not have this limitation.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Heap;
+	/// # use std::alloc::Layout;
+	/// let mut heap = Heap::new(2);
+	/// let layout = Layout::new::<u64>();
+	///
+	/// let _ptr1 = heap.alloc(layout);
+	/// let _ptr2 = heap.alloc(layout);
+	///
+	/// heap.clear();
+	///
+	/// assert_eq!(heap.count(), 0);
+	/// assert_eq!(heap.size(), 0);
+	/// ```
+	pub fn clear(&mut self) {
+		let allocations = self.take_all(true);
+		self.destroy_in_place(allocations);
+	}
+
+	/// Drops the values of the provided allocations and deallocates their memory while the heap is borrowed.
+	fn destroy_in_place(&mut self, mut allocations: List) {
+		drop_values(&allocations);
+
+		while let Some(allocation) = allocations.pop() {
+			self.free_allocation(allocation);
+		}
+	}
+
+	/// Shrinks or grows the memory for the provided pointer and [`Layout`] to `new_size` bytes,
+	/// keeping the alignment of the original layout.
+	///
+	/// The contents are preserved up to the smaller of the old and new sizes. The returned pointer
+	/// replaces the provided one, which is **no longer safe to use**.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Heap;
+	/// # use std::alloc::Layout;
+	/// # use std::ptr::NonNull;
+	/// let mut heap = Heap::new(1);
+	///
+	/// let layout = Layout::new::<u16>();
+	/// let ptr: NonNull<u8> = heap.alloc_zeroed(layout);
+	/// unsafe { *ptr.as_ptr() = 7 }
+	///
+	/// let ptr = heap.realloc(ptr, layout, 8);
+	///
+	/// assert_eq!(heap.size(), 8);
+	/// assert_eq!(heap.count(), 1);
+	/// assert_eq!(unsafe { *ptr.as_ptr() }, 7);
+	/// ```
+	pub fn realloc(&mut self, ptr: NonNull<u8>, layout: Layout, new_size: usize) -> NonNull<u8> {
+		let Ok((new_ptr, live)) = self.resize(ptr, layout, new_size) else {
+			let new_layout =
+				Layout::from_size_align(new_size, layout.align()).expect("Layout creation failed");
+			alloc_failed(new_layout);
+		};
+
+		// Detached allocations are no longer counted
+		if live {
+			self.budget
+				.charge(new_size.saturating_sub(layout.size()), 0);
+			self.budget
+				.release(layout.size().saturating_sub(new_size), 0);
+			self.relieve_pressure();
+		}
+
+		new_ptr
+	}
+
+	/// Calls the pressure callbacks of the thresholds that have been crossed since the last check.
+	///
+	/// The heap is borrowed exclusively here rather than locked by a [`Memory`], so the callbacks can be called right away.
+	fn relieve_pressure(&self) { self.budget.crossed().call() }
+
+	/// Shrinks or grows the memory of an allocation, failing if that would exceed the byte limit
+	/// or if the backend is exhausted.
+	pub(crate) fn try_realloc(
+		&mut self,
+		ptr: NonNull<u8>,
+		layout: Layout,
+		new_size: usize
+	) -> Result<NonNull<u8>, AllocError> {
+		let growth = new_size.saturating_sub(layout.size());
+		self.budget.try_charge(growth, 0)?;
+
+		let (new_ptr, live) = self
+			.resize(ptr, layout, new_size)
+			.inspect_err(|_| self.budget.release(growth, 0))?;
+
+		// Detached allocations are no longer counted
+		if live {
+			self.budget
+				.release(layout.size().saturating_sub(new_size), 0);
+		} else {
+			self.budget.release(growth, 0);
+		}
+
+		Ok(new_ptr)
+	}
+
+	/// Resizes the memory and updates its record, without counting it towards the budget.
+	///
+	/// Returns the new pointer, and whether the allocation is live (i.e., not detached).
+	/// The memory is left untouched if the backend is exhausted.
+	fn resize(
+		&mut self,
+		ptr: NonNull<u8>,
+		layout: Layout,
+		new_size: usize
+	) -> Result<(NonNull<u8>, bool), AllocError> {
+		let new_layout =
+			Layout::from_size_align(new_size, layout.align()).expect("Layout creation failed");
+		let error = AllocError::OutOfMemory {
+			requested: new_size
+		};
+
+		// The record is moved along with the memory, since it is stored within the same block
+		let old = AllocationInfo::of(unsafe { header(ptr).as_ref() });
+		let (block, offset) = block_layout(layout).ok_or(error)?;
+		let (new_block, _) = block_layout(new_layout).ok_or(error)?;
+		let new_block = unsafe {
+			self.backend
+				.reallocate(ptr.sub(offset), block, new_block.size())
+		}
+		.ok_or(error)?;
+
+		let new_ptr = unsafe { new_block.add(offset) };
+		let allocation = header(new_ptr);
+
+		let state = {
+			let record = unsafe { &mut *allocation.as_ptr() };
+			record.ptr = new_ptr;
+			record.layout = new_layout;
+			record.state
+		};
+
+		let live = match state {
+			State::Live => {
+				self.records.relink(allocation);
+				true
+			}
+			State::Detached => {
+				self.detached.relink(allocation);
+				false
+			}
+			State::Taken => false
+		};
+		let record = unsafe { allocation.as_ref() };
+
+		// The mutators pointing to the allocation follow it to the new address
+		if let Some(slot) = record.slot() {
+			slot.relocate(new_ptr);
+		}
+
+		if live {
+			self.stats.record_realloc(
+				layout,
+				self.waste(layout),
+				new_layout,
+				self.waste(new_layout)
+			);
+			#[cfg(feature = "profiling")]
+			self.profile.record_resize(record);
+		}
+
+		let new = AllocationInfo::of(record);
+		self.notify(|observer| observer.on_realloc(&old, &new));
+
+		Ok((new_ptr, live))
+	}
+
+	/// Gets the count of bytes that the backend reserves beyond the block of an allocation with the provided [`Layout`].
+	fn waste(&self, layout: Layout) -> usize {
+		let Some((block, _)) = block_layout(layout) else {
+			return 0;
+		};
+
+		self.backend
+			.reserved_size(block)
+			.saturating_sub(block.size())
+	}
+
+	/// Returns the memory of an allocation whose record has been removed from the lists to the backend, notifying the observers.
+	pub(crate) fn free_allocation(&mut self, allocation: NonNull<Allocation>) {
+		let (ptr, layout, slot) = {
+			let record = unsafe { allocation.as_ref() };
+			self.notify(|observer| observer.on_dealloc(&AllocationInfo::of(record)));
+
+			(record.ptr, record.layout, record.slot)
+		};
+
+		if let Some(slot) = slot {
+			self.release_slot(slot);
+		}
+
+		unsafe { self.deallocate_block(ptr, layout) }
+		self.stats.record_dealloc();
+	}
+
+	/// Removes the record of the live allocation with the provided pointer, returning it.
+	///
+	/// The memory of the allocation is **not** deallocated.
+	///
+	/// # Safety
+	///
+	/// The pointer must belong to a live allocation of the heap.
+	pub(crate) unsafe fn remove_record(&mut self, ptr: NonNull<u8>) -> NonNull<Allocation> {
+		let allocation = header(ptr);
+		self.records.remove(allocation);
+
+		let record = unsafe { allocation.as_ref() };
+
+		self.stats
+			.record_removal(record.layout, self.waste(record.layout));
+		#[cfg(feature = "profiling")]
+		self.profile.record_removal(record);
+		self.budget.release(record.layout.size(), 1);
+
+		allocation
+	}
+
+	/// Adds the record of an existing allocation (e.g., one removed from another heap) to the heap,
+	/// failing if that would exceed the byte limit.
+	///
+	/// # Safety
+	///
+	/// The record must not belong to any heap, and its memory must have been allocated from a backend sharing its memory with the one of the heap.
+	pub(crate) unsafe fn try_insert_record(
+		&mut self,
+		allocation: NonNull<Allocation>
+	) -> Result<(), AllocError> {
+		let layout = unsafe { allocation.as_ref() }.layout;
+		self.budget.try_charge(layout.size(), 1)?;
+
+		unsafe { self.link_record(allocation) };
+		Ok(())
+	}
+
+	/// Adds the record of an existing allocation to the heap, ignoring the byte limit.
+	///
+	/// # Safety
+	///
+	/// See [`try_insert_record`](Heap::try_insert_record).
+	pub(crate) unsafe fn insert_record(&mut self, allocation: NonNull<Allocation>) {
+		let layout = unsafe { allocation.as_ref() }.layout;
+		self.budget.charge(layout.size(), 1);
+
+		unsafe { self.link_record(allocation) };
+	}
+
+	/// Adds the record of an existing allocation to the live ones, once it has been counted towards the budget.
+	unsafe fn link_record(&mut self, mut allocation: NonNull<Allocation>) {
+		let record = unsafe { allocation.as_mut() };
+		record.state = State::Live;
+
+		self.stats
+			.record_insertion(record.layout, self.waste(record.layout));
+		#[cfg(feature = "profiling")]
+		self.profile.record_insertion(record);
+		self.records.push(allocation);
+	}
+
+	/// Gets the record of the allocation with the provided pointer, including the detached ones.
+	///
+	/// # Safety
+	///
+	/// The pointer must belong to an allocation of the heap that has not been deallocated yet.
+	pub(crate) unsafe fn record_mut(&mut self, ptr: NonNull<u8>) -> &mut Allocation {
+		unsafe { &mut *header(ptr).as_ptr() }
+	}
+
+	/// Gets an iterator over the records of the live allocations.
+	pub(crate) fn records(&self) -> impl Iterator<Item = &Allocation> {
+		self.records
+			.iter()
+			.map(|allocation| unsafe { &*allocation.as_ptr() })
+	}
+
+	/// Gets the [`Slot`] of the allocation with the provided pointer, allocating it if needed.
+	///
+	/// # Safety
+	///
+	/// See [`record_mut`](Heap::record_mut).
+	pub(crate) unsafe fn slot(&mut self, ptr: NonNull<u8>) -> Result<NonNull<Slot>, AllocError> {
+		let record = unsafe { self.record_mut(ptr) };
+
+		if let Some(slot) = record.slot {
+			return Ok(slot);
+		}
+
+		let requested = record.layout.size();
+		let slot = self
+			.allocate_slot(ptr)
+			.ok_or(AllocError::OutOfMemory { requested })?;
+		let record = unsafe { self.record_mut(ptr) };
+		record.slot = Some(slot);
+		#[cfg(feature = "alloc")]
+		let id = record.id;
+
+		// The values that existed before the active transaction are watched by it
+		#[cfg(feature = "alloc")]
+		if self
+			.journal
+			.as_ref()
+			.is_some_and(|journal| !journal.is_allocated(id))
+		{
+			unsafe { slot.as_ref() }.watch();
 		}
+
+		Ok(slot)
 	}
 
-	/// Allocates memory for a given [`Layout`].
+	/// Stops watching the mutations of all of the values, including the detached ones.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn unwatch(&self) {
+		let allocations = self.records.iter().chain(self.detached.iter());
+
+		for slot in allocations.filter_map(|allocation| unsafe { allocation.as_ref() }.slot) {
+			unsafe { slot.as_ref() }.unwatch();
+		}
+	}
+
+	/// Invalidates all of the allocations, and removes and returns their records.
 	///
-	/// It is important to deallocate the memory after usage using [`dealloc`](Heap::dealloc). Use [`Memory`] for automatic deallocation.
+	/// If `detach_referenced` is set, the allocations that are still referenced by mutators are detached instead of being returned.
+	/// The memory of the allocations is **not** deallocated.
+	pub(crate) fn take_all(&mut self, detach_referenced: bool) -> List {
+		let taken = core::mem::take(&mut self.records);
+		let mut taken = self.retire(taken, detach_referenced);
+
+		if !detach_referenced {
+			let mut detached = core::mem::take(&mut self.detached);
+			mark_taken(&detached);
+			taken.append(&mut detached);
+		}
+
+		taken
+	}
+
+	/// Invalidates the live allocations with the provided identifiers, and removes and returns their records.
+	///
+	/// The allocations that are still referenced by mutators are detached instead of being returned.
+	/// The memory of the allocations is **not** deallocated.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn take_allocated(&mut self, ids: &BTreeSet<u64>) -> List {
+		let taken = self.records.extract(|a| ids.contains(&a.id));
+		self.retire(taken, true)
+	}
+
+	/// Invalidates the provided allocations, which have been removed from the heap.
 	///
-	/// **Note:** the allocated memory is **not zero-initialized**. For that, use [`alloc_zeroed`](Heap::alloc_zeroed).
+	/// If `detach_referenced` is set, the allocations that are still referenced by mutators are detached instead of being returned.
+	fn retire(&mut self, mut allocations: List, detach_referenced: bool) -> List {
+		for allocation in allocations.iter() {
+			let record = unsafe { allocation.as_ref() };
+
+			if let Some(slot) = record.slot() {
+				slot.invalidate();
+			}
+
+			self.stats
+				.record_removal(record.layout, self.waste(record.layout));
+			#[cfg(feature = "profiling")]
+			self.profile.record_removal(record);
+			self.budget.release(record.layout.size(), 1);
+		}
+
+		if detach_referenced {
+			let mut referenced = allocations.extract(Allocation::is_referenced);
+
+			for mut allocation in referenced.iter() {
+				unsafe { allocation.as_mut() }.state = State::Detached;
+			}
+
+			self.detached.append(&mut referenced);
+		}
+
+		mark_taken(&allocations);
+		allocations
+	}
+
+	/// Records the current contents of the value at the provided pointer in the undo journal of the active transaction,
+	/// returning whether it could be recorded.
 	///
-	/// # Examples
+	/// Only the bytes of plain data values can be recorded, since copying those of any other value would duplicate the resources it owns.
+	#[cfg(feature = "alloc")]
+	pub(crate) fn journal_value(&mut self, ptr: NonNull<u8>) -> bool {
+		let allocation = unsafe { header(ptr).as_ref() };
+
+		match &mut self.journal {
+			Some(journal)
+				if allocation.state == State::Live
+					&& allocation.type_tag.is_some_and(|tag| tag.plain) =>
+			{
+				journal.record(allocation);
+				true
+			}
+			_ => false
+		}
+	}
+
+	/// Moves the provided value, which has just been replaced at the provided pointer, into the undo journal of the active transaction.
 	///
-	/// ```
-	/// # use halloc::Heap;
-	/// # use std::alloc::Layout;
-	/// # use std::ptr::NonNull;
-	/// let mut heap = Heap::new(1);
+	/// The value is leaked if there is no active transaction, like any value that is replaced with [`HeapMutator::write`].
+	#[cfg(feature = "alloc")]
+	pub(crate) fn journal_replaced<T: Allocatable>(&mut self, ptr: NonNull<u8>, previous: T) {
+		let allocation = unsafe { header(ptr).as_ref() };
+
+		match &mut self.journal {
+			Some(journal) if allocation.state == State::Live => {
+				journal.record_replaced(allocation, previous)
+			}
+			_ => core::mem::forget(previous)
+		}
+	}
+
+	/// Registers an observer that is notified of the allocation events of the heap.
 	///
-	/// let layout = Layout::new::<bool>();
-	/// let ptr: NonNull<u8> = heap.alloc(layout);
-	/// unsafe { ptr.as_ptr().write_bytes(0, layout.size()) } // Zero-initializing the memory first
+	/// See [`Memory::add_observer`](crate::Memory::add_observer) for examples.
+	#[cfg(feature = "alloc")]
+	pub fn add_observer(&mut self, observer: impl HeapObserver + 'static) {
+		self.observers.push(Observer(Arc::new(observer)));
+	}
+
+	/// Calls the provided function with each of the registered observers.
+	pub(crate) fn notify(&self, f: impl Fn(&dyn HeapObserver)) {
+		#[cfg(feature = "alloc")]
+		for observer in &self.observers {
+			f(observer.0.as_ref());
+		}
+
+		// Observers cannot be registered without `alloc`
+		#[cfg(not(feature = "alloc"))]
+		let _ = f;
+	}
+
+	/// Shows whether the allocations can be recorded in batches by a [`Cache`], since nothing has to see each of them right away:
+	/// there is no byte limit (nor a parent memory) to check them against, no observer to notify, no transaction to record them in,
+	/// and no profile to time them.
+	///
+	/// The backend must also share its memory with other instances (see [`RawBackend::SHARED`]): the free blocks kept by a cache
+	/// would otherwise be taken from the few bytes of the backend itself (e.g., a [`StaticBuffer`](crate::StaticBuffer)).
+	pub(crate) fn is_cacheable(&self) -> bool {
+		#[cfg(feature = "alloc")]
+		if !self.observers.is_empty() || self.journal.is_some() {
+			return false;
+		}
+
+		B::SHARED && self.budget.is_unlimited() && !cfg!(feature = "profiling")
+	}
+
+	/// Sets the destructor and the type of the value stored at the provided pointer.
 	///
-	/// // Casting the allocated pointer to the desired type
-	/// let as_bool_ptr: *mut bool = ptr.cast::<bool>().as_ptr();
+	/// # Safety
 	///
-	/// // Memory is zero-initialized and has a value of `0`, which is `false`
-	/// assert_eq!(unsafe { *as_bool_ptr }, false);
+	/// See [`record_mut`](Heap::record_mut).
+	pub(crate) unsafe fn set_contents(
+		&mut self,
+		ptr: NonNull<u8>,
+		drop_glue: Option<DropGlue>,
+		type_tag: TypeTag
+	) {
+		let record = unsafe { self.record_mut(ptr) };
+		record.drop_glue = drop_glue;
+		record.type_tag = Some(type_tag);
+	}
+
+	/// Removes and returns the records of all the allocations owned by the provided scope.
 	///
-	/// unsafe { *as_bool_ptr = true }
-	/// assert_eq!(unsafe { *as_bool_ptr }, true);
-	/// ```
-	pub fn alloc(&mut self, layout: Layout) -> NonNull<u8> {
-		// Allocating memory on the heap
-		let ptr = unsafe { std::alloc::alloc(layout) };
+	/// The memory of the allocations is **not** deallocated.
+	pub(crate) fn take_scope(&mut self, scope: usize) -> List {
+		let mut taken = self.records.extract(|a| a.scope == Some(scope));
 
-		// Checking nullness
-		if ptr.is_null() {
-			std::alloc::handle_alloc_error(layout);
-		}
+		for allocation in taken.iter() {
+			let record = unsafe { allocation.as_ref() };
 
-		// Constructing a `NonNull` pointer from a raw one
-		let nn_ptr = unsafe { NonNull::new_unchecked(ptr) };
+			self.stats
+				.record_removal(record.layout, self.waste(record.layout));
+			#[cfg(feature = "profiling")]
+			self.profile.record_removal(record);
+			self.budget.release(record.layout.size(), 1);
+		}
 
-		// Saving that pointer
-		self.ptrs.push((nn_ptr, layout));
+		// Detached allocations of the scope can no longer be referenced once it ends
+		let mut detached = self.detached.extract(|a| a.scope == Some(scope));
+		taken.append(&mut detached);
 
-		nn_ptr
+		mark_taken(&taken);
+		taken
 	}
 
-	/// Allocates memory for a given [`Layout`].
+	/// Returns a copy of all the bytes contained within the [`Heap`].
 	///
-	/// It is important to deallocate the memory after usage using [`dealloc`](Heap::dealloc). Use [`Memory`] for automatic deallocation.
+	/// Values that are not plain data (see [`PlainData`]) are copied as zeros, since their bytes may be uninitialized.
 	///
-	/// Unlike [`alloc`](Heap::alloc), the allocated memory is guaranteed to be zero-initialized.
+	/// Note that if you only need the count of contained bytes, you should use [`size`](Heap::size) instead.
 	///
 	/// # Examples
 	///
@@ -81,192 +1524,434 @@ impl Heap {
 	/// # use std::ptr::NonNull;
 	/// let mut heap = Heap::new(1);
 	///
-	/// let layout = Layout::new::<bool>();
-	/// let ptr: NonNull<u8> = heap.alloc_zeroed(layout);
-	/// // The memory is already zero-initialized, so there's no need to overwrite it
-	/// // unsafe { ptr.as_ptr().write_bytes(0, layout.size()) }
-	///
-	/// // Cast the allocated pointer to the desired type
-	/// let as_bool_ptr: *mut bool = ptr.cast::<bool>().as_ptr();
+	/// let layout = Layout::new::<i32>();
+	/// let ptr: NonNull<u8> = heap.alloc(layout);
+	/// let i32_ptr = ptr.cast::<i32>().as_ptr();
 	///
-	/// // Memory is zero-initialized and has a value of `0`, which is `false`
-	/// assert_eq!(unsafe { *as_bool_ptr }, false);
+	/// unsafe { *i32_ptr = 42 }
 	///
-	/// unsafe { *as_bool_ptr = true }
-	/// assert_eq!(unsafe { *as_bool_ptr }, true);
+	/// let bytes = heap.bytes();
+	/// assert!(
+	///     bytes == vec![42, 0, 0, 0] ||
+	///     bytes == vec![0, 0, 0, 42]
+	/// );
 	/// ```
-	pub fn alloc_zeroed(&mut self, layout: Layout) -> NonNull<u8> {
-		// Allocating non-zeroed memory on the heap
-		let ptr = self.alloc(layout);
+	#[cfg(feature = "alloc")]
+	pub fn bytes(&self) -> Vec<u8> {
+		// Creating the resulting bytes vector
+		let mut bytes = Vec::with_capacity(self.size());
 
-		// Overwriting it with zeros
-		unsafe { ptr.as_ptr().write_bytes(0, layout.size()) }
+		for allocation in self.records() {
+			bytes.extend(allocation.contents());
+		}
 
-		ptr
+		bytes
 	}
 
-	/// Deallocates memory for the provided pointer and [`Layout`].
+	/// Returns the count of bytes contained within all the allocated pointers.
 	///
-	/// It is important to note that after the memory for a provided pointer has been deallocated, it is **no longer safe to use**.
+	/// Not to be confused with [`count`](Heap::count), which returns the count of **pointers** allocated within the heap.
 	///
 	/// # Examples
 	///
 	/// ```
 	/// # use halloc::Heap;
 	/// # use std::alloc::Layout;
-	/// # use std::ptr::NonNull;
-	/// # use std::panic::catch_unwind;
-	/// let mut heap = Heap::new(1);
+	/// let mut heap = Heap::new(10); // Create a heap with enough space for 10 pointers
+	/// let layout = Layout::new::<i32>();
 	///
-	/// let layout = Layout::new::<u8>();
-	/// let ptr: NonNull<u8> = heap.alloc(layout);
+	/// for _ in 0..10 {
+	///     heap.alloc(layout);
+	/// }
 	///
-	/// // Do some operations on the data...
-	/// // e.g., *ptr.as_ptr() = 5;
+	/// assert_eq!(heap.size(), 40); // Each `i32` is 4 bytes
+	/// ```
+	pub fn size(&self) -> usize { self.stats.current_bytes }
+
+	/// Returns the count of pointers contained within the [`Heap`].
 	///
-	/// heap.dealloc(ptr, layout);
+	/// Not to be confused with [`size`](Heap::size), which returns the count of **bytes** contained within all the allocated pointers.
+	///
+	/// # Examples
 	///
-	/// // unsafe { *ptr.as_ptr() = 42 } // We no longer own this memory location, so accessing it is a big no-no!
 	/// ```
-	pub fn dealloc(&mut self, ptr: NonNull<u8>, layout: Layout) {
-		unsafe { std::alloc::dealloc(ptr.as_ptr(), layout) }
-		self.ptrs.retain(|(p, _)| *p != ptr);
-	}
+	/// # use halloc::Heap;
+	/// # use std::alloc::Layout;
+	/// let mut heap = Heap::new(3); // Create a heap with enough space for 3 pointers
+	/// let layout = Layout::new::<i32>();
+	///
+	/// let _ptr1 = heap.alloc(layout);
+	/// let _ptr2 = heap.alloc(layout);
+	/// let _ptr3 = heap.alloc(layout);
+	///
+	/// assert_eq!(heap.count(), 3);
+	/// ```
+	pub fn count(&self) -> usize { self.records.len() }
 
-	/// Returns a copy of all the bytes contained within the [`Heap`].
+	/// Returns a snapshot of the allocation statistics of the [`Heap`].
 	///
-	/// Note that if you only need the count of contained bytes, you should use [`size`](Heap::size) instead.
+	/// The statistics are updated on every allocation and deallocation, so taking a snapshot is cheap.
 	///
 	/// # Examples
 	///
 	/// ```
 	/// # use halloc::Heap;
 	/// # use std::alloc::Layout;
-	/// # use std::ptr::NonNull;
-	/// let mut heap = Heap::new(1);
-	///
-	/// let layout = Layout::new::<i32>();
-	/// let ptr: NonNull<u8> = heap.alloc(layout);
-	/// let i32_ptr = ptr.cast::<i32>().as_ptr();
+	/// let mut heap = Heap::new(2);
 	///
-	/// unsafe { *i32_ptr = 42 }
+	/// let ptr = heap.alloc(Layout::new::<u64>());
+	/// let _ptr = heap.alloc(Layout::new::<u32>());
+	/// heap.dealloc(ptr, Layout::new::<u64>());
 	///
-	/// let bytes = heap.bytes();
-	/// assert!(
-	///     bytes == vec![42, 0, 0, 0] ||
-	///     bytes == vec![0, 0, 0, 42]
-	/// );
+	/// let stats = heap.stats();
+	/// assert_eq!(stats.current_bytes, 4);
+	/// assert_eq!(stats.peak_bytes, 12);
+	/// assert_eq!(stats.total_allocs, 2);
+	/// assert_eq!(stats.total_deallocs, 1);
 	/// ```
-	pub fn bytes(&self) -> Vec<u8> {
-		// Creating the resulting bytes vector
-		let mut bytes = Vec::with_capacity(self.size());
+	pub fn stats(&self) -> HeapStats { self.stats.clone() }
+
+	/// Gathers the utilization of the chunks that the heap allocates from.
+	///
+	/// Returns [`None`] if the backend does not manage chunks of its own, see [`RawBackend::chunks`].
+	#[cfg(feature = "alloc")]
+	pub fn fragmentation(&self) -> Option<Fragmentation> {
+		self.backend.chunks().map(Fragmentation::of)
+	}
+}
+
+impl<B: RawBackend> Drop for Heap<B> {
+	fn drop(&mut self) {
+		let mut allocations = core::mem::take(&mut self.records);
+
+		// The allocations are no longer counted by the parent memories
+		for allocation in allocations.iter() {
+			let layout = unsafe { allocation.as_ref() }.layout;
+			self.budget.release(layout.size(), 1);
+		}
+
+		// Allocations that are still referenced (e.g., by promoted mutators) are leaked, so that they stay usable
+		allocations.append(&mut self.detached);
+		let referenced = allocations.extract(Allocation::is_referenced);
+
+		// Their mutators must no longer access the heap
+		for allocation in referenced.iter() {
+			if let Some(slot) = unsafe { allocation.as_ref() }.slot() {
+				slot.orphan();
+			}
+		}
+
+		mark_taken(&allocations);
+		self.destroy_in_place(allocations);
+	}
+}
+
+/// Marks the provided allocations as taken from their heap, so that their mutators no longer deallocate them.
+fn mark_taken(allocations: &List) {
+	for mut allocation in allocations.iter() {
+		unsafe { allocation.as_mut() }.state = State::Taken;
+	}
+}
+
+/// Drops the values of the provided allocations that have known destructors.
+fn drop_values(allocations: &List) {
+	for allocation in allocations.iter() {
+		let record = unsafe { allocation.as_ref() };
+
+		if let Some(drop_glue) = record.drop_glue {
+			unsafe { drop_glue.drop_value(record.ptr) }
+		}
+	}
+}
+
+/// Signals that memory for the provided [`Layout`] could not be allocated.
+#[track_caller]
+fn alloc_failed(layout: Layout) -> ! {
+	#[cfg(feature = "alloc")]
+	alloc::alloc::handle_alloc_error(layout);
+
+	#[cfg(not(feature = "alloc"))]
+	panic!("Memory allocation of {} bytes failed", layout.size())
+}
+
+/// Drops the values of the provided allocations and deallocates their memory.
+///
+/// The values are dropped without holding the heap lock, since their destructors might use the heap as well.
+pub(crate) fn destroy<B: RawBackend>(heap: &Mutex<Heap<B>>, mut allocations: List) {
+	drop_values(&allocations);
+
+	// Deallocating the memory all at once
+	let mut heap = match heap.lock() {
+		Ok(lock) => lock,
+		Err(_) => {
+			#[cfg(feature = "std")]
+			eprintln!("Heap lock failed");
+			return;
+		}
+	};
+
+	while let Some(allocation) = allocations.pop() {
+		heap.free_allocation(allocation);
+	}
+}
+
+#[derive(Debug)]
+/// A wrapper around a [`NonNull`] pointer to allow safe interaction with [`Heap`] and [`Memory`].
+pub struct HeapMutator<'heap, T: Allocatable + ?Sized, B: RawBackend = DefaultBackend> {
+	/// Pointer to the allocated memory on the heap, as of its allocation
+	///
+	/// Only its metadata (e.g., the length of a slice) is used, since the value might have been moved since then.
+	/// The current address is kept by the slot
+	pub(crate) ptr: NonNull<T>,
+
+	/// Reference to the heap
+	pub(crate) heap: &'heap Mutex<Heap<B>>,
+
+	/// Cache of the memory that the heap belongs to, if any, which is settled whenever the heap is locked
+	pub(crate) cache: Option<&'heap Cache>,
+
+	/// Indicates whether the memory that the mutator is holding should be deallocated
+	///
+	/// Mutators of [`Scope`](crate::Scope) allocations are always marked as such, since the scope deallocates their memory
+	pub(crate) deallocated: bool,
+
+	/// State of the allocation shared with the heap, which the mutator holds a reference to
+	pub(crate) slot: NonNull<Slot>
+}
+
+impl<'heap, T: Allocatable + ?Sized, B: RawBackend> HeapMutator<'heap, T, B> {
+	/// Instantiates a new mutator without checking the pointer for validity.
+	///
+	/// # Panics
+	///
+	/// Panics if the backend of the heap is exhausted, since the state shared by the mutators of the value
+	/// is allocated from it.
+	///
+	/// # Safety
+	///
+	/// This function is **only** safe if the caller first makes sure that the pointer is valid (non-null, writeable, correct alignment and size, etc.),
+	/// and that it has been allocated by the provided heap (e.g., with [`Heap::alloc`]) and not deallocated since.
+	pub unsafe fn new_unchecked(ptr: NonNull<T>, heap: &'heap Mutex<Heap<B>>) -> Self {
+		let slot = unsafe {
+			heap.lock()
+				.expect("Heap lock failed")
+				.slot(ptr.cast::<u8>())
+		};
+
+		// The lock has been released already, so that the heap is not poisoned
+		match slot {
+			Ok(slot) => unsafe { Self::from_slot(ptr, heap, None, slot) },
+			Err(error) => panic!("{error}")
+		}
+	}
+
+	/// Instantiates a new mutator with the [`Slot`] of the allocation, adding a reference to it.
+	///
+	/// # Safety
+	///
+	/// See [`new_unchecked`](HeapMutator::new_unchecked). The slot must belong to the allocation, and must not have been deallocated.
+	/// The cache must be the one of the memory that the heap belongs to, if any.
+	pub(crate) unsafe fn from_slot(
+		ptr: NonNull<T>,
+		heap: &'heap Mutex<Heap<B>>,
+		cache: Option<&'heap Cache>,
+		slot: NonNull<Slot>
+	) -> Self {
+		unsafe { slot.as_ref() }.add_reference();
+
+		Self {
+			ptr,
+			heap,
+			cache,
+			deallocated: false,
+			slot
+		}
+	}
+
+	/// Acquires the heap lock, settling the cache of the memory first.
+	fn lock(&self) -> Option<MutexGuard<'heap, Heap<B>>> { cache::lock(self.heap, self.cache) }
+
+	/// Gets the state of the allocation shared with the heap.
+	pub(crate) fn slot(&self) -> &Slot { unsafe { self.slot.as_ref() } }
+
+	/// Gets the pointer to the current address of the value.
+	pub(crate) fn current(&self) -> NonNull<T> { with_address(self.ptr, self.slot().address()) }
+
+	/// Gets the pointer to the value, making sure that the allocation has not been invalidated.
+	pub(crate) fn checked_ptr(&self) -> NonNull<T> {
+		assert!(
+			self.is_valid(),
+			"Use of a mutator whose allocation has been invalidated"
+		);
+
+		self.current()
+	}
+
+	/// Gets the pointer to the value, pinning it in place since a reference to it is about to be handed out.
+	fn pinned_ptr(&self) -> NonNull<T> {
+		// Reading the address before pinning the value is only sound because the memory is `!Sync` (and mutators are `!Send`),
+		// so the value cannot be moved by another thread in between (see `Memory::compact`)
+		let ptr = self.checked_ptr();
+		self.slot().pin();
+
+		ptr
+	}
+
+	/// Records the value in the undo journal of the active [`Transaction`](crate::Transaction) before it is mutated in place,
+	/// if the value is watched by one.
+	fn before_mutation(&self) {
+		// There is usually no active transaction, so the heap does not have to be locked
+		#[cfg(feature = "alloc")]
+		if self.slot().take_journaled() {
+			self.journal_value();
+		}
+	}
 
-		for (ptr, layout) in &self.ptrs {
-			// Getting the pointer data
-			let data_slice = unsafe { std::slice::from_raw_parts(ptr.as_ptr(), layout.size()) };
+	/// Records the value in the undo journal of the active [`Transaction`](crate::Transaction).
+	#[cfg(feature = "alloc")]
+	fn journal_value(&self) {
+		let recorded = self
+			.lock()
+			.expect("Heap lock failed")
+			.journal_value(self.current().cast::<u8>());
 
-			// Appending to the result
-			bytes.extend_from_slice(data_slice);
+		// Values that are not plain data cannot be restored once they are changed in place, so the transaction stops watching them:
+		// replacing them afterwards would otherwise restore a value that was already changed (e.g., the default left by `take`)
+		if !recorded {
+			self.slot().unwatch();
 		}
-
-		bytes
 	}
 
-	/// Returns the count of bytes contained within all the allocated pointers.
+	/// Shows whether the allocation that the mutator is pointing to is still valid.
 	///
-	/// Not to be confused with [`count`](Heap::count), which returns the count of **pointers** allocated within the heap.
+	/// Allocations are invalidated by [`Memory::reset`](crate::Memory::reset) (or [`Heap::clear`]) while mutators still point to them.
+	/// Any use of the value of an invalidated mutator panics.
 	///
 	/// # Examples
 	///
 	/// ```
-	/// # use halloc::Heap;
-	/// # use std::alloc::Layout;
-	/// let mut heap = Heap::new(10); // Create a heap with enough space for 10 pointers
-	/// let layout = Layout::new::<i32>();
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// let mutator = memory.alloc(5);
 	///
-	/// for _ in 0..10 {
-	///     heap.alloc(layout);
-	/// }
+	/// assert!(mutator.is_valid());
 	///
-	/// assert_eq!(heap.size(), 40); // Each `i32` is 4 bytes
+	/// memory.reset();
+	/// assert!(!mutator.is_valid());
 	/// ```
-	pub fn size(&self) -> usize {
-		// Summating the layout sizes for all currently allocated pointers
-		self.ptrs
-			.iter()
-			.map(|(_, layout)| layout.size())
-			.sum::<usize>()
+	pub fn is_valid(&self) -> bool { !self.slot().is_invalidated() }
+
+	/// Gets an immutable reference to the value that the mutator is pointing to.
+	///
+	/// This pins the value in place, so that it is no longer moved by [`Memory::compact`]
+	/// (see [`unpin`](HeapMutator::unpin)). Use [`with`](HeapMutator::with) to keep the value movable.
+	pub fn get(&self) -> &T { unsafe { self.pinned_ptr().as_ref() } }
+
+	/// Gets a mutable reference to the value that the mutator is pointing to.
+	///
+	/// Like [`get`](HeapMutator::get), this pins the value in place. Use [`with_mut`](HeapMutator::with_mut) to keep the value movable.
+	pub fn get_mut(&mut self) -> &mut T {
+		assert!(self.can_dealloc(), "Mutable reference get failed");
+		let mut ptr = self.pinned_ptr();
+		self.before_mutation();
+
+		unsafe { ptr.as_mut() }
 	}
 
-	/// Returns the count of pointers contained within the [`Heap`].
+	/// Calls the provided function with a reference to the value that the mutator is pointing to.
 	///
-	/// Not to be confused with [`size`](Heap::size), which returns the count of **bytes** contained within all the allocated pointers.
+	/// Unlike [`get`](HeapMutator::get), this does not pin the value: it is only kept in place until the function returns,
+	/// so that [`Memory::compact`] can move it afterwards.
 	///
 	/// # Examples
 	///
 	/// ```
-	/// # use halloc::Heap;
-	/// # use std::alloc::Layout;
-	/// let mut heap = Heap::new(3); // Create a heap with enough space for 3 pointers
-	/// let layout = Layout::new::<i32>();
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// let name = memory.alloc(String::from("player"));
 	///
-	/// let _ptr1 = heap.alloc(layout);
-	/// let _ptr2 = heap.alloc(layout);
-	/// let _ptr3 = heap.alloc(layout);
+	/// let len = name.with(|name| {
+	///     // The value is not moved while it is borrowed
+	///     memory.compact();
+	///     name.len()
+	/// });
 	///
-	/// assert_eq!(heap.count(), 3);
+	/// assert_eq!(len, 6);
+	/// assert!(!name.is_pinned());
 	/// ```
-	pub fn count(&self) -> usize { self.ptrs.len() }
-}
+	pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+		// Like in `pinned_ptr`, the address can only be read before the borrow starts since the memory is `!Sync`
+		let ptr = self.checked_ptr();
+		let _borrow = Borrow::new(self.slot());
 
-#[derive(Debug)]
-/// A wrapper around a [`NonNull`] pointer to allow safe interaction with [`Heap`] and [`Memory`].
-pub struct HeapMutator<'heap, T: Allocatable> {
-	/// Pointer to the allocated memory on the heap
-	pub(crate) ptr: Arc<NonNull<T>>,
+		f(unsafe { ptr.as_ref() })
+	}
 
-	/// Reference to the heap
-	pub(crate) heap: &'heap Mutex<Heap>,
+	/// Calls the provided function with a mutable reference to the value that the mutator is pointing to.
+	///
+	/// Like [`with`](HeapMutator::with), this does not pin the value.
+	pub fn with_mut<R>(&mut self, f: impl FnOnce(&mut T) -> R) -> R {
+		assert!(self.can_dealloc(), "Mutable reference get failed");
+		let mut ptr = self.checked_ptr();
+		self.before_mutation();
+		let _borrow = Borrow::new(self.slot());
 
-	/// Indicates whether the memory that the mutator is holding should be deallocated
-	pub(crate) deallocated: bool
-}
+		f(unsafe { ptr.as_mut() })
+	}
 
-impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
-	/// Instantiates a new mutator without checking the pointer for validity.
+	/// Shows whether the value is pinned in place, since a reference to it has been handed out
+	/// (e.g., by [`get`](HeapMutator::get) or [`Deref`](core::ops::Deref)).
 	///
-	/// # Safety
+	/// Pinned values are not moved by [`Memory::compact`].
+	pub fn is_pinned(&self) -> bool { self.slot().is_pinned() }
+
+	/// Allows the value to be moved by [`Memory::compact`] again, once the references handed out by the mutator are no longer used.
 	///
-	/// This function is **only** safe if the caller first makes sure that the pointer is valid (non-null, writeable, correct alignment and size, etc.)
-	pub unsafe fn new_unchecked(ptr: NonNull<T>, heap: &'heap Mutex<Heap>) -> Self {
-		Self {
-			ptr: Arc::new(ptr),
-			heap,
-			deallocated: false
+	/// Since the mutator is borrowed mutably, none of its references can still be in use.
+	/// This is therefore only possible if there are no other mutators pointing to the value (e.g., clones),
+	/// which the result of this function indicates.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// let mut scores = memory.alloc([0u32; 8]);
+	///
+	/// assert_eq!(scores[0], 0);
+	/// assert!(scores.is_pinned());
+	///
+	/// assert!(scores.unpin());
+	/// assert!(!scores.is_pinned());
+	///
+	/// let shared = scores.clone();
+	/// assert!(!scores.unpin());
+	/// # drop(shared);
+	/// ```
+	pub fn unpin(&mut self) -> bool {
+		if !self.can_dealloc() {
+			return false;
 		}
-	}
-
-	/// Gets an immutable reference to the value that the mutator is pointing to.
-	pub fn get(&self) -> &T { unsafe { (*self.ptr).as_ref() } }
 
-	/// Gets a mutable reference to the value that the mutator is pointing to.
-	pub fn get_mut(&mut self) -> &mut T {
-		let ptr_ref = Arc::get_mut(&mut self.ptr).expect("Mutable reference get failed");
-		unsafe { ptr_ref.as_mut() }
+		self.slot().unpin();
+		true
 	}
 
 	/// Clones the value that the mutator is pointing to.
 	///
 	/// This requires the implementation of [`ToOwned`] for the type of the value that the mutator is holding.
+	#[cfg(feature = "alloc")]
 	pub fn get_owned(&self) -> T
 	where
-		T: ToOwned<Owned = T> {
-		self.get().to_owned()
+		T: ToOwned<Owned = T> + Sized {
+		self.with(T::to_owned)
 	}
 
 	/// Takes the value that the mutator is pointing to, leaving a default one in its place.
 	///
 	/// This requires the implementation of [`Default`] for the type of the value that the mutator is holding.
+	/// Since the value is handed out, a failed [`Transaction`](crate::Transaction) can only put it back if it is plain data
+	/// (see [`Memory::transaction`](crate::Memory::transaction)).
 	///
 	/// # Examples
 	///
@@ -296,11 +1981,52 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	where
 		T: Default {
 		let default = T::default();
-		unsafe { std::ptr::replace(self.ptr.as_ptr(), default) }
+
+		let ptr = self.checked_ptr();
+		self.before_mutation();
+
+		unsafe { core::ptr::replace(ptr.as_ptr(), default) }
 	}
 
 	/// Writes the target value to where the mutator is pointing to.
-	pub fn write(&mut self, value: T) { unsafe { std::ptr::write(self.ptr.as_ptr(), value) } }
+	///
+	/// The previous value is not dropped, unless a [`Transaction`](crate::Transaction) is active: it is then moved into
+	/// the undo journal, so that any value can be restored if the transaction fails (see [`Memory::transaction`](crate::Memory::transaction)),
+	/// and dropped once the transaction is committed.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// let mut name = memory.alloc(String::from("player"));
+	///
+	/// let result = memory.transaction(|_| {
+	///     name.write(String::from("winner"));
+	///     Err::<(), _>("the match was cancelled")
+	/// });
+	///
+	/// assert!(result.is_err());
+	/// assert_eq!(*name, "player");
+	/// ```
+	pub fn write(&mut self, value: T)
+	where
+		T: Sized {
+		let ptr = self.checked_ptr();
+
+		// There is usually no active transaction, so the heap does not have to be locked
+		#[cfg(feature = "alloc")]
+		if self.slot().is_watched() {
+			let previous = unsafe { core::ptr::replace(ptr.as_ptr(), value) };
+
+			self.lock()
+				.expect("Heap lock failed")
+				.journal_replaced(ptr.cast::<u8>(), previous);
+			return;
+		}
+
+		unsafe { core::ptr::write(ptr.as_ptr(), value) }
+	}
 
 	/// Casts the mutator **and** the underlying value to the provided type (`U`), reallocating it, and calling the destructor of the previous value.
 	///
@@ -349,43 +2075,93 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	/// assert_eq!(b.other_something, 42);
 	/// ```
 	///
+	/// Casts are limited like any other allocation:
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// # use std::panic::{catch_unwind, AssertUnwindSafe};
+	/// let memory = Memory::with_limit(8);
+	/// let small = memory.alloc(1u32);
+	///
+	/// // The new value would not fit next to the previous one
+	/// assert!(catch_unwind(AssertUnwindSafe(|| unsafe { small.cast::<u64>() })).is_err());
+	/// assert_eq!(memory.count(), 0);
+	/// ```
+	///
+	/// # Panics
+	///
+	/// Panics if the new allocation would exceed the byte limit of the memory (see [`Memory::with_limit`]),
+	/// or if the backend is exhausted.
+	///
 	/// # Safety
 	///
 	/// This type of casting is generally safe when casting between types of identical structure. Otherwise, it is highly discouraged.
-	pub unsafe fn cast<U: Allocatable>(self) -> HeapMutator<'heap, U> {
-		let mut heap = self.heap.lock().expect("Heap lock failed");
+	pub unsafe fn cast<U: Allocatable>(self) -> HeapMutator<'heap, U, B>
+	where
+		T: Sized {
+		let old_ptr = self.checked_ptr();
+		let mut heap = self.lock().expect("Heap lock failed");
 
 		// Getting layouts for both `T` and `U`
 		let layout_t = Layout::new::<T>();
 		let layout_u = Layout::new::<U>();
 
 		// Deciding the largest layout for the new mutator
-		let new_layout_size = std::cmp::max(layout_t.size(), layout_u.size());
+		let new_layout_size = core::cmp::max(layout_t.size(), layout_u.size());
 		let new_layout = Layout::from_size_align(new_layout_size, layout_u.align())
 			.expect("Layout creation failed");
 
 		// Allocating a new pointer and casting it to `U`
-		let new_ptr = heap.alloc(new_layout).cast::<U>();
+		let new_ptr = match heap.try_alloc_tagged(new_layout, Some(TypeTag::of::<U>())) {
+			Ok(ptr) => ptr.cast::<U>(),
+			Err(error) => {
+				// Releasing the lock before panicking, so that the heap is not poisoned
+				drop(heap);
+				panic!("{error}");
+			}
+		};
+
+		// The new allocation belongs to the same scope as the current one
+		let (scope, from) = {
+			let record = unsafe { heap.record_mut(old_ptr.cast::<u8>()) };
+			(record.scope, AllocationInfo::of(record))
+		};
+
+		let record = unsafe { heap.record_mut(new_ptr.cast::<u8>()) };
+		record.scope = scope;
+		record.drop_glue = DropGlue::of::<U>();
+		let to = AllocationInfo::of(record);
+		heap.notify(|observer| observer.on_cast(&from, &to));
+
+		// The slot has been allocated along with the new allocation
+		let slot = unsafe { heap.slot(new_ptr.cast::<u8>()) }.expect("Slot allocation failed");
+		let crossed = heap.budget.crossed();
 
 		// Heap lock is no longer needed, dropping it to prevent deadlocks during deallocation,
 		// since the `Drop` implementation of `HeapMutator` also requires a heap lock
 		drop(heap);
+		crossed.call();
 
 		// Taking the heap reference
 		let heap_ref = self.heap;
+		let cache = self.cache;
+		let deallocated = self.deallocated;
 
 		unsafe {
 			// Reading the value of the current pointer and casting it to `U`
-			let old_ptr_val = std::ptr::read(self.ptr.as_ptr().cast::<U>());
+			let old_ptr_val = core::ptr::read(old_ptr.as_ptr().cast::<U>());
 
 			// Writing the old value to the new pointer
-			std::ptr::write(new_ptr.as_ptr(), old_ptr_val);
+			core::ptr::write(new_ptr.as_ptr(), old_ptr_val);
 		}
 
 		// Deallocating the old pointer
 		self.dealloc();
 
-		unsafe { HeapMutator::new_unchecked(new_ptr, heap_ref) }
+		let mut mutator = unsafe { HeapMutator::from_slot(new_ptr, heap_ref, cache, slot) };
+		mutator.deallocated = deallocated;
+
+		mutator
 	}
 
 	/// An alternative to [`cast`](HeapMutator::cast) that **ignores all bare-minimum safety precautions**.
@@ -402,13 +2178,81 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	/// # Safety
 	///
 	/// There are no safety guarantees provided by this function.
-	pub unsafe fn cast_unchecked<U: Allocatable>(mut self) -> HeapMutator<'heap, U> {
-		// This should be used to indicate if the memory for that address was already deallocated,
-		// but in this context we are passing that responsibility to the new mutator.
-		// This will deallocate the old mutator at the end of the function, **but not its value**
-		self.deallocated = true;
+	pub unsafe fn cast_unchecked<U: Allocatable>(self) -> HeapMutator<'heap, U, B> {
+		let ptr = self.checked_ptr().cast::<U>();
+
+		// The heap now has to drop the value as `U`
+		if let Some(mut heap) = self.lock() {
+			let from = AllocationInfo::of(unsafe { heap.record_mut(ptr.cast::<u8>()) });
+			unsafe { heap.set_contents(ptr.cast::<u8>(), DropGlue::of::<U>(), TypeTag::of::<U>()) };
+
+			let to = AllocationInfo::of(unsafe { heap.record_mut(ptr.cast::<u8>()) });
+			heap.notify(|observer| observer.on_cast(&from, &to));
+		}
+
+		self.rebind(ptr)
+	}
+
+	/// Converts the mutator into a mutator over a dynamically sized type (e.g., a trait object), keeping the value in place.
+	///
+	/// The provided `coerce` function receives the pointer to the value and should return the same pointer
+	/// coerced to `U`, which stores the pointer metadata (such as the vtable) within the new mutator.
+	/// The value is later dropped and deallocated through that metadata.
+	///
+	/// Prefer the [`unsize!`](crate::unsize) macro, which performs the coercion safely.
+	///
+	/// # Panics
+	///
+	/// Panics if the mutator has existing clones, or if `coerce` returns a pointer to a different address.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Allocatable, Memory, HeapMutator};
+	/// trait Shape {
+	///     fn area(&self) -> f32;
+	/// }
+	///
+	/// struct Square(f32);
+	///
+	/// impl Shape for Square {
+	///     fn area(&self) -> f32 { self.0 * self.0 }
+	/// }
+	///
+	/// impl Allocatable for Square {}
+	/// impl Allocatable for dyn Shape {}
+	///
+	/// let memory = Memory::new();
+	///
+	/// let square = memory.alloc(Square(3.0));
+	/// let shape: HeapMutator<dyn Shape> = unsafe { square.unsize(|ptr| ptr as *mut dyn Shape) };
+	///
+	/// assert_eq!(shape.area(), 9.0);
+	/// assert_eq!(memory.size(), 4);
+	/// ```
+	///
+	/// # Safety
+	///
+	/// The pointer returned by `coerce` must point to the same value, i.e., only its metadata may be changed.
+	pub unsafe fn unsize<U: Allocatable + ?Sized>(
+		self,
+		coerce: impl FnOnce(*mut T) -> *mut U
+	) -> HeapMutator<'heap, U, B> {
+		// Clones would keep deallocating the memory through the old pointer type
+		assert!(
+			self.can_dealloc(),
+			"Cannot unsize a mutator that has existing clones"
+		);
 
-		unsafe { HeapMutator::new_unchecked(self.ptr.cast::<U>(), self.heap) }
+		let old_ptr = self.checked_ptr().as_ptr();
+		let ptr = coerce(old_ptr);
+		assert_eq!(
+			ptr.cast::<u8>(),
+			old_ptr.cast::<u8>(),
+			"Unsizing coercion changed the pointer address"
+		);
+
+		self.rebind(unsafe { NonNull::new_unchecked(ptr) })
 	}
 
 	/// Shows whether the mutator can be deallocated.
@@ -456,7 +2300,7 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	///
 	/// assert_eq!(m1.ref_count(), 1);
 	/// ```
-	pub fn ref_count(&self) -> usize { Arc::strong_count(&self.ptr) }
+	pub fn ref_count(&self) -> usize { self.slot().references() }
 
 	/// Deallocates the mutator along with the contained value, calling [`drop`] on the value.
 	///
@@ -468,7 +2312,7 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	/// - the heap lock could not be acquired
 	/// - there are existing references to the value (in the form of other [`HeapMutator`]s)
 	/// - the mutator has already been marked as dropped
-	pub fn dealloc(mut self) -> bool { self.dealloc_internal() }
+	pub fn dealloc(self) -> bool { ManuallyDrop::new(self).finish() }
 
 	/// Promotes the mutator to a `'static` lifetime, decoupling it from the original memory context.
 	///
@@ -477,6 +2321,8 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	/// - Once promoted, it is no longer guaranteed that the memory will be deallocated.
 	/// - The caller must ensure that the memory referenced by the mutator remains valid for
 	///   the entire duration of the program to avoid undefined behavior.
+	/// - The [`RawBackend`] of the memory is dropped along with it, so any memory that it hands out
+	///   (e.g., a buffer that it owns) must outlive the promoted mutator as well.
 	///
 	/// # Undefined behavior
 	///
@@ -494,23 +2340,219 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	///
 	/// assert_eq!(*m, 5);
 	/// ```
-	pub unsafe fn promote(mut self) -> HeapMutator<'static, T> {
-		self.deallocated = true;
+	pub unsafe fn promote(self) -> HeapMutator<'static, T, B> {
+		// The reference of the mutator to its slot is handed over to the promoted one
+		let this = ManuallyDrop::new(self);
 
-		// SAFETY: We only transmute the lifetime
-		let heap_static =
-			unsafe { std::mem::transmute::<&'heap Mutex<Heap>, &'static Mutex<Heap>>(self.heap) };
+		// SAFETY: We only transmute the lifetimes
+		let heap_static = unsafe {
+			core::mem::transmute::<&'heap Mutex<Heap<B>>, &'static Mutex<Heap<B>>>(this.heap)
+		};
+		let cache_static = this
+			.cache
+			.map(|cache| unsafe { core::mem::transmute::<&'heap Cache, &'static Cache>(cache) });
 
 		HeapMutator {
-			ptr: Arc::clone(&self.ptr),
+			ptr: this.ptr,
 			heap: heap_static,
-			deallocated: false
+			cache: cache_static,
+			deallocated: false,
+			slot: this.slot
+		}
+	}
+
+	/// Moves the value to the provided [`Memory`], returning a mutator that is tied to that memory instead.
+	///
+	/// The value stays in place: only the record of its allocation is moved from one heap to the other,
+	/// so that it is counted (see [`Memory::size`] and [`Memory::count`]) and deallocated by the new memory instead.
+	/// Values of a [`Scope`](crate::Scope) are removed from it, so that they are no longer deallocated when it ends.
+	/// If the memories allocate from different backends (see [`RawBackend::SHARED`]), the bytes of the value
+	/// are moved to a new allocation of the new memory instead.
+	///
+	/// The mutator is given back if it cannot be transferred, which happens if it has been cloned
+	/// (see [`can_dealloc`](HeapMutator::can_dealloc)), or if the value would exceed the byte limit
+	/// (or the backend) of the new memory.
+	///
+	/// # Panics
+	///
+	/// Panics if the allocation of the mutator has been invalidated (see [`is_valid`](HeapMutator::is_valid)).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// let cache = Memory::new();
+	/// let requests = Memory::new();
+	///
+	/// let response: HeapMutator<str> = requests.scope(|scope| {
+	///     let body = scope.alloc_str("cached response");
+	///
+	///     // Keeping the value around after the request is handled
+	///     body.transfer_to(&cache).unwrap()
+	/// });
+	///
+	/// assert_eq!(&*response, "cached response");
+	/// assert_eq!(requests.count(), 0);
+	/// assert_eq!(cache.count(), 1);
+	///
+	/// // Shared values cannot be transferred
+	/// let shared = response.clone();
+	/// let response = response.transfer_to(&requests).unwrap_err();
+	///
+	/// assert_eq!(response.ref_count(), 2);
+	/// # drop(shared);
+	/// ```
+	#[track_caller]
+	pub fn transfer_to<'memory, C: RawBackend>(
+		self,
+		memory: &'memory Memory<C>
+	) -> Result<HeapMutator<'memory, T, C>, Self> {
+		let ptr = self.checked_ptr().cast::<u8>();
+
+		if !self.can_dealloc() {
+			return Err(self);
+		}
+
+		let heap = self.heap;
+
+		if TypeId::of::<B>() != TypeId::of::<C>() || !B::SHARED {
+			return self.move_to(memory);
+		}
+
+		// The backends are the same, so the heaps are of the same type as well
+		let target = unsafe { &*(&memory.heap as *const Mutex<Heap<C>>).cast::<Mutex<Heap<B>>>() };
+
+		if core::ptr::eq(heap, target) {
+			// Only escaping the value from its scope
+			unsafe { self.lock().expect("Heap lock failed").record_mut(ptr) }.scope = None;
+		} else {
+			// Removing the record from the current heap, along with the memory that holds it
+			let allocation = unsafe { self.lock().expect("Heap lock failed").remove_record(ptr) };
+			let scope = core::mem::take(unsafe { &mut (*allocation.as_ptr()).scope });
+
+			let mut target = cache::lock(target, Some(&memory.cache)).expect("Heap lock failed");
+			let result = unsafe { target.try_insert_record(allocation) };
+			let crossed = target.budget.crossed();
+			drop(target);
+
+			if result.is_err() {
+				// Putting the record back, so that the value is not lost
+				unsafe { (*allocation.as_ptr()).scope = scope };
+				unsafe {
+					self.lock()
+						.expect("Heap lock failed")
+						.insert_record(allocation)
+				};
+
+				return Err(self);
+			}
+
+			// The transactions of the new memory do not watch the value
+			#[cfg(feature = "alloc")]
+			self.slot().unwatch();
+			crossed.call();
+		}
+
+		// The new mutator is now responsible for deallocating the memory, and takes over the reference to the slot
+		let this = ManuallyDrop::new(self);
+
+		Ok(HeapMutator {
+			ptr: this.ptr,
+			heap: &memory.heap,
+			cache: Some(&memory.cache),
+			deallocated: false,
+			slot: this.slot
+		})
+	}
+
+	/// Moves the bytes of the value to a new allocation of the provided [`Memory`], for [`transfer_to`](HeapMutator::transfer_to)
+	/// between memories whose backends cannot deallocate the memory of each other.
+	///
+	/// The new allocation is recorded as made by the caller.
+	#[track_caller]
+	fn move_to<'memory, C: RawBackend>(
+		mut self,
+		memory: &'memory Memory<C>
+	) -> Result<HeapMutator<'memory, T, C>, Self> {
+		let old_ptr = self.current();
+
+		let (layout, type_tag, drop_glue) = {
+			let mut heap = self.lock().expect("Heap lock failed");
+			let record = unsafe { heap.record_mut(old_ptr.cast::<u8>()) };
+
+			(record.layout, record.type_tag, record.drop_glue)
+		};
+
+		let mut target = memory.get_heap();
+
+		let Ok(new_ptr) = target.try_alloc_tagged(layout, type_tag) else {
+			drop(target);
+			return Err(self);
+		};
+
+		unsafe {
+			core::ptr::copy_nonoverlapping(
+				old_ptr.as_ptr().cast::<u8>(),
+				new_ptr.as_ptr(),
+				layout.size()
+			);
+		}
+
+		let new_record = unsafe { target.record_mut(new_ptr) };
+		new_record.drop_glue = drop_glue;
+
+		// The value existed before the transaction of the new memory, so it is kept if that fails
+		#[cfg(feature = "alloc")]
+		{
+			let id = new_record.id;
+
+			if let Some(journal) = &mut target.journal {
+				journal.forget_alloc(id);
+			}
+		}
+
+		// The slot has been allocated along with the new allocation
+		let slot = unsafe { target.slot(new_ptr) }.expect("Slot allocation failed");
+		let crossed = target.budget.crossed();
+		drop(target);
+
+		// The value has been moved, so the old memory is only deallocated
+		self.lock()
+			.expect("Heap lock failed")
+			.dealloc(old_ptr.cast::<u8>(), layout);
+		crossed.call();
+
+		self.deallocated = true;
+
+		Ok(unsafe {
+			HeapMutator::from_slot(
+				with_address(old_ptr, new_ptr),
+				&memory.heap,
+				Some(&memory.cache),
+				slot
+			)
+		})
+	}
+
+	/// Consumes the mutator, passing the responsibility of deallocating the memory to a new mutator with the provided pointer.
+	///
+	/// The old mutator is not dropped, so that its reference to the slot is handed over to the new mutator.
+	fn rebind<U: Allocatable + ?Sized>(self, ptr: NonNull<U>) -> HeapMutator<'heap, U, B> {
+		let this = ManuallyDrop::new(self);
+
+		HeapMutator {
+			ptr,
+			heap: this.heap,
+			cache: this.cache,
+			deallocated: this.deallocated,
+			slot: this.slot
 		}
 	}
 
-	/// Deallocates the mutator along with the contained value but **does not** consume the mutator.
+	/// Deallocates the mutator along with the contained value but **does not** consume the mutator,
+	/// dropping its reference to the slot of the value.
 	///
-	/// It is only to be used internally, when it is guaranteed that the mutator will be dropped after that.
+	/// It is only to be used internally, when it is guaranteed that the mutator will not be used after that.
 	///
 	/// The result of this function indicates whether the deallocation was successful.
 	///
@@ -518,64 +2560,192 @@ impl<'heap, T: Allocatable> HeapMutator<'heap, T> {
 	/// - the mutator has already been marked as dropped
 	/// - the heap lock was unable to be acquired
 	/// - there are existing references to the value (in the form of other [`HeapMutator`]s)
-	fn dealloc_internal(&mut self) -> bool {
-		// If the stored memory location was already deallocated, we don't need to do anything
-		// except letting Rust deallocate the mutator itself
-		if self.deallocated {
+	fn finish(&mut self) -> bool {
+		// If the stored memory location was already deallocated (or is deallocated by a scope), or if there are any more references
+		// to this memory location (e.g., clones of the mutator), only the reference to the slot is dropped.
+		// The memory is leaked if the heap has been dropped (e.g., after a promotion)
+		if self.deallocated || !self.can_dealloc() || self.slot().is_orphaned() {
+			self.deallocated = true;
+			self.release_slot();
 			return false;
 		}
 
-		// If there are any more references to this memory location, don't deallocate it
-		// This can happen if the mutator has been cloned (or is a clone of the original mutator)
-		if !self.can_dealloc() {
-			return false;
+		self.deallocated = true;
+
+		// Live values of a memory whose cache is enabled are handed over to the cache, without locking the heap
+		if let Some(cache) = self.cache.filter(|cache| cache.is_enabled()) {
+			if unsafe { header(self.current().cast::<u8>()).as_ref() }.state == State::Live {
+				// Calling `drop` on the contained value, which is kept in place meanwhile
+				{
+					let _borrow = Borrow::new(self.slot());
+					unsafe { self.current().as_ptr().drop_in_place() }
+				}
+
+				// The drop may have reset the memory, in which case the heap deals with the record
+				let allocation = header(self.current().cast::<u8>());
+
+				if unsafe { allocation.as_ref() }.state == State::Live {
+					let _ = self.slot().remove_reference();
+					unsafe { cache.release(self.heap, allocation) }
+					return true;
+				}
+
+				return self.deallocate(false);
+			}
 		}
 
+		self.deallocate(true)
+	}
+
+	/// Deallocates the value through the heap, dropping it first if requested.
+	fn deallocate(&self, drop_value: bool) -> bool {
 		// Safely attempting to get the heap lock
-		let mut heap = match self.heap.lock() {
-			Ok(lock) => lock,
-			Err(_) => {
-				eprintln!("Heap lock failed");
-				return false;
-			}
+		let Some(mut heap) = self.lock() else {
+			#[cfg(feature = "std")]
+			eprintln!("Heap lock failed");
+			return false;
 		};
 
-		// Constructing a layout for `T`
-		let layout = Layout::new::<T>();
+		// The reference is dropped while the heap is locked, so that the slot is deallocated along with the memory
+		if self.slot().remove_reference() {
+			// The heap has already deallocated the memory of the invalidated allocation
+			unsafe { heap.deallocate_slot(self.slot) }
+			return false;
+		}
+
+		let ptr = self.current();
+		let record = unsafe { heap.record_mut(ptr.cast::<u8>()) };
+
+		// The memory of invalidated allocations is about to be deallocated by the heap
+		if record.state == State::Taken {
+			return false;
+		}
+
+		let layout = record.layout;
 
 		// Calling `drop` on the contained value
-		unsafe { self.ptr.as_ptr().drop_in_place() }
+		if drop_value {
+			unsafe { ptr.as_ptr().drop_in_place() }
+		}
 
 		// Deallocating the memory
-		heap.dealloc(self.ptr.cast::<u8>(), layout);
-
-		// Marking as deallocated
-		self.deallocated = true;
+		heap.dealloc(ptr.cast::<u8>(), layout);
 
 		true
 	}
+
+	/// Drops the reference of the mutator to the slot of the value, deallocating the slot if it was the last one.
+	fn release_slot(&self) {
+		if !self.slot().remove_reference() {
+			return;
+		}
+
+		// The heap has already deallocated the memory, so the slot was only left to the mutators
+		if let Some(mut heap) = self.lock() {
+			unsafe { heap.deallocate_slot(self.slot) }
+		}
+	}
+}
+
+impl<'heap, T: Allocatable, B: RawBackend> HeapMutator<'heap, MaybeUninit<T>, B> {
+	/// Converts the mutator into a mutator over the initialized value.
+	///
+	/// # Panics
+	///
+	/// Panics if the mutator has existing clones.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// # use std::mem::MaybeUninit;
+	/// let memory = Memory::new();
+	///
+	/// let mut mutator = memory.alloc_uninit::<String>();
+	/// mutator.write(MaybeUninit::new(String::from("initialized")));
+	///
+	/// let mutator: HeapMutator<String> = unsafe { mutator.assume_init() };
+	/// assert_eq!(*mutator, "initialized");
+	/// ```
+	///
+	/// # Safety
+	///
+	/// The value must be fully initialized, see [`MaybeUninit::assume_init`].
+	pub unsafe fn assume_init(self) -> HeapMutator<'heap, T, B> {
+		// Clones would keep treating the value as uninitialized, so it would never be dropped
+		assert!(
+			self.can_dealloc(),
+			"Cannot initialize a mutator that has existing clones"
+		);
+
+		let ptr = self.checked_ptr().cast::<T>();
+
+		// The value can now be dropped by the heap
+		if let Some(mut heap) = self.lock() {
+			unsafe { heap.set_contents(ptr.cast::<u8>(), DropGlue::of::<T>(), TypeTag::of::<T>()) };
+		}
+
+		self.rebind(ptr)
+	}
 }
 
-impl<'heap, T: Allocatable> std::ops::Deref for HeapMutator<'heap, T> {
+impl<'heap, T: Allocatable + ?Sized, B: RawBackend> core::ops::Deref for HeapMutator<'heap, T, B> {
 	type Target = T;
 
 	fn deref(&self) -> &Self::Target { self.get() }
 }
 
-impl<'heap, T: Allocatable> std::ops::DerefMut for HeapMutator<'heap, T> {
+impl<'heap, T: Allocatable + ?Sized, B: RawBackend> core::ops::DerefMut
+	for HeapMutator<'heap, T, B>
+{
 	fn deref_mut(&mut self) -> &mut Self::Target { self.get_mut() }
 }
 
-impl<'heap, T: Allocatable> Clone for HeapMutator<'heap, T> {
+impl<'heap, T: Allocatable + ?Sized, B: RawBackend> Clone for HeapMutator<'heap, T, B> {
 	fn clone(&self) -> Self {
-		Self {
-			ptr: Arc::clone(&self.ptr),
-			heap: self.heap,
-			deallocated: false
-		}
+		let mut clone = unsafe { Self::from_slot(self.ptr, self.heap, self.cache, self.slot) };
+
+		// Clones of scoped mutators are not responsible for deallocating the memory either
+		clone.deallocated = self.deallocated;
+		clone
+	}
+}
+
+impl<'heap, T: Allocatable + ?Sized, B: RawBackend> Drop for HeapMutator<'heap, T, B> {
+	fn drop(&mut self) { self.finish(); }
+}
+
+/// An access to a value that is in progress, which keeps the value in place until it is dropped.
+struct Borrow<'slot> {
+	/// Slot of the accessed value
+	slot: &'slot Slot
+}
+
+impl<'slot> Borrow<'slot> {
+	/// Starts an access to the value of the provided slot.
+	fn new(slot: &'slot Slot) -> Self {
+		slot.borrow();
+		Self { slot }
 	}
 }
 
-impl<'heap, T: Allocatable> Drop for HeapMutator<'heap, T> {
-	fn drop(&mut self) { self.dealloc_internal(); }
+impl Drop for Borrow<'_> {
+	fn drop(&mut self) { self.slot.release() }
+}
+
+/// Replaces the address of the provided pointer while keeping its metadata (e.g., the length of a slice).
+fn with_address<T: ?Sized>(ptr: NonNull<T>, address: NonNull<u8>) -> NonNull<T> {
+	let mut ptr = ptr.as_ptr();
+
+	// Overwriting the data pointer with `address`, so that the result carries the provenance of the new allocation
+	// rather than the one of the old. Pointers with metadata store their data pointer first (like `Rc` relied on
+	// before `with_metadata_of`), and thin pointers consist of it alone
+	unsafe {
+		core::ptr::addr_of_mut!(ptr)
+			.cast::<*mut u8>()
+			.write(address.as_ptr())
+	}
+	debug_assert_eq!(ptr.cast::<u8>(), address.as_ptr());
+
+	unsafe { NonNull::new_unchecked(ptr) }
 }
@@ -0,0 +1,199 @@
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt::{self, Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::num::NonZeroU32;
+use core::ptr::NonNull;
+use std::collections::HashMap;
+
+use crate::memory::Allocator;
+use crate::{AllocError, HeapMutator, RawBackend, SystemBackend};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+/// A compact identifier of a string interned by an [`Interner`].
+///
+/// Symbols are only meaningful to the interner that created them, and they are numbered in the order of interning.
+pub struct Symbol(NonZeroU32);
+
+/// A table of unique strings allocated on a [`Memory`](crate::Memory), created with [`Memory::interner`](crate::Memory::interner)
+/// or [`Scope::interner`](crate::Scope::interner).
+///
+/// Each unique string is stored once, as an allocation of its own, so the interned bytes show up in [`Memory::stats`](crate::Memory::stats)
+/// and heap dumps like any other `str`. The strings are deallocated when the interner is cleared or dropped,
+/// and they can also be deallocated by the memory itself:
+///
+/// - with an interner created from a [`Scope`](crate::Scope), the strings are deallocated when the scope ends, which the interner cannot outlive
+/// - when the memory is reset (see [`Memory::reset`](crate::Memory::reset)), or a transaction that interned them fails,
+///   their symbols no longer resolve, and their memory is released once another string is interned
+///
+/// The strings are never moved by [`Memory::compact`](crate::Memory::compact), and [`Memory::reset_unchecked`](crate::Memory::reset_unchecked)
+/// must not be called while an interner of the memory is alive.
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::Memory;
+/// let memory = Memory::new();
+/// let mut identifiers = memory.interner();
+///
+/// let x = identifiers.intern("x");
+/// let count = identifiers.intern("count");
+///
+/// assert_eq!(identifiers.intern("x"), x);
+/// assert_eq!(identifiers.resolve(count), Some("count"));
+/// assert_eq!(identifiers.len(), 2);
+///
+/// // Each unique string is allocated once
+/// assert_eq!(memory.stats().current_bytes, "x".len() + "count".len());
+///
+/// memory.reset();
+///
+/// assert!(identifiers.is_empty());
+/// assert_eq!(identifiers.resolve(x), None);
+/// ```
+pub struct Interner<'memory, B: RawBackend = SystemBackend> {
+	/// Allocator that the strings are allocated with
+	allocator: Allocator<'memory, B>,
+
+	/// Interned strings, by the indices of their symbols
+	strings: Vec<HeapMutator<'memory, str, B>>,
+
+	/// Symbols of the interned strings
+	symbols: HashMap<Key, Symbol>
+}
+
+/// A pointer to an interned string, which is hashed and compared as the string itself.
+struct Key(NonNull<str>);
+
+impl Symbol {
+	/// Gets the index of the symbol, which is the count of strings interned before it.
+	pub fn index(self) -> usize { self.0.get() as usize - 1 }
+
+	/// Creates the symbol with the provided index, unless it is too large.
+	fn from_index(index: usize) -> Option<Self> {
+		u32::try_from(index + 1)
+			.ok()
+			.and_then(NonZeroU32::new)
+			.map(Self)
+	}
+}
+
+impl<'memory, B: RawBackend> Interner<'memory, B> {
+	/// Creates an empty interner that allocates the strings with the provided [`Allocator`].
+	pub(crate) fn new(allocator: Allocator<'memory, B>) -> Self {
+		Self {
+			allocator,
+			strings: vec![],
+			symbols: HashMap::new()
+		}
+	}
+
+	/// Gets the symbol of the provided string, interning it first if needed.
+	///
+	/// # Panics
+	///
+	/// Panics if the string would exceed the byte limit of the memory (see [`try_intern`](Interner::try_intern)).
+	#[track_caller]
+	pub fn intern(&mut self, string: &str) -> Symbol {
+		self.try_intern(string)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Gets the symbol of the provided string like [`intern`](Interner::intern),
+	/// failing if the string would exceed the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn try_intern(&mut self, string: &str) -> Result<Symbol, AllocError> {
+		self.prune();
+
+		if let Some(&symbol) = self.symbols.get(string) {
+			return Ok(symbol);
+		}
+
+		let symbol = Symbol::from_index(self.strings.len()).expect("Too many interned strings");
+
+		// Pinning the string, so that the key keeps pointing to it
+		let interned = self.allocator.try_alloc_str(string)?;
+		let key = Key(NonNull::from(interned.get()));
+
+		self.strings.push(interned);
+		self.symbols.insert(key, symbol);
+
+		Ok(symbol)
+	}
+
+	/// Gets the symbol of the provided string, if it has been interned.
+	pub fn get(&self, string: &str) -> Option<Symbol> {
+		self.symbols
+			.get(string)
+			.copied()
+			.filter(|symbol| self.strings[symbol.index()].is_valid())
+	}
+
+	/// Gets the string of the provided symbol, unless it has been deallocated.
+	pub fn resolve(&self, symbol: Symbol) -> Option<&str> {
+		self.strings
+			.get(symbol.index())
+			.filter(|string| string.is_valid())
+			.map(|string| &**string)
+	}
+
+	/// Gets the count of interned strings.
+	pub fn len(&self) -> usize { self.strings.partition_point(|string| string.is_valid()) }
+
+	/// Shows whether there are no interned strings.
+	pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+	/// Gets an iterator over the symbols and their strings, in the order of interning.
+	pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+		self.strings
+			.iter()
+			.take_while(|string| string.is_valid())
+			.enumerate()
+			.map(|(index, string)| {
+				(
+					Symbol::from_index(index).expect("Symbol index overflowed"),
+					&**string
+				)
+			})
+	}
+
+	/// Deallocates all of the strings (unless they belong to a scope, which deallocates them once it ends).
+	///
+	/// The symbols of the strings no longer resolve, and they are handed out again for the strings interned afterwards.
+	pub fn clear(&mut self) {
+		self.symbols.clear();
+		self.strings.clear();
+	}
+
+	/// Forgets the strings that have been deallocated by the memory.
+	fn prune(&mut self) {
+		// Strings are only ever deallocated by the memory from the most recently interned one backwards
+		while let Some(string) = self.strings.pop_if(|string| !string.is_valid()) {
+			// The memory of the string is kept until its mutator is dropped
+			self.symbols.remove(unsafe { string.current().as_ref() });
+		}
+	}
+}
+
+impl<B: RawBackend> Debug for Interner<'_, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_map().entries(self.iter()).finish()
+	}
+}
+
+impl Borrow<str> for Key {
+	fn borrow(&self) -> &str { unsafe { self.0.as_ref() } }
+}
+
+impl PartialEq for Key {
+	fn eq(&self, other: &Self) -> bool {
+		Borrow::<str>::borrow(self) == Borrow::<str>::borrow(other)
+	}
+}
+
+impl Eq for Key {}
+
+impl Hash for Key {
+	// Hashing like `str`, so that the keys can be searched with `&str`
+	fn hash<H: Hasher>(&self, state: &mut H) { Borrow::<str>::borrow(self).hash(state) }
+}
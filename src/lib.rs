@@ -1,27 +1,197 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "alloc")]
+#[macro_use]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 use halloc_macros::impl_alloc;
 
+mod backend;
+mod budget;
+mod buffer;
+mod cache;
+mod compaction;
+#[cfg(feature = "std")]
+mod diff;
+#[cfg(feature = "std")]
+mod dump;
+mod error;
+#[cfg(feature = "alloc")]
+mod fragmentation;
 mod heap;
+#[cfg(feature = "std")]
+mod interner;
+#[cfg(feature = "std")]
+mod map;
 mod memory;
+mod observer;
+#[cfg(feature = "alloc")]
+mod pool;
+#[cfg(feature = "profiling")]
+mod profile;
+mod scope;
+#[cfg(feature = "alloc")]
+mod shared;
+#[cfg(feature = "alloc")]
+mod snapshot;
+mod stats;
+mod string;
+mod sync;
+#[cfg(feature = "std")]
+mod trace;
+#[cfg(feature = "alloc")]
+mod transaction;
+#[cfg(feature = "alloc")]
+mod typed;
+mod vec;
 
+pub use backend::{DefaultBackend, RawBackend, SystemBackend};
+pub use budget::Pressure;
+pub use buffer::StaticBuffer;
+pub use compaction::CompactionReport;
+#[cfg(feature = "std")]
+pub use diff::{DiffGroup, HeapDiff};
+#[cfg(feature = "std")]
+pub use dump::{AllocationSite, DumpEntry, HeapDump};
+#[cfg(feature = "std")]
+pub use error::DumpError;
+pub use error::{AllocError, RestoreError};
+#[cfg(feature = "alloc")]
+pub use fragmentation::{Chunk, Fragmentation};
 pub use heap::{Heap, HeapMutator};
+#[cfg(feature = "std")]
+pub use interner::{Interner, Symbol};
+#[cfg(feature = "std")]
+pub use map::{Entry, HMap};
 pub use memory::Memory;
+pub use observer::{AllocationInfo, HeapObserver};
+#[cfg(feature = "alloc")]
+pub use pool::{Pool, Pooled};
+pub use scope::Scope;
+#[cfg(feature = "alloc")]
+pub use shared::{Cached, SharedMemory, ThreadCache};
+#[cfg(feature = "alloc")]
+pub use snapshot::{Snapshot, SnapshotEntry};
+pub use stats::HeapStats;
+pub use string::HString;
+pub use sync::{Mutex, MutexGuard};
+#[cfg(feature = "std")]
+pub use trace::{ReplayReport, Trace, TraceEvent, TraceRecorder};
+#[cfg(feature = "alloc")]
+pub use transaction::Transaction;
+#[cfg(feature = "alloc")]
+pub use typed::{Handle, TypedMemory};
+pub use vec::HVec;
 
 /// The default initial heap size (in bytes)
 pub const DEFAULT_HEAP_INIT_SIZE: usize = 1024;
 
 /// Represents any value that can be allocated onto the [`Heap`]
-pub trait Allocatable: Sized + 'static {}
+///
+/// The trait is also implemented for some dynamically sized types (such as `[T]` and [`str`]),
+/// which can be allocated with [`Memory::alloc_slice_copy`] and friends.
+pub trait Allocatable: 'static {}
 
 impl_alloc!(Allocatable for {i8, i16, i32, i64, i128});
 impl_alloc!(Allocatable for {u8, u16, u32, u64, u128});
 impl_alloc!(Allocatable for {f32, f64});
-impl_alloc!(Allocatable for {bool, String});
+impl_alloc!(Allocatable for {bool, str});
+#[cfg(feature = "alloc")]
+impl_alloc!(Allocatable for String);
+impl_alloc!(Allocatable for [T]
+	where
+		T: Allocatable
+);
+impl<T: Allocatable, const N: usize> Allocatable for [T; N] {}
+#[cfg(feature = "alloc")]
 impl_alloc!(Allocatable for Vec<T>
 	where
 		T: Allocatable
 );
+impl_alloc!(Allocatable for core::mem::MaybeUninit<T>
+	where
+		T: Allocatable
+);
+impl_alloc!(Allocatable for {
+	dyn core::any::Any,
+	dyn core::any::Any + Send,
+	dyn core::any::Any + Send + Sync
+});
+#[cfg(feature = "std")]
 impl_alloc!(Allocatable for std::collections::HashMap<U, T>
 	where
-		U: std::hash::Hash + 'static,
+		U: core::hash::Hash + 'static,
 		T: Allocatable
 );
+
+/// Represents an [`Allocatable`] value that is plain data: it owns no resources,
+/// so any of its previous states can be restored by copying its bytes back (see [`Memory::restore`]).
+///
+/// Values have to be allocated with [`Memory::alloc_plain`] to be treated as plain data.
+pub trait PlainData: Allocatable + Copy {}
+
+impl_alloc!(PlainData for {i8, i16, i32, i64, i128});
+impl_alloc!(PlainData for {u8, u16, u32, u64, u128});
+impl_alloc!(PlainData for {f32, f64, bool});
+impl<T: PlainData, const N: usize> PlainData for [T; N] {}
+
+/// Converts a [`HeapMutator`] into a mutator over a trait object (or any other unsized type),
+/// keeping the value in place. This is the safe counterpart of [`HeapMutator::unsize`].
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::{unsize, Allocatable, Memory, HeapMutator};
+/// # use std::any::Any;
+/// trait Callable {
+///     fn call(&self, argument: i32) -> i32;
+/// }
+///
+/// // Wrapping closures, so that they can be allocated
+/// struct Native<F>(F);
+///
+/// impl<F: Fn(i32) -> i32> Callable for Native<F> {
+///     fn call(&self, argument: i32) -> i32 { (self.0)(argument) }
+/// }
+///
+/// impl<F: 'static> Allocatable for Native<F> {}
+/// impl Allocatable for dyn Callable {}
+///
+/// let memory = Memory::new();
+///
+/// let offset = 10;
+/// let natives: Vec<HeapMutator<dyn Callable>> = vec![
+///     unsize!(memory.alloc(Native(|x| x * 2)) => dyn Callable),
+///     unsize!(memory.alloc(Native(move |x| x + offset)) => dyn Callable)
+/// ];
+///
+/// assert_eq!(natives[0].call(4), 8);
+/// assert_eq!(natives[1].call(4), 14);
+///
+/// // Built-in trait objects can be used as well
+/// let any: HeapMutator<dyn Any> = unsize!(memory.alloc(5u8) => dyn Any);
+/// assert_eq!(any.downcast_ref::<u8>(), Some(&5));
+///
+/// drop(natives);
+/// assert_eq!(memory.size(), 1);
+/// ```
+///
+/// Only unsizing coercions are accepted, so the value cannot be reinterpreted as another type:
+///
+/// ```compile_fail
+/// # use halloc::{unsize, Memory};
+/// let memory = Memory::new();
+/// let wide = unsize!(memory.alloc(1u8) => u64);
+/// ```
+#[macro_export]
+macro_rules! unsize {
+	($mutator:expr => $target:ty) => {
+		// SAFETY: The pointer is only coerced implicitly, so only unsizing coercions (which keep the address) compile
+		unsafe { $crate::HeapMutator::unsize($mutator, |ptr| -> *mut $target { ptr }) }
+	};
+}
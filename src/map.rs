@@ -0,0 +1,540 @@
+use core::borrow::Borrow;
+use core::fmt::{self, Debug, Formatter};
+use core::hash::{BuildHasher, Hash};
+use core::ops::Index;
+use std::hash::RandomState;
+
+use crate::{AllocError, HVec, Memory, RawBackend, SystemBackend};
+
+/// Marker of an empty slot of the index table of an [`HMap`]
+const EMPTY: usize = usize::MAX;
+
+/// Count of slots that a non-empty index table of an [`HMap`] has at least
+const MIN_SLOTS: usize = 8;
+
+/// A hash map like [`HashMap`](std::collections::HashMap), whose buffers are allocated from a [`Memory`].
+///
+/// The entries are kept next to each other in one [`HVec`], and are found through a table of their indices
+/// in another one (using linear probing), so both of the buffers are accounted for like the one of an [`HVec`].
+/// Entries are iterated over in the order of their insertion, until one of them is removed.
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::{HMap, HString, Memory};
+/// let memory = Memory::new();
+/// let mut scores = HMap::new_in(&memory);
+///
+/// for name in ["alice", "bob", "alice"] {
+///     *scores.entry(HString::from_str_in(name, &memory)).or_insert(0) += 1;
+/// }
+///
+/// assert_eq!(scores["alice"], 2);
+/// assert_eq!(scores.remove("bob"), Some(1));
+/// assert!(!scores.contains_key("bob"));
+///
+/// // The entries, the index table and the key of "alice" are all within the memory
+/// assert_eq!(memory.count(), 3);
+/// ```
+pub struct HMap<'memory, K, V, B: RawBackend = SystemBackend, S = RandomState> {
+	/// Entries of the map
+	entries: HVec<'memory, Bucket<K, V>, B>,
+
+	/// Indices of the entries by the hashes of their keys, with [`EMPTY`] in the free slots
+	///
+	/// The count of slots is either zero or a power of two, and at most three quarters of them are taken
+	indices: HVec<'memory, usize, B>,
+
+	/// Builder of the hashers of the keys
+	hash_builder: S
+}
+
+#[derive(Clone)]
+/// An entry of an [`HMap`], along with the hash of its key.
+struct Bucket<K, V> {
+	/// Hash of the key
+	hash: u64,
+
+	/// Key of the entry
+	key: K,
+
+	/// Value of the entry
+	value: V
+}
+
+/// An entry of an [`HMap`] that may or may not be present, created with [`HMap::entry`].
+pub struct Entry<'map, 'memory, K, V, B: RawBackend = SystemBackend, S = RandomState> {
+	/// Map that the entry belongs to
+	map: &'map mut HMap<'memory, K, V, B, S>,
+
+	/// Key of the entry
+	key: K,
+
+	/// Hash of the key
+	hash: u64,
+
+	/// Index of the entry, if it is present
+	index: Option<usize>
+}
+
+impl<'memory, K, V, B: RawBackend> HMap<'memory, K, V, B> {
+	/// Creates an empty map on the provided [`Memory`], which does not allocate until entries are inserted into it.
+	pub fn new_in(memory: &'memory Memory<B>) -> Self {
+		Self::with_hasher_in(RandomState::new(), memory)
+	}
+
+	/// Creates an empty map on the provided [`Memory`], with space for at least `capacity` entries.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffers would exceed the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn with_capacity_in(capacity: usize, memory: &'memory Memory<B>) -> Self
+	where
+		K: Eq + Hash {
+		let mut map = Self::new_in(memory);
+		map.reserve(capacity);
+
+		map
+	}
+}
+
+impl<'memory, K, V, B: RawBackend, S> HMap<'memory, K, V, B, S> {
+	/// Creates an empty map on the provided [`Memory`], hashing the keys with the provided builder.
+	pub fn with_hasher_in(hash_builder: S, memory: &'memory Memory<B>) -> Self {
+		Self {
+			entries: HVec::new_in(memory),
+			indices: HVec::new_in(memory),
+			hash_builder
+		}
+	}
+
+	/// Gets the [`Memory`] that the buffers are allocated from.
+	pub fn memory(&self) -> &'memory Memory<B> { self.entries.memory() }
+
+	/// Gets the builder of the hashers of the keys.
+	pub fn hasher(&self) -> &S { &self.hash_builder }
+
+	/// Gets the count of entries.
+	pub fn len(&self) -> usize { self.entries.len() }
+
+	/// Shows whether there are no entries.
+	pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+	/// Gets the count of entries that the map can hold without reallocating.
+	pub fn capacity(&self) -> usize {
+		core::cmp::min(self.entries.capacity(), self.indices.len() / 4 * 3)
+	}
+
+	/// Removes all of the entries, keeping the buffers.
+	pub fn clear(&mut self) {
+		self.entries.clear();
+		self.indices.fill(EMPTY);
+	}
+
+	/// Gets an iterator over the keys and the values.
+	pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> + '_ {
+		self.entries
+			.iter()
+			.map(|bucket| (&bucket.key, &bucket.value))
+	}
+
+	/// Gets an iterator over the keys and the mutable values.
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> + '_ {
+		self.entries
+			.iter_mut()
+			.map(|bucket| (&bucket.key, &mut bucket.value))
+	}
+
+	/// Gets an iterator over the keys.
+	pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+		self.entries.iter().map(|bucket| &bucket.key)
+	}
+
+	/// Gets an iterator over the values.
+	pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+		self.entries.iter().map(|bucket| &bucket.value)
+	}
+
+	/// Gets an iterator over the mutable values.
+	pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> + '_ {
+		self.entries.iter_mut().map(|bucket| &mut bucket.value)
+	}
+}
+
+impl<'memory, K: Eq + Hash, V, B: RawBackend, S: BuildHasher> HMap<'memory, K, V, B, S> {
+	/// Reserves space for at least `additional` more entries.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffers would exceed the byte limit of the memory, or if the backend is exhausted (see [`try_reserve`](HMap::try_reserve)).
+	#[track_caller]
+	pub fn reserve(&mut self, additional: usize) {
+		self.try_reserve(additional)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Reserves space for at least `additional` more entries like [`reserve`](HMap::reserve),
+	/// failing if the buffers would exceed the byte limit of the memory, or if the backend is exhausted.
+	pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+		self.entries.try_reserve(additional)?;
+
+		let required = self.len().saturating_add(additional);
+
+		if required > self.indices.len() / 4 * 3 {
+			let slots = required
+				.saturating_mul(4)
+				.div_ceil(3)
+				.checked_next_power_of_two()
+				.ok_or(AllocError::OutOfMemory {
+					requested: usize::MAX
+				})?;
+
+			self.rebuild(core::cmp::max(slots, MIN_SLOTS))?;
+		}
+
+		Ok(())
+	}
+
+	/// Shrinks the buffers to fit the entries, deallocating them if there are none.
+	pub fn shrink_to_fit(&mut self) {
+		self.entries.shrink_to_fit();
+
+		let slots = match self.len() {
+			0 => 0,
+			len => core::cmp::max(
+				len.saturating_mul(4).div_ceil(3).next_power_of_two(),
+				MIN_SLOTS
+			)
+		};
+
+		if slots < self.indices.len() {
+			// Shrinking only fails if the backend is exhausted, in which case the table is kept as it is
+			let _ = self.rebuild(slots);
+		}
+	}
+
+	/// Inserts the provided value under the provided key, returning the value that it replaces, if any.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffers need to grow beyond the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+		let hash = self.hash_builder.hash_one(&key);
+
+		if let Some((_, index)) = self.find(hash, &key) {
+			return Some(core::mem::replace(&mut self.entries[index].value, value));
+		}
+
+		self.reserve(1);
+		self.push(hash, key, value);
+
+		None
+	}
+
+	/// Gets the entry of the provided key, for in-place manipulation.
+	pub fn entry(&mut self, key: K) -> Entry<'_, 'memory, K, V, B, S> {
+		let hash = self.hash_builder.hash_one(&key);
+		let index = self.find(hash, &key).map(|(_, index)| index);
+
+		Entry {
+			map: self,
+			key,
+			hash,
+			index
+		}
+	}
+
+	/// Gets a reference to the value of the provided key, if there is one.
+	pub fn get<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> Option<&V>
+	where
+		K: Borrow<Q> {
+		self.get_key_value(key).map(|(_, value)| value)
+	}
+
+	/// Gets references to the stored key and the value of the provided key, if there are ones.
+	pub fn get_key_value<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> Option<(&K, &V)>
+	where
+		K: Borrow<Q> {
+		let (_, index) = self.find(self.hash_builder.hash_one(key), key)?;
+		let bucket = &self.entries[index];
+
+		Some((&bucket.key, &bucket.value))
+	}
+
+	/// Gets a mutable reference to the value of the provided key, if there is one.
+	pub fn get_mut<Q: ?Sized + Eq + Hash>(&mut self, key: &Q) -> Option<&mut V>
+	where
+		K: Borrow<Q> {
+		let (_, index) = self.find(self.hash_builder.hash_one(key), key)?;
+		Some(&mut self.entries[index].value)
+	}
+
+	/// Shows whether there is a value for the provided key.
+	pub fn contains_key<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> bool
+	where
+		K: Borrow<Q> {
+		self.get(key).is_some()
+	}
+
+	/// Removes and returns the value of the provided key, if there is one.
+	///
+	/// The last entry takes the place of the removed one in the iteration order.
+	pub fn remove<Q: ?Sized + Eq + Hash>(&mut self, key: &Q) -> Option<V>
+	where
+		K: Borrow<Q> {
+		self.remove_entry(key).map(|(_, value)| value)
+	}
+
+	/// Removes and returns the stored key and the value of the provided key, if there are ones.
+	pub fn remove_entry<Q: ?Sized + Eq + Hash>(&mut self, key: &Q) -> Option<(K, V)>
+	where
+		K: Borrow<Q> {
+		let (slot, index) = self.find(self.hash_builder.hash_one(key), key)?;
+		let bucket = self.take(slot, index);
+
+		Some((bucket.key, bucket.value))
+	}
+
+	/// Keeps only the entries for which `f` returns `true`.
+	pub fn retain(&mut self, mut f: impl FnMut(&K, &mut V) -> bool) {
+		let len = self.len();
+
+		// Removing the entries in reverse order, so that only the entries that are kept are moved
+		for index in (0..len).rev() {
+			let bucket = &mut self.entries[index];
+
+			if !f(&bucket.key, &mut bucket.value) {
+				let hash = bucket.hash;
+				self.take(self.slot_of(hash, index), index);
+			}
+		}
+	}
+
+	/// Removes the entry with the provided slot and index, moving the last entry into its place.
+	fn take(&mut self, slot: usize, index: usize) -> Bucket<K, V> {
+		self.erase(slot);
+
+		let bucket = self.entries.swap_remove(index);
+
+		// Pointing the slot of the last entry to the place that it has been moved to
+		if index < self.entries.len() {
+			let slot = self.slot_of(self.entries[index].hash, self.entries.len());
+			self.indices[slot] = index;
+		}
+
+		bucket
+	}
+
+	/// Finds the slot and the index of the entry of the provided key.
+	fn find<Q: ?Sized + Eq>(&self, hash: u64, key: &Q) -> Option<(usize, usize)>
+	where
+		K: Borrow<Q> {
+		if self.indices.is_empty() {
+			return None;
+		}
+
+		let mask = self.indices.len() - 1;
+		let mut slot = hash as usize & mask;
+
+		// There always is an empty slot, so the probing stops
+		loop {
+			match self.indices[slot] {
+				EMPTY => return None,
+				index => {
+					let bucket = &self.entries[index];
+
+					if bucket.hash == hash && bucket.key.borrow() == key {
+						return Some((slot, index));
+					}
+				}
+			}
+
+			slot = (slot + 1) & mask;
+		}
+	}
+
+	/// Finds the slot that holds the provided index of an entry with the provided hash.
+	fn slot_of(&self, hash: u64, index: usize) -> usize {
+		let mask = self.indices.len() - 1;
+		let mut slot = hash as usize & mask;
+
+		while self.indices[slot] != index {
+			slot = (slot + 1) & mask;
+		}
+
+		slot
+	}
+
+	/// Appends a new entry, whose key is not in the map yet, returning its index.
+	///
+	/// There must be space for another entry.
+	fn push(&mut self, hash: u64, key: K, value: V) -> usize {
+		let index = self.entries.len();
+		self.entries.push(Bucket { hash, key, value });
+		self.place(hash, index);
+
+		index
+	}
+
+	/// Puts the provided index of an entry into the first empty slot for its hash.
+	fn place(&mut self, hash: u64, index: usize) {
+		let mask = self.indices.len() - 1;
+		let mut slot = hash as usize & mask;
+
+		while self.indices[slot] != EMPTY {
+			slot = (slot + 1) & mask;
+		}
+
+		self.indices[slot] = index;
+	}
+
+	/// Empties the provided slot, moving the following entries back so that they can still be found.
+	fn erase(&mut self, slot: usize) {
+		let mask = self.indices.len() - 1;
+		let mut hole = slot;
+		let mut next = (slot + 1) & mask;
+
+		loop {
+			let index = self.indices[next];
+
+			if index == EMPTY {
+				break;
+			}
+
+			// Moving the index into the hole, unless the hole lies before the first slot that the index can be in
+			let home = self.entries[index].hash as usize & mask;
+
+			if next.wrapping_sub(home) & mask >= next.wrapping_sub(hole) & mask {
+				self.indices[hole] = index;
+				hole = next;
+			}
+
+			next = (next + 1) & mask;
+		}
+
+		self.indices[hole] = EMPTY;
+	}
+
+	/// Replaces the index table with one of the provided count of slots.
+	fn rebuild(&mut self, slots: usize) -> Result<(), AllocError> {
+		let mut indices = HVec::new_in(self.memory());
+		indices.try_reserve_exact(slots)?;
+		indices.resize(slots, EMPTY);
+
+		self.indices = indices;
+
+		for index in 0..self.entries.len() {
+			self.place(self.entries[index].hash, index);
+		}
+
+		Ok(())
+	}
+}
+
+impl<'map, 'memory, K: Eq + Hash, V, B: RawBackend, S: BuildHasher>
+	Entry<'map, 'memory, K, V, B, S>
+{
+	/// Gets the key of the entry.
+	pub fn key(&self) -> &K {
+		match self.index {
+			Some(index) => &self.map.entries[index].key,
+			None => &self.key
+		}
+	}
+
+	/// Modifies the value of the entry with `f`, if it is present.
+	pub fn and_modify(self, f: impl FnOnce(&mut V)) -> Self {
+		if let Some(index) = self.index {
+			f(&mut self.map.entries[index].value);
+		}
+
+		self
+	}
+
+	/// Gets the value of the entry, inserting the provided one if it is not present.
+	#[track_caller]
+	pub fn or_insert(self, default: V) -> &'map mut V { self.or_insert_with(|| default) }
+
+	/// Gets the value of the entry, inserting the one returned by `f` if it is not present.
+	#[track_caller]
+	pub fn or_insert_with(self, f: impl FnOnce() -> V) -> &'map mut V {
+		let index = match self.index {
+			Some(index) => index,
+			None => {
+				self.map.reserve(1);
+				self.map.push(self.hash, self.key, f())
+			}
+		};
+
+		&mut self.map.entries[index].value
+	}
+
+	/// Gets the value of the entry, inserting the default value if it is not present.
+	#[track_caller]
+	pub fn or_default(self) -> &'map mut V
+	where
+		V: Default {
+		self.or_insert_with(V::default)
+	}
+}
+
+impl<K: Eq + Hash + Borrow<Q>, Q: ?Sized + Eq + Hash, V, B: RawBackend, S: BuildHasher> Index<&Q>
+	for HMap<'_, K, V, B, S>
+{
+	type Output = V;
+
+	fn index(&self, key: &Q) -> &Self::Output { self.get(key).expect("Key is not in the map") }
+}
+
+impl<K: Eq + Hash, V, B: RawBackend, S: BuildHasher> Extend<(K, V)> for HMap<'_, K, V, B, S> {
+	#[track_caller]
+	fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+		let iter = iter.into_iter();
+		self.reserve(iter.size_hint().0);
+
+		for (key, value) in iter {
+			self.insert(key, value);
+		}
+	}
+}
+
+impl<K: Clone, V: Clone, B: RawBackend, S: Clone> Clone for HMap<'_, K, V, B, S> {
+	/// Clones the map into new buffers on the same [`Memory`].
+	fn clone(&self) -> Self {
+		Self {
+			entries: self.entries.clone(),
+			indices: self.indices.clone(),
+			hash_builder: self.hash_builder.clone()
+		}
+	}
+}
+
+impl<K: Debug, V: Debug, B: RawBackend, S> Debug for HMap<'_, K, V, B, S> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_map().entries(self.iter()).finish()
+	}
+}
+
+impl<K: Eq + Hash, V: PartialEq, B: RawBackend, C: RawBackend, S: BuildHasher, T: BuildHasher>
+	PartialEq<HMap<'_, K, V, C, T>> for HMap<'_, K, V, B, S>
+{
+	fn eq(&self, other: &HMap<'_, K, V, C, T>) -> bool {
+		self.len() == other.len()
+			&& self
+				.iter()
+				.all(|(key, value)| other.get(key) == Some(value))
+	}
+}
+
+impl<K: Eq + Hash, V: Eq, B: RawBackend, S: BuildHasher> Eq for HMap<'_, K, V, B, S> {}
+
+impl<K: Debug, V, B: RawBackend, S> Debug for Entry<'_, '_, K, V, B, S> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Entry")
+			.field("key", &self.key)
+			.field("present", &self.index.is_some())
+			.finish_non_exhaustive()
+	}
+}
@@ -1,31 +1,338 @@
-use std::alloc::Layout;
-use std::ptr::write;
-use std::sync::{Mutex, MutexGuard};
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::mem::MaybeUninit;
+use core::ops::{Deref, DerefMut};
+#[cfg(feature = "std")]
+use core::panic::AssertUnwindSafe;
+use core::panic::Location;
+use core::ptr::{write, NonNull};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
 
-use crate::{Allocatable, Heap, HeapMutator, DEFAULT_HEAP_INIT_SIZE};
+use crate::budget::Budget;
+use crate::cache::{self, Cache};
+use crate::heap::{destroy, DropGlue, Slot, TypeTag};
+use crate::sync::{Mutex, MutexGuard};
+use crate::{
+	AllocError, Allocatable, CompactionReport, DefaultBackend, Heap, HeapMutator, HeapStats,
+	PlainData, RawBackend, Scope, StaticBuffer
+};
+#[cfg(feature = "alloc")]
+use crate::{
+	Fragmentation, HeapObserver, Pool, Pressure, RestoreError, Snapshot, SystemBackend,
+	Transaction, DEFAULT_HEAP_INIT_SIZE
+};
+#[cfg(feature = "std")]
+use crate::{HeapDump, Interner};
 
 #[derive(Debug)]
 /// A struct containing a [`Mutex`] of the inner [`Heap`] that is used for direct value allocation.
 ///
+/// The memory of the values is provided by a [`RawBackend`], which is the global allocator by default (see [`SystemBackend`]).
+///
 /// See methods on [`Memory`] for documentation.
-pub struct Memory {
+pub struct Memory<B: RawBackend = DefaultBackend> {
 	// Heap that the current [`Memory`] owns
-	pub(crate) heap: Mutex<Heap>
+	pub(crate) heap: Mutex<Heap<B>>,
+
+	// Cache that allocates and drops the values without locking the heap for each of them
+	pub(crate) cache: Cache
 }
 
+#[cfg(feature = "alloc")]
 impl Memory {
 	/// Initializes [`Memory`] with the default initialization size.
 	pub fn new() -> Self { Self::with_size(DEFAULT_HEAP_INIT_SIZE) }
 
-	/// Initializes [`Memory`] with the provided initialization size.
-	pub fn with_size(initial_size: usize) -> Self {
+	/// Initializes [`Memory`] with the provided initialization size, which is only kept for compatibility (see [`Heap::new`]).
+	pub fn with_size(initial_size: usize) -> Self { Self::from_heap(Heap::new(initial_size)) }
+
+	/// Initializes [`Memory`] with a hard limit of `limit` allocated bytes.
+	///
+	/// Allocations that would exceed the limit fail: the fallible methods (such as [`try_alloc`](Memory::try_alloc))
+	/// return an [`AllocError`], while the rest of the allocation methods panic.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{AllocError, Memory};
+	/// let memory = Memory::with_limit(16);
+	///
+	/// let _first = memory.try_alloc(1u64).unwrap();
+	/// let _second = memory.try_alloc(2u64).unwrap();
+	///
+	/// assert_eq!(
+	///     memory.try_alloc(3u8).err(),
+	///     Some(AllocError::LimitExceeded { requested: 1, available: 0 })
+	/// );
+	/// ```
+	pub fn with_limit(limit: usize) -> Self {
+		Self::with_budget(Budget::new(Some(limit)), SystemBackend)
+	}
+}
+
+impl Memory<StaticBuffer> {
+	/// Initializes [`Memory`] whose values are stored within the provided buffer, see [`StaticBuffer`].
+	///
+	/// The memory never uses the global allocator, so it is bounded by the buffer (e.g., on embedded targets without one,
+	/// where the crate is built without its `alloc` feature). The bookkeeping of the heap is stored within the buffer as well:
+	/// each value is preceded by the record of its allocation, and the state shared with its mutators is carved from the buffer too,
+	/// so every value takes up a couple hundred bytes more than its size.
+	///
+	/// Allocations that do not fit into the buffer fail with [`AllocError::OutOfMemory`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{AllocError, Memory};
+	/// let buffer: &'static mut [u8] = Box::leak(Box::new([0; 1024]));
+	/// let memory = Memory::from_static_buffer(buffer);
+	///
+	/// let readings = memory.alloc([0u32; 32]);
+	/// assert_eq!(
+	///     memory.try_alloc([0u8; 800]).err(),
+	///     Some(AllocError::OutOfMemory { requested: 800 })
+	/// );
+	///
+	/// // The memory can be reused once freed
+	/// drop(readings);
+	/// assert!(memory.try_alloc([0u8; 800]).is_ok());
+	/// ```
+	pub fn from_static_buffer(buffer: &'static mut [u8]) -> Self {
+		Self::with_backend(StaticBuffer::new(buffer))
+	}
+}
+
+impl<B: RawBackend> Memory<B> {
+	/// Initializes [`Memory`] with the default initialization size, allocating the values from the provided [`RawBackend`].
+	///
+	/// Values can still be moved to memories with other backends, see [`HeapMutator::transfer_to`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, RawBackend, SystemBackend};
+	/// # use std::alloc::Layout;
+	/// # use std::ptr::NonNull;
+	/// // A backend whose memory cannot be deallocated by other backends
+	/// #[derive(Debug)]
+	/// struct Scratch;
+	///
+	/// unsafe impl RawBackend for Scratch {
+	///     fn allocate(&mut self, layout: Layout) -> Option<NonNull<u8>> {
+	///         SystemBackend.allocate(layout)
+	///     }
+	///
+	///     unsafe fn deallocate(&mut self, ptr: NonNull<u8>, layout: Layout) {
+	///         unsafe { SystemBackend.deallocate(ptr, layout) }
+	///     }
+	/// }
+	///
+	/// let scratch = Memory::with_backend(Scratch);
+	/// let kept = Memory::new();
+	///
+	/// let name = scratch.alloc_str("player");
+	/// let _frame = scratch.alloc([0u8; 64]);
+	///
+	/// // The value is copied out of the scratch memory
+	/// let name = name.transfer_to(&kept).unwrap();
+	///
+	/// assert_eq!(&*name, "player");
+	/// assert_eq!(scratch.count(), 1);
+	/// assert_eq!(kept.count(), 1);
+	/// ```
+	pub fn with_backend(backend: B) -> Self { Self::with_budget(Budget::new(None), backend) }
+
+	/// Initializes [`Memory`] with the provided [`Budget`] and [`RawBackend`].
+	fn with_budget(budget: Budget, backend: B) -> Self {
+		Self::from_heap(Heap::with_budget(backend, budget.into_heap()))
+	}
+
+	/// Initializes [`Memory`] with the provided [`Heap`].
+	fn from_heap(heap: Heap<B>) -> Self {
+		let cache = Cache::new(&heap);
+
 		Self {
-			heap: Mutex::new(Heap::new(initial_size))
+			heap: Mutex::new(heap),
+			cache
 		}
 	}
 
-	/// Acquires the current [`Heap`] lock.
-	fn get_heap(&self) -> MutexGuard<Heap> { self.heap.lock().expect("Heap lock failed") }
+	/// Creates a child [`Memory`] with a heap of its own, whose allocations are also counted by the current memory.
+	///
+	/// The allocations of the child count towards the [`size`](Memory::size), [`count`](Memory::count)
+	/// and the byte limit of the current memory (and of all of its parents). Dropping or resetting the child
+	/// deallocates all of its values at once, and values can be moved between the memories with [`HeapMutator::transfer_to`].
+	///
+	/// The child allocates its values from a new instance of the same [`RawBackend`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let host = Memory::with_limit(64);
+	/// let _config = host.alloc([0u8; 16]);
+	///
+	/// let plugin = host.child();
+	/// let state = plugin.alloc([0u8; 32]);
+	///
+	/// assert_eq!(plugin.size(), 32);
+	/// assert_eq!(host.size(), 48);
+	///
+	/// // The child is limited by the budget of its parent
+	/// assert!(plugin.try_alloc([0u8; 32]).is_err());
+	///
+	/// // Unloading the plugin releases all of its memory
+	/// drop(state);
+	/// drop(plugin);
+	/// assert_eq!(host.size(), 16);
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn child(&self) -> Self
+	where
+		B: Default {
+		Self::with_budget(Budget::with_parent(self.budget(), None), B::default())
+	}
+
+	/// Creates a child [`Memory`] with a byte limit of its own, on top of the limits of the current memory.
+	///
+	/// See [`child`](Memory::child) and [`with_limit`](Memory::with_limit).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let host = Memory::new();
+	/// let plugin = host.child_with_limit(8);
+	///
+	/// let _first = plugin.try_alloc(1u64).unwrap();
+	/// assert!(plugin.try_alloc(1u64).is_err());
+	/// assert!(host.try_alloc(1u64).is_ok());
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn child_with_limit(&self, limit: usize) -> Self
+	where
+		B: Default {
+		Self::with_budget(
+			Budget::with_parent(self.budget(), Some(limit)),
+			B::default()
+		)
+	}
+
+	/// Gets the [`Budget`] of the underlying heap.
+	#[cfg(feature = "alloc")]
+	fn budget(&self) -> Arc<Budget> { Arc::clone(&self.get_heap().budget) }
+
+	/// Gets the byte limit of the [`Memory`], if it has one.
+	///
+	/// Limits of the parent memories (see [`child`](Memory::child)) are not taken into account.
+	///
+	/// See [`with_limit`](Memory::with_limit).
+	pub fn limit(&self) -> Option<usize> { self.get_heap().budget.limit }
+
+	/// Registers a callback that is called when the allocated bytes rise to or above the provided fraction of the byte limit
+	/// (e.g., `0.75` for 75% of the limit).
+	///
+	/// The callback is called once per crossing: it is only called again after the usage has dropped below the threshold
+	/// and crossed it once more. It is called after the allocation that crossed the threshold, without holding the heap lock,
+	/// so it may use the memory as well. Callbacks are never called for memory without a limit.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// # use std::sync::{Arc, Mutex};
+	/// let memory = Memory::with_limit(100);
+	/// let warnings = Arc::new(Mutex::new(vec![]));
+	///
+	/// for threshold in [0.75, 0.9] {
+	///     let warnings = Arc::clone(&warnings);
+	///     memory.on_pressure(threshold, move |pressure| {
+	///         warnings.lock().unwrap().push((pressure.threshold, pressure.usage));
+	///     });
+	/// }
+	///
+	/// let _small = memory.alloc([0u8; 50]);
+	/// let _large = memory.alloc([0u8; 30]);
+	/// assert_eq!(*warnings.lock().unwrap(), vec![(0.75, 80)]);
+	///
+	/// let _last = memory.alloc([0u8; 15]);
+	/// assert_eq!(*warnings.lock().unwrap(), vec![(0.75, 80), (0.9, 95)]);
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn on_pressure(
+		&self,
+		threshold: f64,
+		callback: impl Fn(&Pressure) + Send + Sync + 'static
+	) {
+		self.budget().add_threshold(threshold, Arc::new(callback));
+	}
+
+	/// Registers an observer that is notified of the allocations, deallocations, reallocations and casts
+	/// made on the underlying heap, see [`HeapObserver`].
+	///
+	/// The observers of the parent memory are not notified of the events of its child memories (see [`child`](Memory::child)).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{AllocationInfo, HeapObserver, Memory};
+	/// # use std::sync::{Arc, Mutex};
+	/// #[derive(Default)]
+	/// struct Log(Mutex<Vec<String>>);
+	///
+	/// impl HeapObserver for Log {
+	///     fn on_alloc(&self, allocation: &AllocationInfo) {
+	///         let name = allocation.type_name.unwrap_or("?");
+	///         self.0.lock().unwrap().push(format!("+{name} ({} bytes)", allocation.layout.size()));
+	///     }
+	///
+	///     fn on_dealloc(&self, allocation: &AllocationInfo) {
+	///         self.0.lock().unwrap().push(format!("-{}", allocation.type_name.unwrap_or("?")));
+	///     }
+	/// }
+	///
+	/// let log = Arc::new(Log::default());
+	///
+	/// let memory = Memory::new();
+	/// memory.add_observer(Arc::clone(&log));
+	///
+	/// let id = memory.alloc(7u32);
+	/// drop(memory.alloc_str("temporary"));
+	///
+	/// assert_eq!(*log.0.lock().unwrap(), ["+u32 (4 bytes)", "+str (9 bytes)", "-str"]);
+	/// # drop(id);
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn add_observer(&self, observer: impl HeapObserver + 'static) {
+		let mut heap = self.get_heap();
+		heap.add_observer(observer);
+
+		// Observers have to see every allocation right away
+		self.cache.update(&heap);
+	}
+
+	/// Acquires the current [`Heap`] lock, recording the values allocated and dropped through the cache first.
+	pub(crate) fn get_heap(&self) -> MutexGuard<'_, Heap<B>> {
+		self.try_get_heap().expect("Heap lock failed")
+	}
+
+	/// Acquires the current [`Heap`] lock like [`get_heap`](Memory::get_heap), returning [`None`] if it is poisoned.
+	pub(crate) fn try_get_heap(&self) -> Option<MutexGuard<'_, Heap<B>>> {
+		cache::lock(&self.heap, Some(&self.cache))
+	}
+
+	/// Gets the [`Allocator`] that allocates values directly onto the current [`Heap`].
+	fn allocator(&self) -> Allocator<'_, B> {
+		Allocator {
+			heap: &self.heap,
+			cache: &self.cache,
+			scope: None
+		}
+	}
 
 	/// Allocates memory for the provided value and returns a [`HeapMutator`] for that address.
 	///
@@ -41,100 +348,1342 @@ impl Memory {
 	/// mutator.write(false);
 	/// assert_eq!(*mutator, false);
 	/// ```
-	pub fn alloc<T: Allocatable>(&self, value: T) -> HeapMutator<T> {
-		// Creating a suitable layout for `T`
-		let layout = Layout::new::<T>();
+	#[track_caller]
+	pub fn alloc<T: Allocatable>(&self, value: T) -> HeapMutator<'_, T, B> {
+		self.allocator().alloc(value)
+	}
 
-		// Acquiring a heap lock
-		let mut heap = self.get_heap();
+	/// Allocates memory for the provided value and returns a [`HeapMutator`] for that address,
+	/// or an [`AllocError`] if the allocation would exceed the byte limit (see [`with_limit`](Memory::with_limit)).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::with_limit(8);
+	///
+	/// let small = memory.try_alloc(5u32);
+	/// let large = memory.try_alloc([0u32; 4]);
+	///
+	/// assert_eq!(*small.unwrap(), 5);
+	/// assert!(large.is_err());
+	/// ```
+	#[track_caller]
+	pub fn try_alloc<T: Allocatable>(&self, value: T) -> Result<HeapMutator<'_, T, B>, AllocError> {
+		self.allocator().try_alloc(value)
+	}
 
-		// Allocating a pointer
-		let ptr = heap.alloc_zeroed(layout).cast::<T>();
+	/// Allocates memory for the provided plain data value and returns a [`HeapMutator`] for that address.
+	///
+	/// Unlike [`alloc`](Memory::alloc), the value is recorded as [`PlainData`], so that it can be restored from a [`Snapshot`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// let position = memory.alloc_plain([1.0f32, 2.0]);
+	///
+	/// assert_eq!(memory.snapshot().entries()[0].is_plain(), true);
+	/// # drop(position);
+	/// ```
+	#[track_caller]
+	pub fn alloc_plain<T: PlainData>(&self, value: T) -> HeapMutator<'_, T, B> {
+		self.allocator().alloc_plain(value)
+	}
 
-		unsafe {
-			// Writing the provided value to the allocated pointer
-			write(ptr.as_ptr(), value);
+	/// Allocates memory for the value returned by `f` and returns a [`HeapMutator`] for that address.
+	///
+	/// `f` is called before the memory is allocated, so it may use the memory as well (even [`reset`](Memory::reset) it),
+	/// and nothing is allocated if it panics. Its result is then moved into the allocated memory, so values that do not fit
+	/// on the stack should be initialized through [`alloc_uninit`](Memory::alloc_uninit) instead.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// let memory = Memory::new();
+	/// let mutator: HeapMutator<[u64; 512]> = memory.alloc_with(|| [7; 512]);
+	///
+	/// assert_eq!(mutator[511], 7);
+	/// assert_eq!(memory.size(), 4096);
+	/// ```
+	///
+	/// The memory can be used by `f`:
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// drop(memory.alloc(1u32));
+	///
+	/// let count = memory.alloc_with(|| {
+	///     memory.reset();
+	///     memory.count() as u64
+	/// });
+	///
+	/// assert_eq!(*count, 0);
+	/// assert_eq!(memory.count(), 1);
+	/// ```
+	#[track_caller]
+	pub fn alloc_with<T: Allocatable>(&self, f: impl FnOnce() -> T) -> HeapMutator<'_, T, B> {
+		self.allocator().alloc_with(f)
+	}
 
-			// Creating the mutator
-			HeapMutator::new_unchecked(ptr, &self.heap)
-		}
+	/// Allocates uninitialized memory for a value of type `T` and returns a [`HeapMutator`] for that address.
+	///
+	/// Use [`HeapMutator::assume_init`] once the value has been initialized. Unlike [`alloc_with`](Memory::alloc_with),
+	/// the value never has to exist on the stack, which makes this suitable for values that do not fit there.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// # use std::mem::MaybeUninit;
+	/// let memory = Memory::new();
+	/// let mut table: HeapMutator<MaybeUninit<[u32; 1024]>> = memory.alloc_uninit();
+	///
+	/// // Initializing the array element by element, directly on the heap
+	/// let ptr = table.as_mut_ptr().cast::<u32>();
+	/// for i in 0..1024 {
+	///     unsafe { ptr.add(i).write(i as u32 * 2) }
+	/// }
+	///
+	/// let table: HeapMutator<[u32; 1024]> = unsafe { table.assume_init() };
+	///
+	/// assert_eq!(table[1023], 2046);
+	/// assert_eq!(memory.count(), 1);
+	/// ```
+	///
+	/// Values larger than the stack can be zeroed in place:
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// # use std::mem::MaybeUninit;
+	/// const LEN: usize = 16 << 20;
+	///
+	/// let memory = Memory::new();
+	/// let mut buffer: HeapMutator<MaybeUninit<[u8; LEN]>> = memory.alloc_uninit();
+	///
+	/// unsafe { buffer.as_mut_ptr().write_bytes(0, 1) }
+	/// let buffer: HeapMutator<[u8; LEN]> = unsafe { buffer.assume_init() };
+	///
+	/// assert_eq!(buffer[LEN - 1], 0);
+	/// assert_eq!(memory.size(), LEN);
+	/// ```
+	#[track_caller]
+	pub fn alloc_uninit<T: Allocatable>(&self) -> HeapMutator<'_, MaybeUninit<T>, B> {
+		self.allocator().alloc_uninit()
 	}
 
-	/// Deallocates the provided [`HeapMutator`] and consuming it,
-	/// though the use of [`HeapMutator::dealloc`] is preferred over [`Memory::dealloc`].
+	/// Allocates memory for a copy of the provided slice and returns a [`HeapMutator`] for it.
 	///
-	/// It is important to note that [`HeapMutator`] implements [`Drop`], so the underlying data
-	/// will be dropped along with the mutator when it goes out of scope.
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// let memory = Memory::new();
+	/// let mut mutator: HeapMutator<[u8]> = memory.alloc_slice_copy(&[1, 2, 3]);
+	///
+	/// mutator[0] = 4;
+	///
+	/// assert_eq!(&*mutator, &[4, 2, 3]);
+	/// assert_eq!(memory.size(), 3);
+	/// ```
+	#[track_caller]
+	pub fn alloc_slice_copy<T: Allocatable + Copy>(&self, slice: &[T]) -> HeapMutator<'_, [T], B> {
+		self.allocator().alloc_slice_copy(slice)
+	}
+
+	/// Allocates memory for a slice of length `len` and returns a [`HeapMutator`] for it.
+	///
+	/// Each element is initialized by calling `f` with its index.
 	///
 	/// # Examples
 	///
 	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// let memory = Memory::new();
+	/// let mutator: HeapMutator<[String]> = memory.alloc_slice_fill_with(3, |i| i.to_string());
+	///
+	/// assert_eq!(&*mutator, &["0", "1", "2"]);
+	/// assert_eq!(memory.count(), 1);
+	/// ```
+	///
+	/// The initializer may reset the memory, which invalidates the slice that is being filled:
+	///
+	/// ```
 	/// # use halloc::Memory;
-	/// let memory = Memory::with_size(1); // Create memory with enough space for 1 pointer
-	/// let mutator = memory.alloc(true);
+	/// let memory = Memory::new();
 	///
-	/// assert_eq!(memory.bytes(), vec![1]);
+	/// let mutator = memory.alloc_slice_fill_with(4, |i| {
+	///     if i == 2 {
+	///         memory.reset();
+	///     }
 	///
-	/// memory.dealloc(mutator);
-	/// assert_eq!(memory.bytes(), vec![]); // Value has been deallocated
+	///     i.to_string()
+	/// });
+	///
+	/// assert!(!mutator.is_valid());
+	/// assert_eq!(memory.count(), 0);
 	/// ```
-	pub fn dealloc<T: Allocatable>(&self, mutator: HeapMutator<T>) { mutator.dealloc(); }
+	#[track_caller]
+	pub fn alloc_slice_fill_with<T: Allocatable>(
+		&self,
+		len: usize,
+		f: impl FnMut(usize) -> T
+	) -> HeapMutator<'_, [T], B> {
+		self.allocator().alloc_slice_fill_with(len, f)
+	}
 
-	/// Gets all of the bytes of the underlying heap.
+	/// Allocates memory for a copy of the provided string slice and returns a [`HeapMutator`] for it.
 	///
-	/// Note that if you only need the count of contained bytes, you should use [`size`](Memory::size) instead.
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// let memory = Memory::new();
+	/// let mut mutator: HeapMutator<str> = memory.alloc_str("hello");
+	///
+	/// mutator.make_ascii_uppercase();
+	///
+	/// assert_eq!(&*mutator, "HELLO");
+	/// assert_eq!(memory.bytes(), b"HELLO".to_vec());
+	/// ```
+	#[track_caller]
+	pub fn alloc_str(&self, string: &str) -> HeapMutator<'_, str, B> {
+		self.allocator().alloc_str(string)
+	}
+
+	/// Allocates memory for all of the items of the provided iterator and returns a [`HeapMutator`] for the resulting slice.
+	///
+	/// The allocation grows (using [`Heap::realloc`]) if the iterator yields more items than its size hint suggested.
 	///
 	/// # Examples
 	///
 	/// ```
-	/// # use halloc::Memory;
-	/// let memory = Memory::with_size(1); // Create memory with enough space for 1 pointer
-	/// let _mutator = memory.alloc(42);
+	/// # use halloc::{Memory, HeapMutator};
+	/// let memory = Memory::new();
+	/// let mutator: HeapMutator<[u32]> = memory.alloc_from_iter((1..=4).filter(|n| n % 2 == 0));
 	///
-	/// let bytes = memory.bytes();
-	/// assert!(
-	///     bytes == vec![42, 0, 0, 0] ||
-	///     bytes == vec![0, 0, 0, 42]
-	/// );
+	/// assert_eq!(&*mutator, &[2, 4]);
+	/// assert_eq!(memory.size(), 8);
 	/// ```
-	pub fn bytes(&self) -> Vec<u8> { self.get_heap().bytes() }
+	#[track_caller]
+	pub fn alloc_from_iter<T: Allocatable>(
+		&self,
+		iter: impl IntoIterator<Item = T>
+	) -> HeapMutator<'_, [T], B> {
+		self.allocator().alloc_from_iter(iter)
+	}
 
-	/// Gets the byte count of the underlying heap.
+	/// Creates a [`Pool`] of values of type `T`, which reuses the memory of the dropped values
+	/// without the bookkeeping of the heap for each of them.
 	///
-	/// Not to be confused with [`count`](Memory::count)
+	/// See [`Pool::alloc`] for examples.
+	#[cfg(feature = "alloc")]
+	pub fn pool<T: Allocatable>(&self) -> Pool<'_, T, B> {
+		Pool::new(&self.heap, self.budget(), None)
+	}
+
+	/// Creates a [`Pool`] of values of type `T` that keeps the dropped values around,
+	/// resetting them to the provided value once they are reused (see [`Pool::take`]).
 	///
 	/// # Examples
 	///
 	/// ```
 	/// # use halloc::Memory;
-	/// let memory = Memory::with_size(1); // Create memory with enough space for 1 pointer
-	/// let _mutator = memory.alloc(42);
+	/// let memory = Memory::new();
+	/// let lines = memory.pool_with_reset(String::with_capacity(64));
 	///
-	/// assert_eq!(memory.size(), 4);
-	/// assert_eq!(memory.count(), 1);
+	/// for i in 0..3 {
+	///     let mut line = lines.take();
+	///     line.push_str(&format!("line {i}"));
+	///
+	///     assert_eq!(*line, format!("line {i}"));
+	///     assert_eq!(memory.size(), size_of::<String>());
+	/// }
+	///
+	/// assert_eq!(lines.capacity(), 8);
+	/// assert!(lines.is_empty());
 	/// ```
-	pub fn size(&self) -> usize { self.get_heap().size() }
+	#[cfg(feature = "alloc")]
+	pub fn pool_with_reset<T: Allocatable + Clone>(&self, reset: T) -> Pool<'_, T, B> {
+		Pool::new(&self.heap, self.budget(), Some(reset))
+	}
 
-	/// Gets the pointer count of the underlying heap.
+	/// Creates an [`Interner`] that stores unique strings on the memory, handing out a [`Symbol`](crate::Symbol) for each of them.
 	///
-	/// Not to be confused with [`size`](Memory::size)
+	/// See [`Interner`] for examples.
+	#[cfg(feature = "std")]
+	pub fn interner(&self) -> Interner<'_, B> { Interner::new(self.allocator()) }
+
+	/// Opens a [`Scope`] for the duration of `f`, deallocating all of the values allocated through it at once when `f` returns.
+	///
+	/// Values that need to outlive the scope can be escaped with [`Scope::persist`].
 	///
 	/// # Examples
 	///
 	/// ```
 	/// # use halloc::Memory;
-	/// let memory = Memory::with_size(3); // Create memory with enough space for 3 pointers
+	/// let memory = Memory::new();
 	///
-	/// let _m1 = memory.alloc(1);
-	/// let _m2 = memory.alloc(2);
-	/// let _m3 = memory.alloc(3);
+	/// let total = memory.scope(|scope| {
+	///     let numbers = scope.alloc_slice_copy(&[1, 2, 3]);
+	///     let doubled = scope.alloc_from_iter(numbers.iter().map(|n| n * 2));
 	///
-	/// assert_eq!(memory.count(), 3);
-	/// assert_eq!(memory.size(), 12); // 4 bytes for each `i32`
+	///     assert_eq!(memory.count(), 2);
+	///
+	///     doubled.iter().sum::<i32>()
+	/// });
+	///
+	/// assert_eq!(total, 12);
+	/// assert_eq!(memory.count(), 0); // Everything has been deallocated along with the scope
 	/// ```
-	pub fn count(&self) -> usize { self.get_heap().count() }
-}
+	pub fn scope<'memory, R>(&'memory self, f: impl FnOnce(&Scope<'memory, B>) -> R) -> R {
+		let scope = Scope::new(self);
+		f(&scope)
+	}
 
-impl Default for Memory {
-	fn default() -> Self { Self::new() }
+	/// Runs `f` within a [`Transaction`], undoing its changes if it returns an error or panics.
+	///
+	/// While the transaction is active, the previous states of the values are recorded in an undo journal:
+	/// values replaced with [`HeapMutator::write`] are moved into it, and the bytes of the values allocated with
+	/// [`alloc_plain`](Memory::alloc_plain) are copied into it right before they are first mutated in place
+	/// (e.g., through [`DerefMut`](std::ops::DerefMut) or [`HeapMutator::take`]).
+	/// If the transaction fails, those states are restored, and all of the values allocated on the memory
+	/// during the transaction are dropped and deallocated (invalidating their mutators, see [`HeapMutator::is_valid`]).
+	/// Values moved in from other memories (see [`HeapMutator::transfer_to`]) are kept, and deallocations made
+	/// during the transaction are not undone. Panics are only caught with the `std` feature.
+	///
+	/// The bytes of any other value cannot be copied without duplicating the resources it owns (e.g., the buffer of a [`String`]),
+	/// so the changes made in place to such a value are kept, along with any later replacement of it.
+	/// Replace those values with [`HeapMutator::write`] to have them restored.
+	///
+	/// # Panics
+	///
+	/// Panics if a transaction is already active on the memory.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	///
+	/// let mut health = memory.alloc_plain(100i32);
+	/// let mut armor = memory.alloc_plain(20i32);
+	///
+	/// let result: Result<(), &str> = memory.transaction(|tx| {
+	///     *armor -= 30;
+	///     *health -= 10;
+	///
+	///     let _effect = tx.alloc(String::from("bleeding"));
+	///
+	///     if *armor < 0 {
+	///         return Err("armor cannot be negative");
+	///     }
+	///
+	///     Ok(())
+	/// });
+	///
+	/// // All of the changes have been undone
+	/// assert!(result.is_err());
+	/// assert_eq!((*health, *armor), (100, 20));
+	/// assert_eq!(memory.count(), 2);
+	///
+	/// memory.transaction(|_| {
+	///     *armor -= 5;
+	///     Ok::<_, ()>(())
+	/// }).unwrap();
+	///
+	/// assert_eq!(*armor, 15);
+	/// ```
+	///
+	/// Values that are not plain data are restored when they are replaced, but not when they are changed in place:
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	///
+	/// let mut title = memory.alloc(String::from("squire"));
+	/// let mut log = memory.alloc(vec![String::from("joined")]);
+	///
+	/// let result = memory.transaction(|_| {
+	///     title.write(String::from("knight"));
+	///     log.push(String::from("promoted"));
+	///
+	///     Err::<(), _>("the ceremony was interrupted")
+	/// });
+	///
+	/// assert!(result.is_err());
+	/// assert_eq!(*title, "squire");
+	/// assert_eq!(*log, ["joined", "promoted"]);
+	/// ```
+	///
+	/// Values moved in during a failed transaction stay on the memory:
+	///
+	/// ```
+	/// # use halloc::{HeapMutator, Memory};
+	/// let inventory = Memory::new();
+	/// let loot = Memory::new();
+	///
+	/// let mut kept: Option<HeapMutator<str>> = None;
+	///
+	/// let result = inventory.transaction(|tx| {
+	///     let sword = loot.alloc_str("sword");
+	///     kept = Some(sword.transfer_to(&inventory).unwrap());
+	///
+	///     let _receipt = tx.alloc(1u32);
+	///     Err::<(), _>("inventory is locked")
+	/// });
+	///
+	/// assert!(result.is_err());
+	/// assert_eq!(inventory.count(), 1);
+	/// assert_eq!(kept.as_deref(), Some("sword"));
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn transaction<R, E>(
+		&self,
+		f: impl FnOnce(&Transaction<'_, B>) -> Result<R, E>
+	) -> Result<R, E> {
+		let transaction = Transaction::begin(self);
+
+		// Panics can only be caught with the standard library
+		#[cfg(feature = "std")]
+		let result = match std::panic::catch_unwind(AssertUnwindSafe(|| f(&transaction))) {
+			Ok(result) => result,
+			Err(payload) => {
+				transaction.rollback();
+				std::panic::resume_unwind(payload)
+			}
+		};
+
+		#[cfg(not(feature = "std"))]
+		let result = f(&transaction);
+
+		match result {
+			Ok(value) => {
+				transaction.commit();
+				Ok(value)
+			}
+			Err(error) => {
+				transaction.rollback();
+				Err(error)
+			}
+		}
+	}
+
+	/// Drops all of the values and deallocates all of the memory of the underlying heap at once.
+	///
+	/// Outstanding mutators are invalidated: any further use of their values panics instead of accessing freed memory
+	/// (see [`HeapMutator::is_valid`]). Since the values of such mutators might still be borrowed, they are only dropped
+	/// and deallocated once their mutators are dropped, but they are no longer counted by [`size`](Memory::size) and [`count`](Memory::count).
+	/// This also means that the values of leaked mutators (e.g., with [`std::mem::forget`]) are never deallocated,
+	/// see [`reset_unchecked`](Memory::reset_unchecked) for that.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// # use std::panic::{catch_unwind, AssertUnwindSafe};
+	/// let memory = Memory::new();
+	///
+	/// let position = memory.alloc([1.0f32, 2.0]);
+	/// let name = memory.alloc_str("player");
+	///
+	/// assert_eq!(memory.count(), 2);
+	///
+	/// memory.reset();
+	/// assert_eq!(memory.count(), 0);
+	///
+	/// // The outstanding mutators have been invalidated, so their use is detected
+	/// assert!(!position.is_valid());
+	/// assert!(catch_unwind(AssertUnwindSafe(|| position[0])).is_err());
+	/// assert!(catch_unwind(AssertUnwindSafe(|| name.len())).is_err());
+	/// ```
+	pub fn reset(&self) {
+		let allocations = self.get_heap().take_all(true);
+		destroy(&self.heap, allocations);
+	}
+
+	/// Drops all of the values and deallocates all of the memory of the underlying heap at once,
+	/// including the values of outstanding mutators.
+	///
+	/// Like with [`reset`](Memory::reset), outstanding mutators are invalidated, and dropping them afterwards does nothing.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	///
+	/// for frame in 0..3 {
+	///     let position = memory.alloc([frame as f32, 0.0]);
+	///     std::mem::forget(memory.alloc(vec![frame; 16]));
+	///
+	///     assert_eq!(position[0], frame as f32);
+	///
+	///     // Both of the values are dropped right away
+	///     unsafe { memory.reset_unchecked() };
+	///     assert!(!position.is_valid());
+	/// }
+	///
+	/// assert_eq!(memory.count(), 0);
+	/// ```
+	///
+	/// # Safety
+	///
+	/// No references to the values obtained from the outstanding mutators (e.g., through [`Deref`](std::ops::Deref)) may be used after this call.
+	pub unsafe fn reset_unchecked(&self) {
+		let allocations = self.get_heap().take_all(false);
+		destroy(&self.heap, allocations);
+	}
+
+	/// Moves the values of the outstanding mutators next to each other, filling the gaps left by the deallocated ones.
+	/// The mutators keep pointing to their values, wherever they are moved to.
+	///
+	/// Each value is moved to the lowest free memory of the backend that fits it, if that is lower than its current address,
+	/// so the result depends on the backend: [`StaticBuffer`] always packs the values towards the start of its buffer.
+	/// The observers are notified of each move as of a reallocation (see [`HeapObserver::on_realloc`]).
+	///
+	/// Since the references to the values cannot be tracked, a value is never moved once a reference to it has been handed out
+	/// (e.g., through [`Deref`](core::ops::Deref)) until the mutator is unpinned (see [`HeapMutator::unpin`]), or while it is accessed
+	/// with [`HeapMutator::with`]. See [`compact_unchecked`](Memory::compact_unchecked) for moving the pinned values as well.
+	/// Memory that is not owned by mutators (e.g., that of the values that are still being allocated) is never moved.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let buffer: &'static mut [u8] = Box::leak(Box::new([0; 4096]));
+	/// let memory = Memory::from_static_buffer(buffer);
+	///
+	/// let mut chunks: Vec<_> = (0..8u64).map(|i| Some(memory.alloc([i; 8]))).collect();
+	///
+	/// // Freeing every other chunk leaves gaps between the remaining ones
+	/// for chunk in chunks.iter_mut().step_by(2) {
+	///     chunk.take();
+	/// }
+	///
+	/// let report = memory.compact();
+	///
+	/// assert_eq!(report.moved, 4);
+	/// assert_eq!(report.moved_bytes, 4 * 64);
+	///
+	/// // The gaps held the bookkeeping of the freed chunks as well
+	/// assert!(report.reclaimed > 3 * 64);
+	///
+	/// let values: Vec<u64> = chunks.iter().flatten().map(|chunk| chunk[0]).collect();
+	/// assert_eq!(values, [1, 3, 5, 7]);
+	/// ```
+	pub fn compact(&self) -> CompactionReport {
+		let mut heap = self.get_heap();
+		self.cache.trim(&mut heap);
+		heap.compact(false)
+	}
+
+	/// Moves the values of the outstanding mutators next to each other like [`compact`](Memory::compact),
+	/// including the pinned values, which are unpinned afterwards.
+	///
+	/// Values that are being accessed with [`HeapMutator::with`] are still not moved.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let buffer: &'static mut [u8] = Box::leak(Box::new([0; 1024]));
+	/// let memory = Memory::from_static_buffer(buffer);
+	///
+	/// let padding = memory.alloc([0u8; 64]);
+	/// let name = memory.alloc_str("player");
+	/// drop(padding);
+	///
+	/// assert_eq!(&*name, "player");
+	/// assert_eq!(memory.compact().skipped, 1);
+	///
+	/// // No references to the value are used past this point
+	/// let report = unsafe { memory.compact_unchecked() };
+	///
+	/// assert_eq!(report.moved, 1);
+	/// assert_eq!(&*name, "player");
+	/// ```
+	///
+	/// # Safety
+	///
+	/// No references to the values obtained from the outstanding mutators (e.g., through [`Deref`](core::ops::Deref)) may be used after this call.
+	pub unsafe fn compact_unchecked(&self) -> CompactionReport {
+		let mut heap = self.get_heap();
+		self.cache.trim(&mut heap);
+		heap.compact(true)
+	}
+
+	/// Deallocates the provided [`HeapMutator`] and consuming it,
+	/// though the use of [`HeapMutator::dealloc`] is preferred over [`Memory::dealloc`].
+	///
+	/// It is important to note that [`HeapMutator`] implements [`Drop`], so the underlying data
+	/// will be dropped along with the mutator when it goes out of scope.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::with_size(1); // Create memory with enough space for 1 pointer
+	/// let mutator = memory.alloc_plain(true);
+	///
+	/// assert_eq!(memory.bytes(), vec![1]);
+	///
+	/// memory.dealloc(mutator);
+	/// assert_eq!(memory.bytes(), vec![]); // Value has been deallocated
+	/// ```
+	pub fn dealloc<T: Allocatable + ?Sized>(&self, mutator: HeapMutator<T, B>) {
+		mutator.dealloc();
+	}
+
+	/// Gets all of the bytes of the underlying heap.
+	///
+	/// Values that are not plain data (see [`alloc_plain`](Memory::alloc_plain)) are copied as zeros, since their bytes may be uninitialized.
+	///
+	/// Note that if you only need the count of contained bytes, you should use [`size`](Memory::size) instead.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::with_size(2); // Create memory with enough space for 2 pointers
+	/// let _mutator = memory.alloc_plain(42u32);
+	/// let _buffer = memory.alloc_uninit::<[u8; 2]>();
+	///
+	/// let bytes = memory.bytes();
+	/// assert!(
+	///     bytes == vec![42, 0, 0, 0, 0, 0] ||
+	///     bytes == vec![0, 0, 0, 42, 0, 0]
+	/// );
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn bytes(&self) -> Vec<u8> { self.get_heap().bytes() }
+
+	/// Gets the byte count of the underlying heap, including the heaps of the child memories (see [`child`](Memory::child)).
+	///
+	/// Not to be confused with [`count`](Memory::count)
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::with_size(1); // Create memory with enough space for 1 pointer
+	/// let _mutator = memory.alloc(42);
+	///
+	/// assert_eq!(memory.size(), 4);
+	/// assert_eq!(memory.count(), 1);
+	/// ```
+	pub fn size(&self) -> usize { self.get_heap().budget.bytes() }
+
+	/// Gets the pointer count of the underlying heap, including the heaps of the child memories (see [`child`](Memory::child)).
+	///
+	/// Not to be confused with [`size`](Memory::size)
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::with_size(3); // Create memory with enough space for 3 pointers
+	///
+	/// let _m1 = memory.alloc(1);
+	/// let _m2 = memory.alloc(2);
+	/// let _m3 = memory.alloc(3);
+	///
+	/// assert_eq!(memory.count(), 3);
+	/// assert_eq!(memory.size(), 12); // 4 bytes for each `i32`
+	/// ```
+	pub fn count(&self) -> usize { self.get_heap().budget.count() }
+
+	/// Gets a snapshot of the allocation statistics of the underlying heap.
+	///
+	/// See [`HeapStats`] for the available statistics.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{HeapStats, Memory};
+	/// let memory = Memory::new();
+	///
+	/// let numbers = memory.alloc_slice_copy(&[1u64, 2, 3]);
+	/// drop(memory.alloc(5u8));
+	///
+	/// let stats = memory.stats();
+	/// assert_eq!(stats.current_bytes, 24);
+	/// assert_eq!(stats.current_count, 1);
+	/// assert_eq!(stats.peak_bytes, 25);
+	/// assert_eq!(stats.peak_count, 2);
+	/// assert_eq!(stats.total_allocs, 2);
+	/// assert_eq!(stats.total_deallocs, 1);
+	/// assert_eq!(stats.size_histogram[HeapStats::size_class(24)], 1);
+	/// # drop(numbers);
+	/// ```
+	///
+	/// Backends that round up the blocks of the values report the bytes that they reserve beyond them:
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let buffer: &'static mut [u8] = Box::leak(Box::new([0; 1024]));
+	/// let memory = Memory::from_static_buffer(buffer);
+	///
+	/// let flag = memory.alloc(true);
+	/// let words = memory.alloc([0u64; 2]);
+	///
+	/// // The blocks are made of whole words
+	/// assert_eq!(memory.stats().alignment_waste, size_of::<usize>() - 1);
+	///
+	/// drop(flag);
+	/// assert_eq!(memory.stats().alignment_waste, 0);
+	/// # drop(words);
+	/// ```
+	pub fn stats(&self) -> HeapStats { self.get_heap().stats() }
+
+	/// Gathers the utilization of the chunks that the underlying heap allocates from,
+	/// which shows how well the freed memory can be reused (see [`Fragmentation`]).
+	///
+	/// Returns [`None`] if the backend does not manage chunks of its own, such as [`SystemBackend`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// # #[repr(align(16))]
+	/// # struct Aligned([u8; 2048]);
+	/// let buffer: &'static mut [u8] = &mut Box::leak(Box::new(Aligned([0; 2048]))).0;
+	/// let memory = Memory::from_static_buffer(buffer);
+	///
+	/// let mut values: Vec<_> = (0..4).map(|_| Some(memory.alloc([0u8; 128]))).collect();
+	///
+	/// // Each value takes up the same space along with its bookkeeping
+	/// let footprint = memory.fragmentation().unwrap().in_use / 4;
+	/// values[1].take();
+	///
+	/// let fragmentation = memory.fragmentation().unwrap();
+	/// let free = 2048 - 3 * footprint;
+	///
+	/// assert_eq!(fragmentation.reserved, 2048);
+	/// assert_eq!(fragmentation.in_use, 3 * footprint);
+	/// assert_eq!(fragmentation.free_blocks, 2);
+	/// assert_eq!(fragmentation.largest_free, free - footprint);
+	/// assert_eq!(fragmentation.ratio, footprint as f64 / free as f64);
+	///
+	/// assert!(Memory::new().fragmentation().is_none());
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn fragmentation(&self) -> Option<Fragmentation> {
+		// The free blocks of the cache are not counted as used
+		let mut heap = self.get_heap();
+		self.cache.trim(&mut heap);
+		heap.fragmentation()
+	}
+
+	/// Copies the contents of all the values of the underlying heap, along with their layouts and types.
+	///
+	/// The values of child memories (see [`child`](Memory::child)) are not included. See [`restore`](Memory::restore) for examples.
+	#[cfg(feature = "alloc")]
+	pub fn snapshot(&self) -> Snapshot { Snapshot::capture(&self.get_heap()) }
+
+	/// Writes the contents captured by [`snapshot`](Memory::snapshot) back into the same allocations.
+	///
+	/// Only the values allocated with [`alloc_plain`](Memory::alloc_plain) can be restored. Nothing is written
+	/// (and an error is returned instead) if the snapshot contains any other values, or if any of the captured values
+	/// has since been deallocated, resized or cast to another type.
+	///
+	/// Allocations made after the snapshot are left untouched.
+	///
+	/// Like [`compact`](Memory::compact), the values that a reference has been handed out for (e.g., through [`Deref`](core::ops::Deref))
+	/// cannot be overwritten until their mutators are unpinned (see [`HeapMutator::unpin`]), and neither can the values
+	/// that are accessed with [`HeapMutator::with`]. See [`restore_unchecked`](Memory::restore_unchecked) for overwriting the pinned values as well.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, RestoreError};
+	/// let memory = Memory::new();
+	///
+	/// let mut position = memory.alloc_plain([0.0f32, 0.0]);
+	/// let mut tick = memory.alloc_plain(0u64);
+	///
+	/// let checkpoint = memory.snapshot();
+	///
+	/// position.with_mut(|position| position[0] = 5.0);
+	/// tick.with_mut(|tick| *tick += 1);
+	///
+	/// memory.restore(&checkpoint).unwrap();
+	/// assert_eq!(*position, [0.0, 0.0]);
+	/// assert_eq!(*tick, 0);
+	///
+	/// // The references handed out above pin the values
+	/// assert!(matches!(
+	///     memory.restore(&checkpoint),
+	///     Err(RestoreError::Borrowed { .. })
+	/// ));
+	///
+	/// position.unpin();
+	/// tick.unpin();
+	/// assert!(memory.restore(&checkpoint).is_ok());
+	///
+	/// // Values that own resources cannot be restored
+	/// let _name = memory.alloc(String::from("player"));
+	/// assert!(matches!(
+	///     memory.restore(&memory.snapshot()),
+	///     Err(RestoreError::NotPlainData { .. })
+	/// ));
+	/// ```
+	#[cfg(feature = "alloc")]
+	pub fn restore(&self, snapshot: &Snapshot) -> Result<(), RestoreError> {
+		snapshot.restore(&mut self.get_heap(), false)
+	}
+
+	/// Writes the contents captured by [`snapshot`](Memory::snapshot) back into the same allocations like [`restore`](Memory::restore),
+	/// including the pinned values.
+	///
+	/// Values that are being accessed with [`HeapMutator::with`] are still not overwritten.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	///
+	/// let mut health = memory.alloc_plain(100u32);
+	/// let checkpoint = memory.snapshot();
+	///
+	/// *health -= 30;
+	/// assert_eq!(*health, 70);
+	///
+	/// // No references to the value are used past this point
+	/// unsafe { memory.restore_unchecked(&checkpoint) }.unwrap();
+	///
+	/// assert_eq!(*health, 100);
+	/// ```
+	///
+	/// # Safety
+	///
+	/// No references to the values obtained from the outstanding mutators (e.g., through [`Deref`](core::ops::Deref)) may be used after this call.
+	#[cfg(feature = "alloc")]
+	pub unsafe fn restore_unchecked(&self, snapshot: &Snapshot) -> Result<(), RestoreError> {
+		snapshot.restore(&mut self.get_heap(), true)
+	}
+
+	/// Copies all of the values of the underlying heap, along with their layouts, types and allocation sites.
+	///
+	/// The values of child memories (see [`child`](Memory::child)) are not included.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// let _scores = memory.alloc([10u16, 20, 30]);
+	///
+	/// let dump = memory.dump();
+	/// let entry = &dump.entries()[0];
+	///
+	/// assert_eq!(entry.layout().size(), 6);
+	/// assert_eq!(entry.type_name(), Some("[u16; 3]"));
+	/// assert_eq!(entry.site().line, line!() - 7);
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn dump(&self) -> HeapDump { HeapDump::capture(&self.get_heap()) }
+
+	/// Writes a [`dump`](Memory::dump) of the underlying heap to the provided writer,
+	/// in a format that can be read back with [`HeapDump::read_from`].
+	///
+	/// The heap lock is released before anything is written.
+	#[cfg(feature = "std")]
+	pub fn dump_to(&self, writer: impl Write) -> io::Result<()> { self.dump().write_to(writer) }
+
+	/// Writes the profile of the underlying heap to the provided writer, in the `dhat-heap.json` format
+	/// of [DHAT](https://valgrind.org/docs/manual/dh-manual.html), which can be opened with its
+	/// [viewer](https://nnethercote.github.io/dh_view/dh_view.html) offline.
+	///
+	/// The profile covers all of the allocations made since the memory was created, grouped by their allocation sites:
+	/// their sizes, their lifetimes, and the usage at the peak of the heap and at the time of the call.
+	/// Values transferred from other memories (see [`HeapMutator::transfer_to`]) are counted as allocated upon their arrival.
+	///
+	/// Only available with the `profiling` feature.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	///
+	/// for i in 0..10u64 {
+	///     let _temporary = memory.alloc([i; 8]);
+	/// }
+	///
+	/// let mut profile = vec![];
+	/// memory.write_profile(&mut profile).unwrap();
+	///
+	/// let profile = String::from_utf8(profile).unwrap();
+	/// assert!(profile.contains("\"dhatFileVersion\":2"));
+	/// assert!(profile.contains("\"tb\":640,\"tbk\":10"));
+	/// ```
+	#[cfg(feature = "profiling")]
+	pub fn write_profile(&self, mut writer: impl Write) -> io::Result<()> {
+		// Rendering the profile first, so that the heap lock is not held while writing
+		let profile = self.get_heap().profile.to_json();
+		writer.write_all(profile.as_bytes())
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl Default for Memory {
+	fn default() -> Self { Self::new() }
+}
+
+impl<B: RawBackend> Drop for Memory<B> {
+	fn drop(&mut self) {
+		// Recording the values of the cache, so that the heap drops them, and returning its free memory to the backend
+		if let Some(mut heap) = self.try_get_heap() {
+			self.cache.trim(&mut heap);
+		}
+	}
+}
+
+#[derive(Debug)]
+/// The allocation logic shared between [`Memory`] and [`Scope`](crate::Scope).
+///
+/// See the corresponding methods on [`Memory`] for documentation.
+pub(crate) struct Allocator<'heap, B: RawBackend> {
+	/// Heap that the values are allocated onto
+	pub(crate) heap: &'heap Mutex<Heap<B>>,
+
+	/// Cache of the memory that the heap belongs to
+	pub(crate) cache: &'heap Cache,
+
+	/// Identifier of the scope that owns the allocations, if any
+	pub(crate) scope: Option<usize>
+}
+
+impl<B: RawBackend> Clone for Allocator<'_, B> {
+	fn clone(&self) -> Self { *self }
+}
+
+impl<B: RawBackend> Copy for Allocator<'_, B> {}
+
+impl<'heap, B: RawBackend> Allocator<'heap, B> {
+	/// Acquires the [`Heap`] lock.
+	fn lock(&self) -> HeapGuard<'heap, B> {
+		HeapGuard {
+			guard: Some(cache::lock(self.heap, Some(self.cache)).expect("Heap lock failed"))
+		}
+	}
+
+	/// Acquires the [`Heap`] lock and allocates memory for a given [`Layout`], assigning it to the current scope.
+	///
+	/// Fails if the allocation would exceed the byte limit of the heap.
+	#[track_caller]
+	fn try_alloc_raw(
+		&self,
+		layout: Layout,
+		type_tag: TypeTag
+	) -> Result<(HeapGuard<'heap, B>, NonNull<u8>), AllocError> {
+		let mut heap = self.lock();
+		let ptr = heap.try_alloc_tagged(layout, Some(type_tag))?;
+		unsafe { heap.record_mut(ptr) }.scope = self.scope;
+
+		Ok((heap, ptr))
+	}
+
+	/// Acquires the [`Heap`] lock and allocates memory for a given [`Layout`], assigning it to the current scope.
+	///
+	/// Panics if the allocation would exceed the byte limit of the heap.
+	#[track_caller]
+	fn alloc_raw(&self, layout: Layout, type_tag: TypeTag) -> (HeapGuard<'heap, B>, NonNull<u8>) {
+		// The lock is released before panicking, so that the heap is not poisoned
+		self.try_alloc_raw(layout, type_tag)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Creates a mutator for the provided pointer.
+	///
+	/// # Safety
+	///
+	/// The pointer must have been allocated by [`alloc_raw`](Allocator::alloc_raw) and point to a valid value.
+	unsafe fn mutator<T: Allocatable + ?Sized>(
+		&self,
+		heap: &mut Heap<B>,
+		ptr: NonNull<T>
+	) -> HeapMutator<'heap, T, B> {
+		// The slot has been allocated along with the memory
+		let slot = unsafe { heap.slot(ptr.cast::<u8>()) }.expect("Slot allocation failed");
+		unsafe { self.bind(ptr, slot) }
+	}
+
+	/// Creates a mutator for the provided pointer with the provided slot of its allocation.
+	///
+	/// # Safety
+	///
+	/// See [`mutator`](Allocator::mutator). The slot must belong to the allocation.
+	unsafe fn bind<T: Allocatable + ?Sized>(
+		&self,
+		ptr: NonNull<T>,
+		slot: NonNull<Slot>
+	) -> HeapMutator<'heap, T, B> {
+		let mut mutator = unsafe { HeapMutator::from_slot(ptr, self.heap, Some(self.cache), slot) };
+
+		// The memory of scoped allocations is deallocated by the scope itself
+		mutator.deallocated = self.scope.is_some();
+
+		mutator
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc<T: Allocatable>(&self, value: T) -> HeapMutator<'heap, T, B> {
+		self.try_alloc(value)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	#[track_caller]
+	pub(crate) fn try_alloc<T: Allocatable>(
+		&self,
+		value: T
+	) -> Result<HeapMutator<'heap, T, B>, AllocError> {
+		self.try_alloc_tagged(value, TypeTag::of::<T>())
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc_plain<T: PlainData>(&self, value: T) -> HeapMutator<'heap, T, B> {
+		self.try_alloc_tagged(value, TypeTag::plain::<T>())
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Allocates memory for the provided value, recording it as a value of the provided type.
+	#[track_caller]
+	fn try_alloc_tagged<T: Allocatable>(
+		&self,
+		value: T,
+		type_tag: TypeTag
+	) -> Result<HeapMutator<'heap, T, B>, AllocError> {
+		// Creating a suitable layout for `T`
+		let layout = Layout::new::<T>();
+
+		// Allocating through the cache first, which does not lock the heap
+		let cached = self.cache.alloc(
+			self.heap,
+			layout,
+			type_tag,
+			DropGlue::of::<T>(),
+			self.scope,
+			Location::caller()
+		);
+
+		if let Some((ptr, slot)) = cached {
+			return Ok(unsafe {
+				write(ptr.cast::<T>().as_ptr(), value);
+				self.bind(ptr.cast::<T>(), slot)
+			});
+		}
+
+		// Allocating a pointer, which doesn't need to be zeroed since it is overwritten right away
+		let (mut heap, ptr) = self.try_alloc_raw(layout, type_tag)?;
+
+		Ok(unsafe {
+			// Writing the provided value to the allocated pointer
+			write(ptr.cast::<T>().as_ptr(), value);
+			heap.set_contents(ptr, DropGlue::of::<T>(), type_tag);
+
+			// Creating the mutator
+			self.mutator(&mut heap, ptr.cast::<T>())
+		})
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc_with<T: Allocatable>(
+		&self,
+		f: impl FnOnce() -> T
+	) -> HeapMutator<'heap, T, B> {
+		// Calling `f` before allocating, since it may use the memory as well (e.g., reset it)
+		let value = f();
+		self.alloc(value)
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc_uninit<T: Allocatable>(&self) -> HeapMutator<'heap, MaybeUninit<T>, B> {
+		// Creating a suitable layout for `T`
+		let layout = Layout::new::<T>();
+
+		// Allocating a pointer
+		let type_tag = TypeTag::of::<MaybeUninit<T>>();
+		let (mut heap, ptr) = self.alloc_raw(layout, type_tag);
+		unsafe { heap.set_contents(ptr, None, type_tag) };
+
+		unsafe { self.mutator(&mut heap, ptr.cast::<MaybeUninit<T>>()) }
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc_slice_copy<T: Allocatable + Copy>(
+		&self,
+		slice: &[T]
+	) -> HeapMutator<'heap, [T], B> {
+		// Creating a suitable layout for the slice
+		let layout = Layout::for_value(slice);
+
+		// Allocating a pointer
+		let type_tag = TypeTag::of::<[T]>();
+		let (mut heap, ptr) = self.alloc_raw(layout, type_tag);
+		unsafe { heap.set_contents(ptr, None, type_tag) };
+
+		let ptr = ptr.cast::<T>();
+
+		unsafe {
+			// Copying the elements over to the allocated pointer
+			core::ptr::copy_nonoverlapping(slice.as_ptr(), ptr.as_ptr(), slice.len());
+
+			// Creating the mutator
+			self.mutator(&mut heap, NonNull::slice_from_raw_parts(ptr, slice.len()))
+		}
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc_slice_fill_with<T: Allocatable>(
+		&self,
+		len: usize,
+		mut f: impl FnMut(usize) -> T
+	) -> HeapMutator<'heap, [T], B> {
+		// Creating a suitable layout for the slice
+		let layout = Layout::array::<T>(len).expect("Layout creation failed");
+
+		// Allocating a pointer without holding the lock while `f` runs, since it may allocate too
+		let mut guard = SliceGuard::new(*self, layout, len);
+
+		while guard.len < len {
+			let value = f(guard.len);
+			guard.push(value);
+		}
+
+		guard.finish()
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc_str(&self, string: &str) -> HeapMutator<'heap, str, B> {
+		self.try_alloc_str(string)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	#[track_caller]
+	pub(crate) fn try_alloc_str(
+		&self,
+		string: &str
+	) -> Result<HeapMutator<'heap, str, B>, AllocError> {
+		// Creating a suitable layout for the string
+		let layout = Layout::for_value(string);
+
+		// Allocating a pointer
+		// A string owns no resources, and all of its bytes are initialized
+		let type_tag = TypeTag {
+			plain: true,
+			..TypeTag::of::<str>()
+		};
+		let (mut heap, ptr) = self.try_alloc_raw(layout, type_tag)?;
+		unsafe { heap.set_contents(ptr, None, type_tag) };
+
+		Ok(unsafe {
+			// Copying the bytes over to the allocated pointer
+			core::ptr::copy_nonoverlapping(string.as_ptr(), ptr.as_ptr(), string.len());
+
+			// `str` has the same layout as `[u8]`, and the bytes were copied from a valid `str`
+			let str_ptr = NonNull::slice_from_raw_parts(ptr, string.len()).as_ptr() as *mut str;
+
+			// Creating the mutator
+			self.mutator(&mut heap, NonNull::new_unchecked(str_ptr))
+		})
+	}
+
+	#[track_caller]
+	pub(crate) fn alloc_from_iter<T: Allocatable>(
+		&self,
+		iter: impl IntoIterator<Item = T>
+	) -> HeapMutator<'heap, [T], B> {
+		let mut iter = iter.into_iter();
+
+		// Starting with the lower bound of the iterator size
+		let capacity = iter.size_hint().0;
+		let layout = Layout::array::<T>(capacity).expect("Layout creation failed");
+
+		let mut guard = SliceGuard::new(*self, layout, capacity);
+
+		for value in iter.by_ref() {
+			if guard.len == guard.capacity {
+				guard.grow(core::cmp::max(guard.capacity * 2, 4));
+			}
+
+			guard.push(value);
+		}
+
+		// Shrinking the allocation to the actual length
+		if guard.len != guard.capacity {
+			guard.grow(guard.len);
+		}
+
+		guard.finish()
+	}
+}
+
+/// A lock of the [`Heap`] that calls the pressure callbacks of the crossed thresholds once it is released.
+///
+/// The callbacks are called without holding the lock, since they may use the memory as well.
+struct HeapGuard<'heap, B: RawBackend> {
+	/// Underlying lock, which is only taken out when the guard is dropped
+	guard: Option<MutexGuard<'heap, Heap<B>>>
+}
+
+impl<B: RawBackend> Deref for HeapGuard<'_, B> {
+	type Target = Heap<B>;
+
+	fn deref(&self) -> &Self::Target { self.guard.as_ref().expect("Heap lock released") }
+}
+
+impl<B: RawBackend> DerefMut for HeapGuard<'_, B> {
+	fn deref_mut(&mut self) -> &mut Self::Target {
+		self.guard.as_mut().expect("Heap lock released")
+	}
+}
+
+impl<B: RawBackend> Drop for HeapGuard<'_, B> {
+	fn drop(&mut self) {
+		let Some(heap) = self.guard.take() else {
+			return;
+		};
+
+		let crossed = heap.budget.crossed();
+		drop(heap);
+		crossed.call();
+	}
+}
+
+/// A partially initialized slice on the [`Heap`], which drops its initialized elements
+/// and deallocates the memory if it is not finished (e.g., when the initializer panics).
+///
+/// The slice is borrowed through its [`Slot`] while it is being filled, so that the initializers can reset the memory
+/// (or roll back a transaction) without freeing it: the allocation is detached instead, like one referenced by a mutator.
+struct SliceGuard<'heap, T: Allocatable, B: RawBackend> {
+	/// Allocator that the slice was allocated with
+	allocator: Allocator<'heap, B>,
+
+	/// State of the allocation, which the guard holds a reference to, borrowed until the guard is finished or dropped
+	slot: NonNull<Slot>,
+
+	/// Pointer to the first element of the slice
+	ptr: NonNull<T>,
+
+	/// Count of elements that the allocation has space for
+	capacity: usize,
+
+	/// Count of initialized elements
+	len: usize
+}
+
+impl<'heap, T: Allocatable, B: RawBackend> SliceGuard<'heap, T, B> {
+	/// Allocates an uninitialized slice with space for `capacity` elements.
+	#[track_caller]
+	fn new(allocator: Allocator<'heap, B>, layout: Layout, capacity: usize) -> Self {
+		let (mut heap, ptr) = allocator.alloc_raw(layout, TypeTag::of::<[T]>());
+
+		// The slot has been allocated along with the memory
+		let slot = unsafe { heap.slot(ptr) }.expect("Slot allocation failed");
+		let state = unsafe { slot.as_ref() };
+		state.add_reference();
+		state.borrow();
+
+		Self {
+			allocator,
+			slot,
+			ptr: ptr.cast::<T>(),
+			capacity,
+			len: 0
+		}
+	}
+
+	/// Gets the state of the allocation.
+	fn slot(&self) -> &Slot { unsafe { self.slot.as_ref() } }
+
+	/// Writes the value after the last initialized element.
+	fn push(&mut self, value: T) {
+		debug_assert!(self.len < self.capacity);
+
+		unsafe { write(self.ptr.as_ptr().add(self.len), value) }
+		self.len += 1;
+	}
+
+	/// Resizes the allocation to have space for `capacity` elements.
+	fn grow(&mut self, capacity: usize) {
+		let layout = Layout::array::<T>(self.capacity).expect("Layout creation failed");
+		let new_layout = Layout::array::<T>(capacity).expect("Layout creation failed");
+
+		let mut heap = self.allocator.lock();
+
+		match heap.try_realloc(self.ptr.cast::<u8>(), layout, new_layout.size()) {
+			Ok(ptr) => self.ptr = ptr.cast::<T>(),
+			Err(error) => {
+				// Releasing the lock before panicking, so that the heap is not poisoned
+				drop(heap);
+				panic!("{error}");
+			}
+		}
+
+		self.capacity = capacity;
+	}
+
+	/// Turns the initialized slice into a [`HeapMutator`].
+	fn finish(self) -> HeapMutator<'heap, [T], B> {
+		debug_assert_eq!(self.len, self.capacity);
+
+		let guard = core::mem::ManuallyDrop::new(self);
+		let ptr = guard.ptr.cast::<u8>();
+
+		// The elements can now be dropped by the heap
+		let mut heap = guard.allocator.lock();
+		unsafe {
+			heap.set_contents(
+				ptr,
+				DropGlue::of_slice::<T>(guard.len),
+				TypeTag::of::<[T]>()
+			)
+		};
+
+		// The mutator is invalid if the allocation has been detached in the meantime
+		let mutator = unsafe {
+			guard.allocator.mutator(
+				&mut heap,
+				NonNull::slice_from_raw_parts(guard.ptr, guard.len)
+			)
+		};
+
+		// The mutator holds a reference of its own, so that of the guard is never the last one
+		guard.slot().release();
+		let _ = guard.slot().remove_reference();
+
+		mutator
+	}
+}
+
+impl<'heap, T: Allocatable, B: RawBackend> Drop for SliceGuard<'heap, T, B> {
+	fn drop(&mut self) {
+		let layout = Layout::array::<T>(self.capacity).expect("Layout creation failed");
+
+		unsafe {
+			// Dropping the initialized elements
+			core::ptr::slice_from_raw_parts_mut(self.ptr.as_ptr(), self.len).drop_in_place();
+		}
+
+		let Some(mut heap) = cache::lock(self.allocator.heap, Some(self.allocator.cache)) else {
+			return;
+		};
+
+		// Detached allocations are deallocated as well, since no mutator points to them
+		if !self.slot().is_released() {
+			heap.dealloc(self.ptr.cast::<u8>(), layout);
+		}
+
+		self.slot().release();
+
+		if self.slot().remove_reference() {
+			unsafe { heap.deallocate_slot(self.slot) }
+		}
+	}
 }
@@ -0,0 +1,96 @@
+#[cfg(feature = "alloc")]
+use alloc::sync::Arc;
+use core::alloc::Layout;
+use core::any::TypeId;
+#[cfg(feature = "alloc")]
+use core::fmt::{self, Debug, Formatter};
+use core::panic::Location;
+use core::ptr::NonNull;
+
+use crate::heap::Allocation;
+
+/// Receives the allocation events of a [`Heap`](crate::Heap), once registered with
+/// [`Memory::add_observer`](crate::Memory::add_observer) or [`Heap::add_observer`](crate::Heap::add_observer).
+///
+/// All of the methods do nothing by default, so that only the events of interest have to be handled.
+///
+/// **Note:** the observers are called while the heap is locked, so they must not use the memory they observe.
+pub trait HeapObserver: Send + Sync {
+	/// Called after memory has been allocated.
+	fn on_alloc(&self, _allocation: &AllocationInfo) {}
+
+	/// Called right before memory is deallocated.
+	fn on_dealloc(&self, _allocation: &AllocationInfo) {}
+
+	/// Called after memory has been resized (e.g., with [`Heap::realloc`](crate::Heap::realloc))
+	/// or moved (e.g., by [`Memory::compact`](crate::Memory::compact)), with the allocation before and after the change.
+	fn on_realloc(&self, _old: &AllocationInfo, _new: &AllocationInfo) {}
+
+	/// Called after a value has been cast to another type with [`HeapMutator::cast`](crate::HeapMutator::cast)
+	/// or [`HeapMutator::cast_unchecked`](crate::HeapMutator::cast_unchecked), with the allocation before and after the cast.
+	///
+	/// Since [`cast`](crate::HeapMutator::cast) moves the value, its new allocation is also reported to [`on_alloc`](HeapObserver::on_alloc)
+	/// and its previous allocation to [`on_dealloc`](HeapObserver::on_dealloc).
+	fn on_cast(&self, _from: &AllocationInfo, _to: &AllocationInfo) {}
+}
+
+#[cfg(feature = "alloc")]
+impl<T: HeapObserver + ?Sized> HeapObserver for Arc<T> {
+	fn on_alloc(&self, allocation: &AllocationInfo) { (**self).on_alloc(allocation) }
+
+	fn on_dealloc(&self, allocation: &AllocationInfo) { (**self).on_dealloc(allocation) }
+
+	fn on_realloc(&self, old: &AllocationInfo, new: &AllocationInfo) {
+		(**self).on_realloc(old, new)
+	}
+
+	fn on_cast(&self, from: &AllocationInfo, to: &AllocationInfo) { (**self).on_cast(from, to) }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// The description of an allocation passed to a [`HeapObserver`].
+pub struct AllocationInfo {
+	/// Unique identifier of the allocation, which stays the same when it is resized
+	pub id: u64,
+
+	/// Pointer to the allocated memory
+	pub ptr: NonNull<u8>,
+
+	/// Layout of the allocated memory
+	pub layout: Layout,
+
+	/// Identifier of the value's type, if it is known
+	pub type_id: Option<TypeId>,
+
+	/// Name of the value's type, if it is known
+	pub type_name: Option<&'static str>,
+
+	/// Location of the code that requested the allocation
+	pub site: &'static Location<'static>
+}
+
+#[cfg(feature = "alloc")]
+#[derive(Clone)]
+/// A registered [`HeapObserver`].
+pub(crate) struct Observer(pub(crate) Arc<dyn HeapObserver>);
+
+impl AllocationInfo {
+	/// Describes the provided allocation.
+	pub(crate) fn of(allocation: &Allocation) -> Self {
+		Self {
+			id: allocation.id,
+			ptr: allocation.ptr,
+			layout: allocation.layout,
+			type_id: allocation.type_tag.map(|tag| tag.id),
+			type_name: allocation.type_tag.map(|tag| tag.name),
+			site: allocation.site
+		}
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl Debug for Observer {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Observer").finish_non_exhaustive()
+	}
+}
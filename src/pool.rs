@@ -0,0 +1,317 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::cell::RefCell;
+use core::fmt::{self, Debug, Formatter};
+use core::mem::size_of;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::budget::Budget;
+use crate::sync::Mutex;
+use crate::{AllocError, Allocatable, Heap, RawBackend, SystemBackend};
+
+/// Count of slots within the first chunk of a [`Pool`]
+const INITIAL_CAPACITY: usize = 8;
+
+/// A pool of values of the same type, created with [`Memory::pool`](crate::Memory::pool).
+///
+/// The values are stored in chunks of slots allocated from the heap, and the slots of dropped values are reused.
+/// Unlike [`Memory::alloc`](crate::Memory::alloc), allocating and dropping a value does not lock the heap and is not recorded by it,
+/// so the values are not visible to [`Memory::stats`](crate::Memory::stats), snapshots or observers.
+/// They are still counted by [`Memory::size`](crate::Memory::size) and [`Memory::count`](crate::Memory::count),
+/// and the byte limit of the memory applies to them.
+///
+/// The chunks are only returned to the heap once the pool is dropped.
+pub struct Pool<'memory, T: Allocatable, B: RawBackend = SystemBackend> {
+	/// Heap that the chunks are allocated from
+	heap: &'memory Mutex<Heap<B>>,
+
+	/// State shared with the values of the pool
+	slots: Slots<T>
+}
+
+/// The state of a [`Pool`] that its values return their slots to.
+struct Slots<T> {
+	/// Chunks and free slots of the pool
+	state: RefCell<PoolState<T>>,
+
+	/// Accounting of the memory that the pool belongs to
+	budget: Arc<Budget>,
+
+	/// Value that the reused values are reset to, if any
+	///
+	/// If set, the values of the free slots are kept around instead of being dropped
+	reset: Option<T>
+}
+
+/// The chunks and free slots of a [`Pool`].
+struct PoolState<T> {
+	/// Allocated chunks, along with the count of slots within them
+	chunks: Vec<(NonNull<T>, usize)>,
+
+	/// Count of slots of the last chunk that have never been used
+	unused: usize,
+
+	/// Slots of the dropped values
+	free: Vec<NonNull<T>>
+}
+
+/// A value stored within a [`Pool`], which returns its slot to the pool once dropped.
+pub struct Pooled<'pool, T: Allocatable> {
+	/// Pointer to the value
+	ptr: NonNull<T>,
+
+	/// State of the pool that the value belongs to
+	slots: &'pool Slots<T>
+}
+
+impl<'memory, T: Allocatable, B: RawBackend> Pool<'memory, T, B> {
+	/// Creates an empty pool on the provided heap, with an optional reset value.
+	pub(crate) fn new(
+		heap: &'memory Mutex<Heap<B>>,
+		budget: Arc<Budget>,
+		reset: Option<T>
+	) -> Self {
+		Self {
+			heap,
+			slots: Slots {
+				state: RefCell::new(PoolState {
+					chunks: vec![],
+					unused: 0,
+					free: vec![]
+				}),
+				budget,
+				reset
+			}
+		}
+	}
+
+	/// Moves the provided value into the pool, reusing the slot of a dropped value if there is one.
+	///
+	/// # Panics
+	///
+	/// Panics if the value would exceed the byte limit of the memory (see [`try_alloc`](Pool::try_alloc)).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// struct Particle {
+	///     position: [f32; 2],
+	///     ttl: u32
+	/// }
+	///
+	/// impl halloc::Allocatable for Particle {}
+	///
+	/// let memory = Memory::new();
+	/// let particles = memory.pool::<Particle>();
+	///
+	/// for frame in 0..100 {
+	///     let particle = particles.alloc(Particle {
+	///         position: [frame as f32, 0.0],
+	///         ttl: 10
+	///     });
+	///
+	///     assert_eq!(particle.ttl, 10);
+	///     assert_eq!(memory.count(), 1);
+	/// }
+	///
+	/// // The same slot has been reused every frame
+	/// assert_eq!(particles.capacity(), 8);
+	/// assert_eq!(memory.count(), 0);
+	/// ```
+	pub fn alloc(&self, value: T) -> Pooled<'_, T> {
+		self.try_alloc(value)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Moves the provided value into the pool like [`alloc`](Pool::alloc),
+	/// failing if the value would exceed the byte limit of the memory, or if the backend is exhausted.
+	pub fn try_alloc(&self, value: T) -> Result<Pooled<'_, T>, AllocError> {
+		let (ptr, reused) = self.acquire()?;
+
+		if reused && self.slots.reset.is_some() {
+			// Dropping the value that has been kept around
+			unsafe { *ptr.as_ptr() = value }
+		} else {
+			unsafe { ptr.write(value) }
+		}
+
+		Ok(Pooled {
+			ptr,
+			slots: &self.slots
+		})
+	}
+
+	/// Takes a value that is equal to the reset value of the pool (see [`Memory::pool_with_reset`](crate::Memory::pool_with_reset)).
+	///
+	/// The values of the free slots are reset with [`Clone::clone_from`], so that their resources (e.g., buffers) are reused.
+	///
+	/// # Panics
+	///
+	/// Panics if the pool has no reset value, or if the value would exceed the byte limit of the memory.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	/// let buffers = memory.pool_with_reset(Vec::<u8>::new());
+	///
+	/// let mut buffer = buffers.take();
+	/// buffer.extend_from_slice(b"request");
+	/// drop(buffer);
+	///
+	/// // The buffer is cleared, but keeps its capacity
+	/// let buffer = buffers.take();
+	///
+	/// assert!(buffer.is_empty());
+	/// assert!(buffer.capacity() >= 7);
+	/// ```
+	pub fn take(&self) -> Pooled<'_, T>
+	where
+		T: Clone {
+		let reset = self.slots.reset.as_ref().expect("Pool has no reset value");
+		let (ptr, reused) = self.acquire().unwrap_or_else(|error| panic!("{error}"));
+
+		if reused {
+			unsafe { (*ptr.as_ptr()).clone_from(reset) }
+		} else {
+			unsafe { ptr.write(reset.clone()) }
+		}
+
+		Pooled {
+			ptr,
+			slots: &self.slots
+		}
+	}
+
+	/// Gets the count of values that are currently stored within the pool.
+	pub fn len(&self) -> usize {
+		let state = self.slots.state.borrow();
+		state.capacity() - state.unused - state.free.len()
+	}
+
+	/// Shows whether there are no values stored within the pool.
+	pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+	/// Gets the count of slots that have been allocated, which is the count of values that can be stored without allocating.
+	pub fn capacity(&self) -> usize { self.slots.state.borrow().capacity() }
+
+	/// Charges the budget for a value and finds a slot for it, returning whether the slot has been used before.
+	fn acquire(&self) -> Result<(NonNull<T>, bool), AllocError> {
+		let budget = &self.slots.budget;
+		budget.try_charge(size_of::<T>(), 1)?;
+
+		match self.slot() {
+			Ok(slot) => {
+				budget.crossed().call();
+				Ok(slot)
+			}
+			Err(error) => {
+				budget.release(size_of::<T>(), 1);
+				Err(error)
+			}
+		}
+	}
+
+	/// Finds a slot for a value, allocating a new chunk if needed.
+	fn slot(&self) -> Result<(NonNull<T>, bool), AllocError> {
+		let mut state = self.slots.state.borrow_mut();
+
+		if let Some(ptr) = state.free.pop() {
+			return Ok((ptr, true));
+		}
+
+		if state.unused == 0 {
+			// Each chunk is as large as all of the previous ones together
+			let capacity = core::cmp::max(INITIAL_CAPACITY, state.capacity());
+			let layout = Layout::array::<T>(capacity).map_err(|_| AllocError::OutOfMemory {
+				requested: capacity.saturating_mul(size_of::<T>())
+			})?;
+
+			let ptr = self
+				.heap
+				.lock()
+				.expect("Heap lock failed")
+				.allocate_unrecorded(layout)?;
+
+			state.chunks.push((ptr.cast::<T>(), capacity));
+			state.unused = capacity;
+		}
+
+		let &(chunk, capacity) = state.chunks.last().expect("Pool has no chunks");
+		let ptr = unsafe { chunk.add(capacity - state.unused) };
+		state.unused -= 1;
+
+		Ok((ptr, false))
+	}
+}
+
+impl<T> PoolState<T> {
+	/// Gets the count of slots within the chunks.
+	fn capacity(&self) -> usize { self.chunks.iter().map(|&(_, capacity)| capacity).sum() }
+}
+
+impl<T: Allocatable, B: RawBackend> Debug for Pool<'_, T, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Pool")
+			.field("len", &self.len())
+			.field("capacity", &self.capacity())
+			.finish_non_exhaustive()
+	}
+}
+
+impl<T: Allocatable, B: RawBackend> Drop for Pool<'_, T, B> {
+	fn drop(&mut self) {
+		let state = self.slots.state.get_mut();
+
+		// Dropping the values that have been kept around for reuse
+		if self.slots.reset.is_some() {
+			for ptr in state.free.drain(..) {
+				unsafe { ptr.drop_in_place() }
+			}
+		}
+
+		let mut heap = match self.heap.lock() {
+			Ok(lock) => lock,
+			Err(_) => {
+				#[cfg(feature = "std")]
+				eprintln!("Heap lock failed");
+				return;
+			}
+		};
+
+		for (chunk, capacity) in state.chunks.drain(..) {
+			let layout = Layout::array::<T>(capacity).expect("Layout creation failed");
+			unsafe { heap.deallocate_unrecorded(chunk.cast::<u8>(), layout) }
+		}
+	}
+}
+
+impl<T: Allocatable> Deref for Pooled<'_, T> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target { unsafe { self.ptr.as_ref() } }
+}
+
+impl<T: Allocatable> DerefMut for Pooled<'_, T> {
+	fn deref_mut(&mut self) -> &mut Self::Target { unsafe { self.ptr.as_mut() } }
+}
+
+impl<T: Allocatable + Debug> Debug for Pooled<'_, T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Debug::fmt(&**self, f) }
+}
+
+impl<T: Allocatable> Drop for Pooled<'_, T> {
+	fn drop(&mut self) {
+		// The value is dropped first, since its destructor might use the pool as well
+		if self.slots.reset.is_none() {
+			unsafe { self.ptr.drop_in_place() }
+		}
+
+		self.slots.state.borrow_mut().free.push(self.ptr);
+		self.slots.budget.release(size_of::<T>(), 1);
+	}
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::panic::Location;
+use std::time::Instant;
+
+use crate::heap::Allocation;
+
+/// Location of the code that requested an allocation
+type Site = &'static Location<'static>;
+
+#[derive(Debug)]
+/// The lifetimes of all the allocations made within a [`Heap`](crate::Heap),
+/// aggregated per allocation site in the same way as [DHAT](https://valgrind.org/docs/manual/dh-manual.html) does.
+pub(crate) struct Profile {
+	/// Time at which the profiling started
+	start: Instant,
+
+	/// Allocations that are currently live, by their identifiers
+	blocks: HashMap<u64, Block>,
+
+	/// Statistics of the allocation sites
+	points: HashMap<Site, ProgramPoint>,
+
+	/// Count of bytes that are currently allocated
+	bytes: usize,
+
+	/// Highest count of bytes that were allocated at the same time
+	max_bytes: usize,
+
+	/// Time at which the highest count of bytes was reached, in microseconds since the start
+	max_time: u64,
+
+	/// Indicates whether the usage of the allocation sites at the time of the peak has been recorded
+	peak_recorded: bool
+}
+
+#[derive(Debug)]
+/// A live allocation.
+struct Block {
+	/// Site that the allocation was made at
+	site: Site,
+
+	/// Current size of the allocation
+	size: usize,
+
+	/// Time at which the allocation was made, in microseconds since the start
+	start: u64
+}
+
+#[derive(Debug, Default)]
+/// The statistics of a single allocation site (a "program point" in DHAT terms).
+struct ProgramPoint {
+	/// Count of bytes allocated over the lifetime of the heap
+	total_bytes: u64,
+
+	/// Count of allocations made over the lifetime of the heap
+	total_blocks: u64,
+
+	/// Sum of the lifetimes of the finished allocations, in microseconds
+	total_lifetimes: u64,
+
+	/// Count of bytes that are currently allocated
+	bytes: usize,
+
+	/// Count of allocations that are currently live
+	blocks: usize,
+
+	/// Highest count of bytes that were allocated at the same time
+	max_bytes: usize,
+
+	/// Count of live allocations at the time of the highest count of bytes
+	max_blocks: usize,
+
+	/// Count of bytes that were allocated at the time of the peak of the whole heap
+	peak_bytes: usize,
+
+	/// Count of allocations that were live at the time of the peak of the whole heap
+	peak_blocks: usize
+}
+
+impl Profile {
+	/// Starts profiling.
+	pub(crate) fn new() -> Self {
+		Self {
+			start: Instant::now(),
+			blocks: HashMap::new(),
+			points: HashMap::new(),
+			bytes: 0,
+			max_bytes: 0,
+			max_time: 0,
+			peak_recorded: false
+		}
+	}
+
+	/// Gets the time passed since the start, in microseconds.
+	fn now(&self) -> u64 { self.start.elapsed().as_micros() as u64 }
+
+	/// Records the addition of an allocation to the heap, either a new or an existing one.
+	pub(crate) fn record_insertion(&mut self, allocation: &Allocation) {
+		let size = allocation.layout.size();
+		let point = self.points.entry(allocation.site).or_default();
+
+		point.total_bytes += size as u64;
+		point.total_blocks += 1;
+		point.blocks += 1;
+
+		let start = self.now();
+		self.blocks.insert(
+			allocation.id,
+			Block {
+				site: allocation.site,
+				size,
+				start
+			}
+		);
+
+		self.add_bytes(allocation.site, size);
+	}
+
+	/// Records the resizing of an allocation.
+	pub(crate) fn record_resize(&mut self, allocation: &Allocation) {
+		let new_size = allocation.layout.size();
+
+		let Some(block) = self.blocks.get_mut(&allocation.id) else {
+			return;
+		};
+
+		let (site, size) = (block.site, block.size);
+		block.size = new_size;
+
+		if new_size > size {
+			let point = self.points.entry(site).or_default();
+			point.total_bytes += (new_size - size) as u64;
+
+			self.add_bytes(site, new_size - size);
+		} else {
+			self.remove_bytes(site, size - new_size);
+		}
+	}
+
+	/// Records the removal of an allocation from the heap, finishing its lifetime.
+	pub(crate) fn record_removal(&mut self, allocation: &Allocation) {
+		let Some(block) = self.blocks.remove(&allocation.id) else {
+			return;
+		};
+
+		let lifetime = self.now() - block.start;
+		let point = self.points.entry(block.site).or_default();
+
+		point.total_lifetimes += lifetime;
+		point.blocks -= 1;
+
+		self.remove_bytes(block.site, block.size);
+	}
+
+	/// Counts the bytes of an allocation site as allocated.
+	fn add_bytes(&mut self, site: Site, bytes: usize) {
+		let point = self.points.entry(site).or_default();
+		point.bytes += bytes;
+
+		if point.bytes > point.max_bytes {
+			point.max_bytes = point.bytes;
+			point.max_blocks = point.blocks;
+		}
+
+		self.bytes += bytes;
+
+		if self.bytes > self.max_bytes {
+			self.max_bytes = self.bytes;
+			self.max_time = self.now();
+			self.peak_recorded = false;
+		}
+	}
+
+	/// Stops counting the bytes of an allocation site as allocated.
+	fn remove_bytes(&mut self, site: Site, bytes: usize) {
+		// Recording the peak right before the usage falls from it
+		if bytes > 0 && !self.peak_recorded && self.bytes == self.max_bytes {
+			for point in self.points.values_mut() {
+				point.peak_bytes = point.bytes;
+				point.peak_blocks = point.blocks;
+			}
+
+			self.peak_recorded = true;
+		}
+
+		self.points.entry(site).or_default().bytes -= bytes;
+		self.bytes -= bytes;
+	}
+
+	/// Renders the profile in the `dhat-heap.json` format, which can be opened with the DHAT viewer.
+	pub(crate) fn to_json(&self) -> String {
+		let end = self.now();
+
+		// Lifetimes of the live allocations are counted up to the end of the profile
+		let mut live_lifetimes: HashMap<Site, u64> = HashMap::new();
+
+		for block in self.blocks.values() {
+			*live_lifetimes.entry(block.site).or_default() += end - block.start;
+		}
+
+		let mut points: Vec<_> = self.points.iter().collect();
+		points.sort_by_key(|(site, _)| (site.file(), site.line(), site.column()));
+
+		let command = std::env::args().collect::<Vec<_>>().join(" ");
+
+		let mut json = String::new();
+		json.push_str("{\"dhatFileVersion\":2,\"mode\":\"rust-heap\",\"verb\":\"Allocated\",");
+		json.push_str("\"bklt\":true,\"bkacc\":false,\"tu\":\"µs\",\"Mtu\":\"s\",\"tuth\":10,");
+
+		let _ = write!(
+			json,
+			"\"cmd\":{},\"pid\":{},\"tg\":{},\"te\":{},\"pps\":[",
+			escape(&command),
+			std::process::id(),
+			self.max_time,
+			end
+		);
+
+		for (index, (site, point)) in points.iter().enumerate() {
+			// The usage at the time of the peak has not been recorded yet only if the usage is still at the peak
+			let (peak_bytes, peak_blocks) = if self.peak_recorded {
+				(point.peak_bytes, point.peak_blocks)
+			} else {
+				(point.bytes, point.blocks)
+			};
+
+			let _ = write!(
+				json,
+				"{}{{\"tb\":{},\"tbk\":{},\"tl\":{},\"mb\":{},\"mbk\":{},\"gb\":{},\"gbk\":{},\"eb\":{},\"ebk\":{},\"fs\":[{}]}}",
+				if index == 0 { "" } else { "," },
+				point.total_bytes,
+				point.total_blocks,
+				point.total_lifetimes + live_lifetimes.get(*site).copied().unwrap_or(0),
+				point.max_bytes,
+				point.max_blocks,
+				peak_bytes,
+				peak_blocks,
+				point.bytes,
+				point.blocks,
+				index + 1
+			);
+		}
+
+		// The first frame is always the root
+		json.push_str("],\"ftbl\":[\"[root]\"");
+
+		for (site, _) in &points {
+			let _ = write!(json, ",{}", escape(&site.to_string()));
+		}
+
+		json.push_str("]}");
+		json
+	}
+}
+
+/// Renders a string as a JSON string literal.
+fn escape(string: &str) -> String {
+	let mut escaped = String::with_capacity(string.len() + 2);
+	escaped.push('"');
+
+	for c in string.chars() {
+		match c {
+			'"' => escaped.push_str("\\\""),
+			'\\' => escaped.push_str("\\\\"),
+			'\n' => escaped.push_str("\\n"),
+			c if c.is_control() => {
+				let _ = write!(escaped, "\\u{:04x}", c as u32);
+			}
+			c => escaped.push(c)
+		}
+	}
+
+	escaped.push('"');
+	escaped
+}
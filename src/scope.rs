@@ -0,0 +1,257 @@
+use core::mem::{ManuallyDrop, MaybeUninit};
+
+use crate::heap::destroy;
+use crate::memory::Allocator;
+#[cfg(feature = "std")]
+use crate::Interner;
+use crate::{AllocError, Allocatable, DefaultBackend, HeapMutator, Memory, PlainData, RawBackend};
+
+#[derive(Debug)]
+/// A region of allocations on [`Memory`] that are all deallocated at once when the scope ends.
+///
+/// Scopes are created with [`Memory::scope`]. Mutators of scoped allocations do not deallocate anything when dropped,
+/// and they cannot outlive the scope unless they are escaped with [`persist`](Scope::persist):
+///
+/// ```compile_fail
+/// # use halloc::Memory;
+/// let memory = Memory::new();
+/// let escaped = memory.scope(|scope| scope.alloc(5));
+/// ```
+///
+/// See methods on [`Scope`] for documentation.
+pub struct Scope<'memory, B: RawBackend = DefaultBackend> {
+	/// Memory that the scope allocates onto
+	memory: &'memory Memory<B>,
+
+	/// Identifier of the scope within the heap
+	id: usize
+}
+
+impl<'memory, B: RawBackend> Scope<'memory, B> {
+	/// Opens a new scope on the provided [`Memory`].
+	pub(crate) fn new(memory: &'memory Memory<B>) -> Self {
+		let mut heap = memory.get_heap();
+
+		let id = heap.next_scope;
+		heap.next_scope += 1;
+
+		Self { memory, id }
+	}
+
+	/// Gets the [`Allocator`] that assigns allocations to the current scope.
+	fn allocator(&self) -> Allocator<'_, B> {
+		Allocator {
+			heap: &self.memory.heap,
+			cache: &self.memory.cache,
+			scope: Some(self.id)
+		}
+	}
+
+	/// Gets the [`Memory`] that the scope allocates onto.
+	pub fn memory(&self) -> &'memory Memory<B> { self.memory }
+
+	/// Allocates memory for the provided value within the scope and returns a [`HeapMutator`] for that address.
+	///
+	/// See [`Memory::alloc`].
+	#[track_caller]
+	pub fn alloc<T: Allocatable>(&self, value: T) -> HeapMutator<'_, T, B> {
+		self.allocator().alloc(value)
+	}
+
+	/// Allocates memory for the provided value within the scope and returns a [`HeapMutator`] for that address,
+	/// or an [`AllocError`] if the allocation would exceed the byte limit.
+	///
+	/// See [`Memory::try_alloc`].
+	#[track_caller]
+	pub fn try_alloc<T: Allocatable>(&self, value: T) -> Result<HeapMutator<'_, T, B>, AllocError> {
+		self.allocator().try_alloc(value)
+	}
+
+	/// Allocates memory for the provided plain data value within the scope and returns a [`HeapMutator`] for that address.
+	///
+	/// See [`Memory::alloc_plain`].
+	#[track_caller]
+	pub fn alloc_plain<T: PlainData>(&self, value: T) -> HeapMutator<'_, T, B> {
+		self.allocator().alloc_plain(value)
+	}
+
+	/// Allocates memory for the value returned by `f` within the scope and returns a [`HeapMutator`] for that address.
+	///
+	/// See [`Memory::alloc_with`].
+	#[track_caller]
+	pub fn alloc_with<T: Allocatable>(&self, f: impl FnOnce() -> T) -> HeapMutator<'_, T, B> {
+		self.allocator().alloc_with(f)
+	}
+
+	/// Allocates uninitialized memory for a value of type `T` within the scope and returns a [`HeapMutator`] for that address.
+	///
+	/// See [`Memory::alloc_uninit`].
+	#[track_caller]
+	pub fn alloc_uninit<T: Allocatable>(&self) -> HeapMutator<'_, MaybeUninit<T>, B> {
+		self.allocator().alloc_uninit()
+	}
+
+	/// Allocates memory for a copy of the provided slice within the scope and returns a [`HeapMutator`] for it.
+	///
+	/// See [`Memory::alloc_slice_copy`].
+	#[track_caller]
+	pub fn alloc_slice_copy<T: Allocatable + Copy>(&self, slice: &[T]) -> HeapMutator<'_, [T], B> {
+		self.allocator().alloc_slice_copy(slice)
+	}
+
+	/// Allocates memory for a slice of length `len` within the scope and returns a [`HeapMutator`] for it.
+	///
+	/// See [`Memory::alloc_slice_fill_with`].
+	#[track_caller]
+	pub fn alloc_slice_fill_with<T: Allocatable>(
+		&self,
+		len: usize,
+		f: impl FnMut(usize) -> T
+	) -> HeapMutator<'_, [T], B> {
+		self.allocator().alloc_slice_fill_with(len, f)
+	}
+
+	/// Allocates memory for a copy of the provided string slice within the scope and returns a [`HeapMutator`] for it.
+	///
+	/// See [`Memory::alloc_str`].
+	#[track_caller]
+	pub fn alloc_str(&self, string: &str) -> HeapMutator<'_, str, B> {
+		self.allocator().alloc_str(string)
+	}
+
+	/// Allocates memory for all of the items of the provided iterator within the scope and returns a [`HeapMutator`] for the resulting slice.
+	///
+	/// See [`Memory::alloc_from_iter`].
+	#[track_caller]
+	pub fn alloc_from_iter<T: Allocatable>(
+		&self,
+		iter: impl IntoIterator<Item = T>
+	) -> HeapMutator<'_, [T], B> {
+		self.allocator().alloc_from_iter(iter)
+	}
+
+	/// Creates an [`Interner`] whose strings are allocated within the scope, and deallocated when it ends.
+	///
+	/// See [`Memory::interner`].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// let memory = Memory::new();
+	///
+	/// let unique = memory.scope(|scope| {
+	///     let mut words = scope.interner();
+	///
+	///     for word in "the cat saw the other cat".split(' ') {
+	///         words.intern(word);
+	///     }
+	///
+	///     assert_eq!(memory.count(), 4);
+	///     words.len()
+	/// });
+	///
+	/// assert_eq!(unique, 4);
+	/// assert_eq!(memory.count(), 0);
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn interner(&self) -> Interner<'_, B> { Interner::new(self.allocator()) }
+
+	/// Escapes the provided mutator from the scope, so that its memory is no longer deallocated when the scope ends.
+	///
+	/// The returned mutator is tied to the [`Memory`] instead, and deallocates the memory when dropped, like any other mutator.
+	///
+	/// # Panics
+	///
+	/// Panics if the mutator belongs to a different [`Memory`] or a different [`Scope`], or if it has existing clones.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Memory, HeapMutator};
+	/// let memory = Memory::new();
+	///
+	/// let greeting: HeapMutator<str> = memory.scope(|scope| {
+	///     let name = scope.alloc_str("world");
+	///     let greeting = scope.alloc_str(&format!("hello, {}", &*name));
+	///
+	///     scope.persist(greeting)
+	/// });
+	///
+	/// assert_eq!(&*greeting, "hello, world");
+	/// assert_eq!(memory.count(), 1);
+	/// ```
+	///
+	/// Mutators with clones cannot be persisted:
+	///
+	/// ```
+	/// # use halloc::Memory;
+	/// # use std::panic::{catch_unwind, AssertUnwindSafe};
+	/// let memory = Memory::new();
+	///
+	/// memory.scope(|scope| {
+	///     let value = scope.alloc(1u32);
+	///     let clone = value.clone();
+	///
+	///     assert!(catch_unwind(AssertUnwindSafe(|| scope.persist(value))).is_err());
+	///     assert_eq!(*clone, 1);
+	/// });
+	///
+	/// assert_eq!(memory.count(), 0);
+	/// ```
+	pub fn persist<T: Allocatable + ?Sized>(
+		&self,
+		mutator: HeapMutator<'_, T, B>
+	) -> HeapMutator<'memory, T, B> {
+		assert!(
+			core::ptr::eq(mutator.heap, &self.memory.heap),
+			"Cannot persist a mutator of a different memory"
+		);
+
+		// Clones would keep treating the memory as owned by the scope, so nobody would deallocate it
+		assert!(
+			mutator.can_dealloc(),
+			"Cannot persist a mutator that has existing clones"
+		);
+
+		let ptr = mutator.checked_ptr();
+		let mut heap = self.memory.get_heap();
+		let record = unsafe { heap.record_mut(ptr.cast::<u8>()) };
+
+		// Removing the allocation from the scope
+		match record.scope {
+			Some(id) if id == self.id => record.scope = None,
+			None => {}
+			Some(_) => panic!("Cannot persist a mutator of a different scope")
+		}
+
+		drop(heap);
+
+		// The new mutator is now responsible for deallocating the memory, taking over the reference to the slot
+		let mutator = ManuallyDrop::new(mutator);
+
+		HeapMutator {
+			ptr: mutator.ptr,
+			heap: &self.memory.heap,
+			cache: Some(&self.memory.cache),
+			deallocated: false,
+			slot: mutator.slot
+		}
+	}
+}
+
+impl<B: RawBackend> Drop for Scope<'_, B> {
+	fn drop(&mut self) {
+		// Taking the records of the scope's allocations
+		let allocations = match self.memory.try_get_heap() {
+			Some(mut heap) => heap.take_scope(self.id),
+			None => {
+				#[cfg(feature = "std")]
+				eprintln!("Heap lock failed");
+				return;
+			}
+		};
+
+		destroy(&self.memory.heap, allocations);
+	}
+}
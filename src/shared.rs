@@ -0,0 +1,594 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use core::alloc::Layout;
+use core::fmt::{self, Debug, Formatter};
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::panic::Location;
+use core::ptr::{self, NonNull};
+use core::sync::atomic::{AtomicBool, AtomicPtr, Ordering};
+
+use crate::budget::Budget;
+use crate::cache::{Bins, BATCH};
+#[cfg(feature = "std")]
+use crate::dump::HeapDump;
+use crate::heap::{header, write_record, Allocation, List, TypeTag};
+use crate::sync::{Mutex, MutexGuard};
+use crate::{
+	AllocError, Allocatable, Heap, HeapObserver, HeapStats, RawBackend, Snapshot, SystemBackend
+};
+
+#[derive(Debug)]
+/// A memory that can be shared between threads, which allocates values through per-thread caches (see [`ThreadCache`]).
+///
+/// [`Memory`](crate::Memory) locks its heap for every allocation and deallocation, and it cannot be shared between threads,
+/// since it may hold values that must not be dropped on other threads. Instead, each thread creates a [`ThreadCache`] of the shared memory,
+/// which keeps free blocks for values of a few layouts and only locks the heap once per batch of allocations and deallocations.
+///
+/// The values are recorded by the heap like the ones of a [`Memory`](crate::Memory), with their own sizes,
+/// although the caches only move their records in batches. The queries ([`size`](SharedMemory::size), [`stats`](SharedMemory::stats), and so on)
+/// move the pending records of all of the caches first, so that they are exact.
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::SharedMemory;
+/// let memory = SharedMemory::new();
+///
+/// std::thread::scope(|s| {
+///     for thread in 0..4u64 {
+///         let memory = &memory;
+///
+///         s.spawn(move || {
+///             let cache = memory.cache();
+///             let values: Vec<_> = (0..100).map(|i| cache.alloc(thread * 100 + i)).collect();
+///
+///             assert_eq!(*values[5], thread * 100 + 5);
+///         });
+///     }
+/// });
+///
+/// let stats = memory.stats();
+/// assert_eq!(stats.total_allocs, 400);
+/// assert_eq!(stats.total_deallocs, 400);
+/// assert_eq!(memory.count(), 0);
+/// ```
+pub struct SharedMemory<B: RawBackend = SystemBackend> {
+	/// Heap that records the values and that the blocks are allocated from
+	heap: Mutex<Heap<B>>,
+
+	/// Byte limit of the values, which is only charged by the caches when there is a limit
+	budget: Arc<Budget>,
+
+	/// Most recently created state of a cache, which links to the earlier ones
+	states: AtomicPtr<CacheState>
+}
+
+// SAFETY: The heap only dereferences the records of the values for dumps and snapshots, which cannot race with their owners,
+// it never drops them, and its observers and backend are `Send`
+unsafe impl<B: RawBackend> Send for SharedMemory<B> {}
+unsafe impl<B: RawBackend> Sync for SharedMemory<B> {}
+
+/// A cache of free blocks of a [`SharedMemory`], created with [`SharedMemory::cache`].
+///
+/// A cache belongs to the thread that created it. Its values can be sent to other threads (if they are [`Send`]),
+/// where dropping them hands their blocks back to the cache without locking anything.
+/// The blocks handed back after the cache is dropped are reused by the next cache of the memory.
+pub struct ThreadCache<'shared, B: RawBackend = SystemBackend> {
+	/// Memory that the cache belongs to
+	memory: &'shared SharedMemory<B>,
+
+	/// State shared with the values of the cache
+	state: &'shared CacheState
+}
+
+/// The state of a [`ThreadCache`] that its values hand their blocks back to, possibly from other threads.
+///
+/// The states are never deallocated before their memory, so that they can outlive their caches,
+/// and they are reused by the caches created later on.
+struct CacheState {
+	/// Blocks and records owned by the cache, which are only locked by other threads to record them in the heap
+	local: Mutex<Local>,
+
+	/// Records of the dropped values, linked through their `link`, which are yet to be removed from the heap
+	returned: AtomicPtr<Allocation>,
+
+	/// Indicates that a cache is using the state
+	claimed: AtomicBool,
+
+	/// State created before this one
+	next: Option<NonNull<CacheState>>
+}
+
+#[derive(Default)]
+/// The blocks and records of a [`CacheState`] that the heap does not know about.
+struct Local {
+	/// Free blocks that the values are allocated from, by layout
+	bins: Bins,
+
+	/// Records of the values allocated since they were last moved into the heap
+	pending: List
+}
+
+// SAFETY: The blocks and the records are owned by the state
+unsafe impl Send for Local {}
+
+/// A value allocated by a [`ThreadCache`], which hands its block back to the cache once dropped.
+///
+/// Values that are too large or too aligned for the caches to keep their blocks (or zero-sized) are allocated by the heap directly,
+/// locking it for their allocation and deallocation.
+pub struct Cached<'shared, T: Allocatable, B: RawBackend = SystemBackend> {
+	/// Pointer to the value
+	ptr: NonNull<T>,
+
+	/// Memory that the value belongs to
+	memory: &'shared SharedMemory<B>,
+
+	/// State of the cache that the value belongs to
+	state: &'shared CacheState,
+
+	/// Indicates that the value is owned
+	marker: PhantomData<T>
+}
+
+// SAFETY: The value is owned, and its block can be handed back from any thread
+unsafe impl<T: Allocatable + Send, B: RawBackend> Send for Cached<'_, T, B> {}
+unsafe impl<T: Allocatable + Sync, B: RawBackend> Sync for Cached<'_, T, B> {}
+
+impl SharedMemory {
+	/// Initializes [`SharedMemory`] that allocates from the global allocator.
+	pub fn new() -> Self { Self::with_backend(SystemBackend) }
+
+	/// Initializes [`SharedMemory`] with a hard limit of `limit` bytes of values.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{AllocError, SharedMemory};
+	/// let memory = SharedMemory::with_limit(32);
+	/// let cache = memory.cache();
+	///
+	/// let _first = cache.alloc(1u64);
+	/// let _second = cache.alloc(2u8);
+	///
+	/// assert_eq!(
+	///     cache.try_alloc([0u8; 24]).err(),
+	///     Some(AllocError::LimitExceeded { requested: 24, available: 23 })
+	/// );
+	/// ```
+	pub fn with_limit(limit: usize) -> Self {
+		Self::with_budget(Budget::new(Some(limit)), SystemBackend)
+	}
+}
+
+impl<B: RawBackend> SharedMemory<B> {
+	/// Initializes [`SharedMemory`] that allocates from the provided [`RawBackend`].
+	pub fn with_backend(backend: B) -> Self { Self::with_budget(Budget::new(None), backend) }
+
+	/// Initializes [`SharedMemory`] with the provided [`Budget`] and [`RawBackend`].
+	fn with_budget(budget: Budget, backend: B) -> Self {
+		let budget = Arc::new(budget);
+
+		Self {
+			heap: Mutex::new(Heap::with_budget(backend, Arc::clone(&budget))),
+			budget,
+			states: AtomicPtr::new(ptr::null_mut())
+		}
+	}
+
+	/// Creates a cache for the current thread, see [`ThreadCache`].
+	pub fn cache(&self) -> ThreadCache<'_, B> {
+		ThreadCache {
+			memory: self,
+			state: self.claim()
+		}
+	}
+
+	/// Gets the count of bytes of the values of all of the caches.
+	///
+	/// The free blocks kept by the caches are not counted.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::SharedMemory;
+	/// let memory = SharedMemory::new();
+	/// let cache = memory.cache();
+	///
+	/// let _small = cache.alloc(1u8);
+	/// let _large = cache.alloc([0u8; 20]);
+	///
+	/// assert_eq!(memory.size(), 21);
+	/// assert_eq!(memory.count(), 2);
+	/// ```
+	pub fn size(&self) -> usize { self.settled().size() }
+
+	/// Gets the count of the values of all of the caches.
+	pub fn count(&self) -> usize { self.settled().count() }
+
+	/// Gets the byte limit of the values, if there is one.
+	pub fn limit(&self) -> Option<usize> { self.budget.limit }
+
+	/// Returns a snapshot of the allocation statistics of the values of all of the caches,
+	/// see [`Memory::stats`](crate::Memory::stats).
+	pub fn stats(&self) -> HeapStats { self.settled().stats() }
+
+	/// Registers an observer that is notified of the allocation events of the heap, see [`Memory::add_observer`](crate::Memory::add_observer).
+	///
+	/// The events of the values allocated and dropped through the caches are delivered in batches,
+	/// once the caches record them in the heap.
+	pub fn add_observer(&self, observer: impl HeapObserver + 'static) {
+		self.settled().add_observer(observer)
+	}
+
+	/// Copies the contents of all the values of all of the caches, along with their layouts and types,
+	/// see [`Memory::snapshot`](crate::Memory::snapshot).
+	///
+	/// # Safety
+	///
+	/// None of the values may be mutated by other threads while the snapshot is taken.
+	pub unsafe fn snapshot(&self) -> Snapshot { Snapshot::capture(&self.settled()) }
+
+	/// Copies all of the values of all of the caches, along with their layouts, types and allocation sites,
+	/// see [`Memory::dump`](crate::Memory::dump).
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::SharedMemory;
+	/// let memory = SharedMemory::new();
+	/// let mut total = memory.cache().alloc(0u32);
+	///
+	/// std::thread::scope(|s| {
+	///     s.spawn(|| *total += 5);
+	/// });
+	///
+	/// // The thread above has been joined, so no values are mutated
+	/// let dump = unsafe { memory.dump() };
+	///
+	/// assert_eq!(dump.entries()[0].type_name(), Some("u32"));
+	/// assert_eq!(dump.entries()[0].layout().size(), 4);
+	/// ```
+	///
+	/// # Safety
+	///
+	/// None of the values may be mutated by other threads while the dump is taken.
+	#[cfg(feature = "std")]
+	pub unsafe fn dump(&self) -> HeapDump { HeapDump::capture(&self.settled()) }
+
+	/// Records the pending values of all of the caches in the heap, returning the locked heap.
+	fn settled(&self) -> MutexGuard<'_, Heap<B>> {
+		for state in self.states() {
+			let mut local = state.local.lock().expect("Cache lock failed");
+			let mut heap = self.heap.lock().expect("Heap lock failed");
+
+			state.settle(&mut heap, &mut local);
+		}
+
+		self.heap.lock().expect("Heap lock failed")
+	}
+
+	/// Gets an iterator over the states of all of the caches that have been created.
+	fn states(&self) -> impl Iterator<Item = &CacheState> {
+		let head = NonNull::new(self.states.load(Ordering::Acquire));
+
+		// The states are never deallocated before the memory, nor modified once published (besides their atomics and locks)
+		core::iter::successors(head, |state| unsafe { state.as_ref() }.next)
+			.map(|state| unsafe { state.as_ref() })
+	}
+
+	/// Claims a state that no cache is using, or creates a new one.
+	fn claim(&self) -> &CacheState {
+		let unclaimed = self.states().find(|state| {
+			state
+				.claimed
+				.compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+				.is_ok()
+		});
+
+		if let Some(state) = unclaimed {
+			return state;
+		}
+
+		let state = NonNull::from(Box::leak(Box::new(CacheState {
+			local: Mutex::new(Local::default()),
+			returned: AtomicPtr::new(ptr::null_mut()),
+			claimed: AtomicBool::new(true),
+			next: None
+		})));
+
+		// Publishing the state in front of the others
+		let mut head = self.states.load(Ordering::Relaxed);
+
+		loop {
+			unsafe { (*state.as_ptr()).next = NonNull::new(head) }
+
+			match self.states.compare_exchange_weak(
+				head,
+				state.as_ptr(),
+				Ordering::Release,
+				Ordering::Relaxed
+			) {
+				Ok(_) => return unsafe { state.as_ref() },
+				Err(current) => head = current
+			}
+		}
+	}
+
+	/// Counts a value of `bytes` bytes towards the byte limit, failing if that would exceed it.
+	fn charge(&self, bytes: usize) -> Result<(), AllocError> {
+		// Only sharing the counters between the threads when the limit has to be checked,
+		// the heap counts the values once they are recorded
+		match self.budget.limit {
+			Some(_) => self.budget.try_charge(bytes, 1),
+			None => Ok(())
+		}
+	}
+
+	/// Stops counting a value of `bytes` bytes towards the byte limit.
+	fn release(&self, bytes: usize) {
+		if self.budget.limit.is_some() {
+			self.budget.release(bytes, 1);
+		}
+	}
+}
+
+impl<'shared, B: RawBackend> ThreadCache<'shared, B> {
+	/// Moves the provided value into a free block of the cache, taking a batch of blocks from the heap if there are none.
+	///
+	/// # Panics
+	///
+	/// Panics if the value would exceed the byte limit of the memory, or if the backend is exhausted (see [`try_alloc`](ThreadCache::try_alloc)).
+	#[track_caller]
+	pub fn alloc<T: Allocatable>(&self, value: T) -> Cached<'shared, T, B> {
+		self.try_alloc(value)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Moves the provided value into a free block of the cache like [`alloc`](ThreadCache::alloc),
+	/// failing if the value would exceed the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn try_alloc<T: Allocatable>(&self, value: T) -> Result<Cached<'shared, T, B>, AllocError> {
+		let layout = Layout::new::<T>();
+
+		let ptr = match is_cached(layout) {
+			true => self.acquire(layout, TypeTag::of::<T>(), Location::caller())?,
+			false => self
+				.memory
+				.heap
+				.lock()
+				.expect("Heap lock failed")
+				.try_alloc_tagged(layout, Some(TypeTag::of::<T>()))?
+		}
+		.cast::<T>();
+
+		unsafe { ptr.write(value) }
+
+		Ok(Cached {
+			ptr,
+			memory: self.memory,
+			state: self.state,
+			marker: PhantomData
+		})
+	}
+
+	/// Records the pending values of the cache in the heap, and returns all of its free blocks
+	/// (including the ones handed back by the values dropped on other threads) to the backend.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::SharedMemory;
+	/// let memory = SharedMemory::new();
+	/// let cache = memory.cache();
+	///
+	/// let values: Vec<_> = (0..100u32).map(|i| cache.alloc(i)).collect();
+	///
+	/// // Dropping the values on another thread
+	/// std::thread::scope(|s| {
+	///     s.spawn(move || drop(values));
+	/// });
+	///
+	/// cache.flush();
+	///
+	/// assert_eq!(memory.size(), 0);
+	/// assert_eq!(memory.stats().total_deallocs, 100);
+	/// ```
+	pub fn flush(&self) {
+		let mut local = self.state.local.lock().expect("Cache lock failed");
+		let mut heap = match self.memory.heap.lock() {
+			Ok(lock) => lock,
+			Err(_) => {
+				#[cfg(feature = "std")]
+				eprintln!("Heap lock failed");
+				return;
+			}
+		};
+
+		self.state.settle(&mut heap, &mut local);
+		local.bins.trim(&mut heap);
+	}
+
+	/// Takes a free block for a value of the provided layout and type,
+	/// recording the pending values in the heap once there is a batch of them.
+	fn acquire(
+		&self,
+		layout: Layout,
+		type_tag: TypeTag,
+		site: &'static Location<'static>
+	) -> Result<NonNull<u8>, AllocError> {
+		self.memory.charge(layout.size())?;
+
+		let mut local = self.state.local.lock().expect("Cache lock failed");
+
+		if !local.bins.has(layout) || local.pending.len() >= BATCH {
+			// Taking back the blocks of the dropped values, or a batch of blocks from the heap at once
+			let mut heap = self.memory.heap.lock().expect("Heap lock failed");
+			self.state.settle(&mut heap, &mut local);
+
+			if !local.bins.has(layout) && !local.bins.refill(&mut heap, layout) {
+				self.memory.release(layout.size());
+				return Err(AllocError::OutOfMemory {
+					requested: layout.size()
+				});
+			}
+		}
+
+		let ptr = local.bins.take(layout).expect("Cache has no free block");
+
+		// The record is written right away, so that the value can be handed back before it is recorded in the heap
+		let allocation = unsafe { write_record(ptr, layout, Some(type_tag), site) };
+		local.pending.push(allocation);
+
+		Ok(ptr)
+	}
+}
+
+impl CacheState {
+	/// Records the pending values in the provided heap, and removes the records of the values handed back,
+	/// whose blocks become free blocks of the cache.
+	fn settle<B: RawBackend>(&self, heap: &mut Heap<B>, local: &mut Local) {
+		let counted = heap.budget.limit.is_some();
+
+		// Recording the pending values first, since some of them may have been handed back already
+		while let Some(allocation) = local.pending.pop() {
+			// The values are counted once they are allocated when there is a limit
+			if !counted {
+				heap.budget
+					.charge(unsafe { allocation.as_ref() }.layout.size(), 1);
+			}
+
+			unsafe { heap.track(allocation) };
+		}
+
+		// Only ever taking the whole list at once, so that the records pushed concurrently cannot be mixed up
+		let mut returned = NonNull::new(self.returned.swap(ptr::null_mut(), Ordering::Acquire));
+
+		while let Some(allocation) = returned {
+			let (ptr, layout) = {
+				let record = unsafe { allocation.as_ref() };
+				returned = record.link();
+
+				(record.ptr, record.layout)
+			};
+
+			if !counted {
+				heap.budget.release(layout.size(), 1);
+			}
+
+			unsafe { heap.forget(ptr) };
+			unsafe { local.bins.put(heap, ptr, layout) }
+		}
+	}
+
+	/// Hands the record of a dropped value back, without locking.
+	fn give(&self, allocation: NonNull<Allocation>) {
+		let mut head = self.returned.load(Ordering::Relaxed);
+
+		loop {
+			// Only referencing the link of the record, since the cache may be moving the record between its lists meanwhile
+			unsafe { Allocation::link_of(allocation) }.store(head, Ordering::Relaxed);
+
+			match self.returned.compare_exchange_weak(
+				head,
+				allocation.as_ptr(),
+				Ordering::Release,
+				Ordering::Relaxed
+			) {
+				Ok(_) => return,
+				Err(current) => head = current
+			}
+		}
+	}
+}
+
+/// Shows whether the values of the provided layout are allocated through the caches, rather than by the heap directly.
+fn is_cached(layout: Layout) -> bool { layout.size() != 0 && Bins::fits(layout) }
+
+impl Default for SharedMemory {
+	fn default() -> Self { Self::new() }
+}
+
+impl<B: RawBackend> Drop for SharedMemory<B> {
+	fn drop(&mut self) {
+		let mut state = NonNull::new(*self.states.get_mut());
+
+		let Ok(mut heap) = self.heap.lock() else {
+			return;
+		};
+
+		while let Some(ptr) = state {
+			// No caches or values are left, so the states can be deallocated along with their blocks
+			let current = unsafe { Box::from_raw(ptr.as_ptr()) };
+			state = current.next;
+
+			let Ok(mut local) = current.local.lock() else {
+				continue;
+			};
+
+			current.settle(&mut heap, &mut local);
+			local.bins.trim(&mut heap);
+		}
+	}
+}
+
+impl Debug for CacheState {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("CacheState")
+			.field("claimed", &self.claimed)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<B: RawBackend> Debug for ThreadCache<'_, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("ThreadCache")
+			.field("state", self.state)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<B: RawBackend> Drop for ThreadCache<'_, B> {
+	fn drop(&mut self) {
+		self.flush();
+
+		// Letting the next cache reuse the state, along with the blocks handed back from now on
+		self.state.claimed.store(false, Ordering::Release);
+	}
+}
+
+impl<T: Allocatable, B: RawBackend> Deref for Cached<'_, T, B> {
+	type Target = T;
+
+	fn deref(&self) -> &Self::Target { unsafe { self.ptr.as_ref() } }
+}
+
+impl<T: Allocatable, B: RawBackend> DerefMut for Cached<'_, T, B> {
+	fn deref_mut(&mut self) -> &mut Self::Target { unsafe { self.ptr.as_mut() } }
+}
+
+impl<T: Allocatable + Debug, B: RawBackend> Debug for Cached<'_, T, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Debug::fmt(&**self, f) }
+}
+
+impl<T: Allocatable, B: RawBackend> Drop for Cached<'_, T, B> {
+	fn drop(&mut self) {
+		unsafe { self.ptr.drop_in_place() }
+
+		let ptr = self.ptr.cast::<u8>();
+		let layout = Layout::new::<T>();
+
+		match is_cached(layout) {
+			// The record is removed once the cache takes the block back
+			true => {
+				self.state.give(header(ptr));
+				self.memory.release(layout.size());
+			}
+			false => {
+				if let Ok(mut heap) = self.memory.heap.lock() {
+					heap.dealloc(ptr, layout);
+				}
+			}
+		}
+	}
+}
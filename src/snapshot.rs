@@ -0,0 +1,139 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::any::TypeId;
+
+use crate::heap::Heap;
+use crate::{RawBackend, RestoreError};
+
+#[derive(Debug, Clone)]
+/// A copy of the contents of all the allocations of a [`Memory`](crate::Memory), taken with [`Memory::snapshot`](crate::Memory::snapshot).
+///
+/// Unlike [`Memory::bytes`](crate::Memory::bytes), the snapshot keeps the boundaries and the types of the allocations,
+/// so that it can be written back with [`Memory::restore`](crate::Memory::restore).
+pub struct Snapshot {
+	/// Copies of the captured allocations
+	entries: Vec<SnapshotEntry>
+}
+
+#[derive(Debug, Clone)]
+/// A copy of the contents of a single allocation within a [`Snapshot`].
+pub struct SnapshotEntry {
+	/// Unique identifier of the allocation
+	id: u64,
+
+	/// Address of the allocation at the time of the snapshot
+	address: usize,
+
+	/// Layout of the allocation
+	layout: Layout,
+
+	/// Identifier of the value's type, if it is known
+	type_id: Option<TypeId>,
+
+	/// Name of the value's type, if it is known
+	type_name: Option<&'static str>,
+
+	/// Indicates whether the value is plain data
+	plain: bool,
+
+	/// Copy of the allocated bytes
+	bytes: Vec<u8>
+}
+
+impl Snapshot {
+	/// Copies the contents of all the live allocations of the provided heap.
+	pub(crate) fn capture<B: RawBackend>(heap: &Heap<B>) -> Self {
+		let entries = heap
+			.records()
+			.map(|allocation| SnapshotEntry {
+				id: allocation.id,
+				address: allocation.ptr.as_ptr() as usize,
+				layout: allocation.layout,
+				type_id: allocation.type_tag.map(|tag| tag.id),
+				type_name: allocation.type_tag.map(|tag| tag.name),
+				plain: allocation.type_tag.is_some_and(|tag| tag.plain),
+				bytes: allocation.contents()
+			})
+			.collect();
+
+		Self { entries }
+	}
+
+	/// Writes the captured contents back into the allocations of the provided heap.
+	///
+	/// Nothing is written if any of the entries cannot be restored. Values that are pinned in place
+	/// are only overwritten if `ignore_pins` is set, while values that are being accessed never are.
+	pub(crate) fn restore<B: RawBackend>(
+		&self,
+		heap: &mut Heap<B>,
+		ignore_pins: bool
+	) -> Result<(), RestoreError> {
+		let allocations: BTreeMap<_, _> = heap.records().map(|a| (a.id, a)).collect();
+		let mut targets = Vec::with_capacity(self.entries.len());
+
+		// Validating all of the entries first
+		for entry in &self.entries {
+			if !entry.plain {
+				return Err(RestoreError::NotPlainData {
+					type_name: entry.type_name
+				});
+			}
+
+			let target = allocations
+				.get(&entry.id)
+				.filter(|a| {
+					a.layout == entry.layout && a.type_tag.map(|tag| tag.id) == entry.type_id
+				})
+				.ok_or(RestoreError::Missing {
+					address: entry.address
+				})?;
+
+			// References to the value may still be in use, so it cannot be overwritten
+			if target.slot().is_some_and(|slot| slot.is_fixed(ignore_pins)) {
+				return Err(RestoreError::Borrowed {
+					address: entry.address
+				});
+			}
+
+			targets.push(target.ptr);
+		}
+
+		for (entry, ptr) in self.entries.iter().zip(targets) {
+			unsafe {
+				core::ptr::copy_nonoverlapping(
+					entry.bytes.as_ptr(),
+					ptr.as_ptr(),
+					entry.bytes.len()
+				)
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Gets the captured allocations.
+	pub fn entries(&self) -> &[SnapshotEntry] { &self.entries }
+
+	/// Gets the count of bytes captured within the snapshot.
+	pub fn size(&self) -> usize { self.entries.iter().map(|e| e.bytes.len()).sum() }
+}
+
+impl SnapshotEntry {
+	/// Gets the address of the allocation at the time of the snapshot.
+	pub fn address(&self) -> usize { self.address }
+
+	/// Gets the layout of the allocation.
+	pub fn layout(&self) -> Layout { self.layout }
+
+	/// Gets the name of the value's type, if it is known.
+	pub fn type_name(&self) -> Option<&'static str> { self.type_name }
+
+	/// Shows whether the value is plain data, and can therefore be restored.
+	pub fn is_plain(&self) -> bool { self.plain }
+
+	/// Gets the captured bytes of the allocation.
+	///
+	/// The bytes of values that are not plain data are all zeros, since they may be uninitialized.
+	pub fn bytes(&self) -> &[u8] { &self.bytes }
+}
@@ -0,0 +1,117 @@
+use core::alloc::Layout;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// A snapshot of the allocation statistics of a [`Heap`](crate::Heap).
+///
+/// Statistics are gathered with [`Memory::stats`](crate::Memory::stats) or [`Heap::stats`](crate::Heap::stats).
+pub struct HeapStats {
+	/// Count of bytes that are currently allocated
+	pub current_bytes: usize,
+
+	/// Count of allocations that are currently live
+	pub current_count: usize,
+
+	/// Highest count of bytes that were allocated at the same time
+	pub peak_bytes: usize,
+
+	/// Highest count of allocations that were live at the same time
+	pub peak_count: usize,
+
+	/// Count of allocations made over the lifetime of the heap
+	pub total_allocs: u64,
+
+	/// Count of deallocations made over the lifetime of the heap
+	pub total_deallocs: u64,
+
+	/// Count of bytes allocated over the lifetime of the heap (including the growth of reallocations)
+	pub total_bytes: u64,
+
+	/// Count of allocations made over the lifetime of the heap per size class, see [`size_class`](HeapStats::size_class)
+	pub size_histogram: [u64; HeapStats::SIZE_CLASSES],
+
+	/// Count of bytes that the backend reserves beyond the size of each live allocation (e.g., to round its block up),
+	/// which is always `0` for backends that do not know how much they reserve, see [`RawBackend::reserved_size`](crate::RawBackend::reserved_size)
+	pub alignment_waste: usize
+}
+
+impl HeapStats {
+	/// The count of size classes in [`size_histogram`](HeapStats::size_histogram).
+	pub const SIZE_CLASSES: usize = 16;
+
+	/// Gets the size class of an allocation of `size` bytes.
+	///
+	/// Class `0` holds allocations of up to 1 byte, and every class `n` after that holds allocations of up to `2^n` bytes.
+	/// The last class also holds all the allocations larger than that.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::HeapStats;
+	/// assert_eq!(HeapStats::size_class(0), 0);
+	/// assert_eq!(HeapStats::size_class(4), 2);
+	/// assert_eq!(HeapStats::size_class(5), 3);
+	/// assert_eq!(HeapStats::size_class(usize::MAX), HeapStats::SIZE_CLASSES - 1);
+	/// ```
+	pub fn size_class(size: usize) -> usize {
+		if size <= 1 {
+			return 0;
+		}
+
+		// Rounding the size up to the next power of two
+		let class = (usize::BITS - (size - 1).leading_zeros()) as usize;
+		core::cmp::min(class, Self::SIZE_CLASSES - 1)
+	}
+
+	/// Records a new allocation, for which the backend reserved `waste` bytes beyond its size.
+	pub(crate) fn record_alloc(&mut self, layout: Layout, waste: usize) {
+		self.total_allocs += 1;
+		self.total_bytes += layout.size() as u64;
+		self.size_histogram[Self::size_class(layout.size())] += 1;
+
+		self.record_insertion(layout, waste);
+	}
+
+	/// Records the addition of an existing allocation to the heap.
+	pub(crate) fn record_insertion(&mut self, layout: Layout, waste: usize) {
+		self.current_count += 1;
+		self.peak_count = core::cmp::max(self.peak_count, self.current_count);
+
+		self.add_bytes(layout, waste);
+	}
+
+	/// Records the resizing of an allocation.
+	pub(crate) fn record_realloc(
+		&mut self,
+		layout: Layout,
+		waste: usize,
+		new_layout: Layout,
+		new_waste: usize
+	) {
+		self.total_bytes += new_layout.size().saturating_sub(layout.size()) as u64;
+
+		self.remove_bytes(layout, waste);
+		self.add_bytes(new_layout, new_waste);
+	}
+
+	/// Records the removal of an allocation from the heap.
+	pub(crate) fn record_removal(&mut self, layout: Layout, waste: usize) {
+		self.current_count -= 1;
+		self.remove_bytes(layout, waste);
+	}
+
+	/// Records the deallocation of memory.
+	pub(crate) fn record_dealloc(&mut self) { self.total_deallocs += 1; }
+
+	/// Adds the bytes of an allocation to the current usage.
+	fn add_bytes(&mut self, layout: Layout, waste: usize) {
+		self.current_bytes += layout.size();
+		self.peak_bytes = core::cmp::max(self.peak_bytes, self.current_bytes);
+		self.alignment_waste += waste;
+	}
+
+	/// Removes the bytes of an allocation from the current usage.
+	fn remove_bytes(&mut self, layout: Layout, waste: usize) {
+		self.current_bytes -= layout.size();
+		self.alignment_waste -= waste;
+	}
+}
@@ -0,0 +1,281 @@
+use core::borrow::{Borrow, BorrowMut};
+use core::cmp::Ordering;
+use core::fmt::{self, Debug, Display, Formatter, Write};
+use core::hash::{Hash, Hasher};
+use core::ops::{Deref, DerefMut};
+
+use crate::{AllocError, DefaultBackend, HVec, Memory, RawBackend};
+
+/// A growable UTF-8 string like [`String`], whose buffer is allocated from a [`Memory`].
+///
+/// The buffer is accounted for like the one of an [`HVec`].
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::{HString, Memory};
+/// use std::fmt::Write;
+///
+/// let memory = Memory::new();
+/// let mut log = HString::from_str_in("frame", &memory);
+///
+/// write!(log, " {} took {}ms", 7, 16).unwrap();
+/// log.push('!');
+///
+/// assert_eq!(log, "frame 7 took 16ms!");
+/// assert_eq!(memory.size(), log.capacity());
+/// ```
+pub struct HString<'memory, B: RawBackend = DefaultBackend> {
+	/// UTF-8 bytes of the string
+	bytes: HVec<'memory, u8, B>
+}
+
+impl<'memory, B: RawBackend> HString<'memory, B> {
+	/// Creates an empty string on the provided [`Memory`], which does not allocate until text is pushed to it.
+	pub fn new_in(memory: &'memory Memory<B>) -> Self {
+		Self {
+			bytes: HVec::new_in(memory)
+		}
+	}
+
+	/// Creates an empty string on the provided [`Memory`], with space for at least `capacity` bytes.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffer would exceed the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn with_capacity_in(capacity: usize, memory: &'memory Memory<B>) -> Self {
+		Self {
+			bytes: HVec::with_capacity_in(capacity, memory)
+		}
+	}
+
+	/// Creates a string on the provided [`Memory`] with a copy of the provided text.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffer would exceed the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn from_str_in(string: &str, memory: &'memory Memory<B>) -> Self {
+		let mut bytes = HVec::with_capacity_in(string.len(), memory);
+		bytes.extend_from_slice(string.as_bytes());
+
+		Self { bytes }
+	}
+
+	/// Gets the [`Memory`] that the buffer is allocated from.
+	pub fn memory(&self) -> &'memory Memory<B> { self.bytes.memory() }
+
+	/// Gets the length of the string in bytes.
+	pub fn len(&self) -> usize { self.bytes.len() }
+
+	/// Shows whether the string is empty.
+	pub fn is_empty(&self) -> bool { self.bytes.is_empty() }
+
+	/// Gets the count of bytes that the string can hold without reallocating.
+	pub fn capacity(&self) -> usize { self.bytes.capacity() }
+
+	/// Reserves space for at least `additional` more bytes (see [`HVec::reserve`]).
+	#[track_caller]
+	pub fn reserve(&mut self, additional: usize) { self.bytes.reserve(additional) }
+
+	/// Reserves space for at least `additional` more bytes,
+	/// failing if the buffer would exceed the byte limit of the memory, or if the backend is exhausted.
+	pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+		self.bytes.try_reserve(additional)
+	}
+
+	/// Shrinks the buffer to fit the text, deallocating it if the string is empty.
+	pub fn shrink_to_fit(&mut self) { self.bytes.shrink_to_fit() }
+
+	/// Appends the provided character to the end.
+	#[track_caller]
+	pub fn push(&mut self, character: char) { self.push_str(character.encode_utf8(&mut [0; 4])) }
+
+	/// Appends the provided text to the end.
+	#[track_caller]
+	pub fn push_str(&mut self, string: &str) { self.bytes.extend_from_slice(string.as_bytes()) }
+
+	/// Removes and returns the last character, if there is one.
+	pub fn pop(&mut self) -> Option<char> {
+		let character = self.chars().next_back()?;
+		self.bytes.truncate(self.len() - character.len_utf8());
+
+		Some(character)
+	}
+
+	/// Inserts the provided character at the provided byte index.
+	///
+	/// # Panics
+	///
+	/// Panics if the index does not lie on a character boundary, or if the buffer cannot grow.
+	#[track_caller]
+	pub fn insert(&mut self, index: usize, character: char) {
+		self.insert_str(index, character.encode_utf8(&mut [0; 4]))
+	}
+
+	/// Inserts the provided text at the provided byte index.
+	///
+	/// # Panics
+	///
+	/// Panics if the index does not lie on a character boundary, or if the buffer cannot grow.
+	#[track_caller]
+	pub fn insert_str(&mut self, index: usize, string: &str) {
+		assert!(
+			self.is_char_boundary(index),
+			"Index {index} is not a character boundary"
+		);
+
+		// Appending the text, and rotating it into its place
+		self.bytes.extend_from_slice(string.as_bytes());
+		self.bytes[index..].rotate_right(string.len());
+	}
+
+	/// Removes and returns the character at the provided byte index.
+	///
+	/// # Panics
+	///
+	/// Panics if the index does not lie on a character boundary, or if it is out of bounds.
+	#[track_caller]
+	pub fn remove(&mut self, index: usize) -> char {
+		let character = self[index..]
+			.chars()
+			.next()
+			.expect("Index is out of bounds");
+
+		// Rotating the character to the end, and cutting it off
+		self.bytes[index..].rotate_left(character.len_utf8());
+		self.bytes.truncate(self.len() - character.len_utf8());
+
+		character
+	}
+
+	/// Shortens the string to `len` bytes.
+	///
+	/// # Panics
+	///
+	/// Panics if `len` does not lie on a character boundary.
+	#[track_caller]
+	pub fn truncate(&mut self, len: usize) {
+		if len < self.len() {
+			assert!(
+				self.is_char_boundary(len),
+				"Length {len} is not a character boundary"
+			);
+			self.bytes.truncate(len);
+		}
+	}
+
+	/// Removes all of the text, keeping the buffer.
+	pub fn clear(&mut self) { self.bytes.clear() }
+
+	/// Gets the text of the string.
+	pub fn as_str(&self) -> &str { unsafe { core::str::from_utf8_unchecked(&self.bytes) } }
+
+	/// Gets the mutable text of the string.
+	pub fn as_mut_str(&mut self) -> &mut str {
+		unsafe { core::str::from_utf8_unchecked_mut(&mut self.bytes) }
+	}
+}
+
+impl<B: RawBackend> Deref for HString<'_, B> {
+	type Target = str;
+
+	fn deref(&self) -> &Self::Target { self.as_str() }
+}
+
+impl<B: RawBackend> DerefMut for HString<'_, B> {
+	fn deref_mut(&mut self) -> &mut Self::Target { self.as_mut_str() }
+}
+
+impl<B: RawBackend> AsRef<str> for HString<'_, B> {
+	fn as_ref(&self) -> &str { self }
+}
+
+impl<B: RawBackend> AsRef<[u8]> for HString<'_, B> {
+	fn as_ref(&self) -> &[u8] { self.as_bytes() }
+}
+
+impl<B: RawBackend> Borrow<str> for HString<'_, B> {
+	fn borrow(&self) -> &str { self }
+}
+
+impl<B: RawBackend> BorrowMut<str> for HString<'_, B> {
+	fn borrow_mut(&mut self) -> &mut str { self }
+}
+
+impl<B: RawBackend> Write for HString<'_, B> {
+	fn write_str(&mut self, string: &str) -> fmt::Result {
+		self.bytes
+			.try_reserve(string.len())
+			.map_err(|_| fmt::Error)?;
+		self.push_str(string);
+
+		Ok(())
+	}
+}
+
+impl<'string, B: RawBackend> Extend<&'string str> for HString<'_, B> {
+	#[track_caller]
+	fn extend<I: IntoIterator<Item = &'string str>>(&mut self, iter: I) {
+		for string in iter {
+			self.push_str(string);
+		}
+	}
+}
+
+impl<B: RawBackend> Extend<char> for HString<'_, B> {
+	#[track_caller]
+	fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+		let iter = iter.into_iter();
+		self.reserve(iter.size_hint().0);
+
+		for character in iter {
+			self.push(character);
+		}
+	}
+}
+
+impl<B: RawBackend> Clone for HString<'_, B> {
+	/// Clones the string into a new buffer on the same [`Memory`].
+	fn clone(&self) -> Self {
+		Self {
+			bytes: self.bytes.clone()
+		}
+	}
+}
+
+impl<B: RawBackend> Debug for HString<'_, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Debug::fmt(self.as_str(), f) }
+}
+
+impl<B: RawBackend> Display for HString<'_, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Display::fmt(self.as_str(), f) }
+}
+
+impl<B: RawBackend, C: RawBackend> PartialEq<HString<'_, C>> for HString<'_, B> {
+	fn eq(&self, other: &HString<'_, C>) -> bool { self.as_str() == other.as_str() }
+}
+
+impl<B: RawBackend> PartialEq<str> for HString<'_, B> {
+	fn eq(&self, other: &str) -> bool { self.as_str() == other }
+}
+
+impl<B: RawBackend> PartialEq<&str> for HString<'_, B> {
+	fn eq(&self, other: &&str) -> bool { self.as_str() == *other }
+}
+
+impl<B: RawBackend> Eq for HString<'_, B> {}
+
+impl<B: RawBackend> PartialOrd for HString<'_, B> {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl<B: RawBackend> Ord for HString<'_, B> {
+	fn cmp(&self, other: &Self) -> Ordering { self.as_str().cmp(other.as_str()) }
+}
+
+impl<B: RawBackend> Hash for HString<'_, B> {
+	// Hashing like `str`, so that maps with string keys can be searched with `&str`
+	fn hash<H: Hasher>(&self, state: &mut H) { self.as_str().hash(state) }
+}
@@ -0,0 +1,106 @@
+//! The lock that guards the [`Heap`](crate::Heap) of a [`Memory`](crate::Memory).
+//!
+//! With the `std` feature, this is [`std::sync::Mutex`]. Without it, a spin lock with the same interface is used instead,
+//! which never reports poisoning.
+
+#[cfg(feature = "std")]
+pub use std::sync::{Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+pub use spin::{Mutex, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+mod spin {
+	use core::cell::UnsafeCell;
+	use core::fmt::{self, Debug, Formatter};
+	use core::ops::{Deref, DerefMut};
+	use core::sync::atomic::{AtomicBool, Ordering};
+
+	/// A mutual exclusion lock that busy-waits until it is released.
+	///
+	/// **Note:** the lock must not be acquired from an interrupt handler that may preempt its holder,
+	/// since the handler would spin forever.
+	pub struct Mutex<T: ?Sized> {
+		/// Indicates whether the lock is held
+		locked: AtomicBool,
+
+		/// Guarded value
+		data: UnsafeCell<T>
+	}
+
+	/// A held [`Mutex`], which releases it when dropped.
+	pub struct MutexGuard<'lock, T: ?Sized> {
+		/// Lock that is held
+		lock: &'lock Mutex<T>
+	}
+
+	/// The error of a poisoned [`Mutex`], which the spin lock never reports.
+	pub struct PoisonError<T> {
+		/// Guard of the lock, never actually held
+		_guard: core::marker::PhantomData<T>
+	}
+
+	unsafe impl<T: ?Sized + Send> Send for Mutex<T> {}
+	unsafe impl<T: ?Sized + Send> Sync for Mutex<T> {}
+	unsafe impl<T: ?Sized + Sync> Sync for MutexGuard<'_, T> {}
+
+	impl<T> Mutex<T> {
+		/// Creates an unlocked lock around the provided value.
+		pub const fn new(value: T) -> Self {
+			Self {
+				locked: AtomicBool::new(false),
+				data: UnsafeCell::new(value)
+			}
+		}
+	}
+
+	impl<T: ?Sized> Mutex<T> {
+		/// Acquires the lock, spinning until it is available.
+		///
+		/// Never fails, the [`Result`] is only returned for compatibility with [`std::sync::Mutex`].
+		pub fn lock(&self) -> Result<MutexGuard<'_, T>, PoisonError<MutexGuard<'_, T>>> {
+			while self
+				.locked
+				.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+				.is_err()
+			{
+				// Waiting without writing, so that the cache line is not contended
+				while self.locked.load(Ordering::Relaxed) {
+					core::hint::spin_loop();
+				}
+			}
+
+			Ok(MutexGuard { lock: self })
+		}
+	}
+
+	impl<T: ?Sized + Debug> Debug for Mutex<T> {
+		fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+			f.debug_struct("Mutex").finish_non_exhaustive()
+		}
+	}
+
+	impl<T: ?Sized> Deref for MutexGuard<'_, T> {
+		type Target = T;
+
+		fn deref(&self) -> &Self::Target { unsafe { &*self.lock.data.get() } }
+	}
+
+	impl<T: ?Sized> DerefMut for MutexGuard<'_, T> {
+		fn deref_mut(&mut self) -> &mut Self::Target { unsafe { &mut *self.lock.data.get() } }
+	}
+
+	impl<T> Debug for PoisonError<T> {
+		fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+			f.debug_struct("PoisonError").finish_non_exhaustive()
+		}
+	}
+
+	impl<T: ?Sized + Debug> Debug for MutexGuard<'_, T> {
+		fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Debug::fmt(&**self, f) }
+	}
+
+	impl<T: ?Sized> Drop for MutexGuard<'_, T> {
+		fn drop(&mut self) { self.lock.locked.store(false, Ordering::Release) }
+	}
+}
@@ -0,0 +1,451 @@
+use std::alloc::Layout;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::ptr::NonNull;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::{AllocationInfo, DumpError, Heap, HeapObserver, RawBackend};
+
+/// Bytes that every allocation trace starts with
+const MAGIC: &[u8; 8] = b"HALLOCTR";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// The sequence of allocations, deallocations and reallocations made on a heap, recorded with a [`TraceRecorder`].
+///
+/// Traces can be written to and read from a compact binary format, and replayed against a [`Heap`]
+/// to compare the allocation strategies on real workloads.
+pub struct Trace {
+	/// Recorded events, in order
+	events: Vec<TraceEvent>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// A single event of a [`Trace`].
+///
+/// Allocations are referred to by their index, which is the count of [`Alloc`](TraceEvent::Alloc) events before them.
+pub enum TraceEvent {
+	/// Memory was allocated
+	Alloc {
+		/// Layout of the allocated memory
+		layout: Layout
+	},
+
+	/// Memory was deallocated
+	Dealloc {
+		/// Index of the allocation
+		index: usize
+	},
+
+	/// Memory was resized
+	Realloc {
+		/// Index of the allocation
+		index: usize,
+
+		/// New size of the allocation
+		new_size: usize
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// The results of replaying a [`Trace`] with [`Trace::replay`].
+pub struct ReplayReport {
+	/// Time spent within the allocation, deallocation and reallocation calls
+	pub elapsed: Duration,
+
+	/// Count of replayed events
+	pub events: usize,
+
+	/// Highest count of bytes that were allocated at the same time
+	pub peak_bytes: usize,
+
+	/// Highest count of allocations that were live at the same time
+	pub peak_count: usize,
+
+	/// Fraction of the address range spanned by the live allocations that was not used by them,
+	/// at the time of the highest count of allocated bytes (`0.0` means that the allocations were packed tightly)
+	pub fragmentation: f64,
+
+	/// Count of allocations and reallocations that failed, since the backend was exhausted or the byte limit was exceeded
+	pub failures: usize
+}
+
+#[derive(Debug, Default)]
+/// A [`HeapObserver`] that records a [`Trace`] of the heap that it is registered on.
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::{Heap, Memory, Trace, TraceRecorder};
+/// # use std::sync::Arc;
+/// let recorder = Arc::new(TraceRecorder::new());
+///
+/// let memory = Memory::new();
+/// memory.add_observer(Arc::clone(&recorder));
+///
+/// // Running the workload
+/// let mut kept = vec![];
+/// for i in 0..100u64 {
+///     let _temporary = memory.alloc_str("temporary");
+///     kept.push(memory.alloc([i; 4]));
+/// }
+///
+/// let mut file = vec![];
+/// recorder.trace().write_to(&mut file).unwrap();
+///
+/// // Replaying it later
+/// let trace = Trace::read_from(file.as_slice()).unwrap();
+/// let report = trace.replay(&mut Heap::new(0));
+///
+/// assert_eq!(report.events, 300);
+/// assert_eq!(report.peak_bytes, 100 * 32 + 9);
+/// ```
+pub struct TraceRecorder {
+	/// Events recorded so far, along with the indices of the live allocations
+	state: Mutex<Recording>
+}
+
+#[derive(Debug, Default)]
+/// The state of a [`TraceRecorder`].
+struct Recording {
+	/// Recorded events, in order
+	events: Vec<TraceEvent>,
+
+	/// Indices of the live allocations, by their identifiers
+	indices: HashMap<u64, usize>,
+
+	/// Count of recorded allocations
+	count: usize
+}
+
+impl Trace {
+	/// Version of the format written by [`write_to`](Trace::write_to).
+	pub const VERSION: u32 = 1;
+
+	/// Gets the recorded events.
+	pub fn events(&self) -> &[TraceEvent] { &self.events }
+
+	/// Writes the trace to the provided writer.
+	///
+	/// Each event takes a few bytes: sizes are stored as variable-length integers, and allocations are referred to
+	/// by their distance from the latest allocation, which is usually small.
+	pub fn write_to(&self, mut writer: impl Write) -> io::Result<()> {
+		writer.write_all(MAGIC)?;
+		writer.write_all(&Self::VERSION.to_le_bytes())?;
+		write_varint(&mut writer, self.events.len() as u64)?;
+
+		let mut count = 0;
+
+		for event in &self.events {
+			match *event {
+				TraceEvent::Alloc { layout } => {
+					writer.write_all(&[0, layout.align().trailing_zeros() as u8])?;
+					write_varint(&mut writer, layout.size() as u64)?;
+
+					count += 1;
+				}
+				TraceEvent::Dealloc { index } => {
+					writer.write_all(&[1])?;
+					write_varint(&mut writer, (count - index) as u64)?;
+				}
+				TraceEvent::Realloc { index, new_size } => {
+					writer.write_all(&[2])?;
+					write_varint(&mut writer, (count - index) as u64)?;
+					write_varint(&mut writer, new_size as u64)?;
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Reads a trace written by [`write_to`](Trace::write_to).
+	pub fn read_from(mut reader: impl Read) -> Result<Self, DumpError> {
+		let mut magic = [0; 8];
+		reader.read_exact(&mut magic)?;
+
+		if &magic != MAGIC {
+			return Err(DumpError::InvalidFormat);
+		}
+
+		let mut version = [0; 4];
+		reader.read_exact(&mut version)?;
+		let version = u32::from_le_bytes(version);
+
+		if version != Self::VERSION {
+			return Err(DumpError::UnsupportedVersion { version });
+		}
+
+		let len = read_varint(&mut reader)?;
+		let mut events = vec![];
+		let mut count = 0;
+
+		for _ in 0..len {
+			let mut tag = [0; 1];
+			reader.read_exact(&mut tag)?;
+
+			let event = match tag[0] {
+				0 => {
+					let mut align = [0; 1];
+					reader.read_exact(&mut align)?;
+
+					let align = 1usize
+						.checked_shl(align[0] as u32)
+						.ok_or(DumpError::InvalidFormat)?;
+					let size = read_usize(&mut reader)?;
+
+					count += 1;
+
+					TraceEvent::Alloc {
+						layout: Layout::from_size_align(size, align)
+							.map_err(|_| DumpError::InvalidFormat)?
+					}
+				}
+				1 => TraceEvent::Dealloc {
+					index: read_index(&mut reader, count)?
+				},
+				2 => TraceEvent::Realloc {
+					index: read_index(&mut reader, count)?,
+					new_size: read_usize(&mut reader)?
+				},
+				_ => return Err(DumpError::InvalidFormat)
+			};
+
+			events.push(event);
+		}
+
+		Ok(Self { events })
+	}
+
+	/// Replays the trace against the provided heap, measuring the time spent within the heap and the memory usage.
+	///
+	/// The heap may allocate from any [`RawBackend`], so that different backends can be compared on the same workload.
+	/// Allocations and reallocations that the backend cannot provide the memory for are counted as failures,
+	/// and the later events referring to failed allocations are skipped (reallocations that fail keep the previous memory).
+	///
+	/// Allocations that are still live at the end of the trace are deallocated afterwards.
+	/// Deallocations and reallocations of unknown or already deallocated allocations are skipped.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{Heap, Memory, StaticBuffer, TraceRecorder};
+	/// # use std::sync::Arc;
+	/// let recorder = Arc::new(TraceRecorder::new());
+	///
+	/// let memory = Memory::new();
+	/// memory.add_observer(Arc::clone(&recorder));
+	///
+	/// let blocks: Vec<_> = (0..3).map(|_| memory.alloc([0u64; 8])).collect();
+	///
+	/// // Replaying the workload on a buffer that only fits two of the blocks
+	/// let buffer: &'static mut [u8] = Box::leak(Box::new([0; 512]));
+	/// let mut heap = Heap::with_backend(0, StaticBuffer::new(buffer));
+	/// let report = recorder.trace().replay(&mut heap);
+	///
+	/// assert_eq!(report.failures, 1);
+	/// assert_eq!(report.peak_count, 2);
+	/// # drop(blocks);
+	/// ```
+	pub fn replay<B: RawBackend>(&self, heap: &mut Heap<B>) -> ReplayReport {
+		// Pointers and layouts of the allocations, by their indices
+		let mut allocations: Vec<Option<(NonNull<u8>, Layout)>> = vec![];
+
+		// Sizes of the live allocations, by their addresses
+		let mut live: BTreeMap<usize, usize> = BTreeMap::new();
+
+		let mut report = ReplayReport {
+			elapsed: Duration::ZERO,
+			events: 0,
+			peak_bytes: 0,
+			peak_count: 0,
+			fragmentation: 0.0,
+			failures: 0
+		};
+
+		let mut bytes = 0;
+
+		for event in &self.events {
+			match *event {
+				TraceEvent::Alloc { layout } => {
+					let start = Instant::now();
+					let result = heap.try_alloc_tagged(layout, None);
+					report.elapsed += start.elapsed();
+
+					match result {
+						Ok(ptr) => {
+							allocations.push(Some((ptr, layout)));
+							live.insert(ptr.as_ptr() as usize, layout.size());
+							bytes += layout.size();
+						}
+						Err(_) => {
+							// Keeping the indices of the later allocations in place
+							allocations.push(None);
+							report.failures += 1;
+						}
+					}
+				}
+				TraceEvent::Dealloc { index } => {
+					let Some((ptr, layout)) = allocations.get_mut(index).and_then(Option::take)
+					else {
+						continue;
+					};
+
+					let start = Instant::now();
+					heap.dealloc(ptr, layout);
+					report.elapsed += start.elapsed();
+
+					live.remove(&(ptr.as_ptr() as usize));
+					bytes -= layout.size();
+				}
+				TraceEvent::Realloc { index, new_size } => {
+					let Some(Some((ptr, layout))) = allocations.get_mut(index) else {
+						continue;
+					};
+
+					let start = Instant::now();
+					let result = heap.try_realloc(*ptr, *layout, new_size);
+					report.elapsed += start.elapsed();
+
+					match result {
+						Ok(new_ptr) => {
+							live.remove(&(ptr.as_ptr() as usize));
+							live.insert(new_ptr.as_ptr() as usize, new_size);
+							bytes = bytes - layout.size() + new_size;
+
+							*ptr = new_ptr;
+							*layout = Layout::from_size_align(new_size, layout.align())
+								.expect("Layout creation failed");
+						}
+						// The allocation keeps its previous memory
+						Err(_) => report.failures += 1
+					}
+				}
+			}
+
+			report.events += 1;
+			report.peak_count = std::cmp::max(report.peak_count, live.len());
+
+			if bytes > report.peak_bytes {
+				report.peak_bytes = bytes;
+				report.fragmentation = fragmentation(&live, bytes);
+			}
+		}
+
+		// Cleaning up the allocations that were never deallocated
+		for (ptr, layout) in allocations.into_iter().flatten() {
+			heap.dealloc(ptr, layout);
+		}
+
+		report
+	}
+}
+
+impl TraceRecorder {
+	/// Creates a recorder with an empty trace.
+	pub fn new() -> Self { Self::default() }
+
+	/// Gets a copy of the trace recorded so far.
+	pub fn trace(&self) -> Trace {
+		Trace {
+			events: self.lock().events.clone()
+		}
+	}
+
+	/// Acquires the lock of the recording.
+	fn lock(&self) -> std::sync::MutexGuard<'_, Recording> {
+		self.state.lock().expect("Recording lock failed")
+	}
+}
+
+impl HeapObserver for TraceRecorder {
+	fn on_alloc(&self, allocation: &AllocationInfo) {
+		let mut state = self.lock();
+		let index = state.count;
+
+		state.count += 1;
+		state.indices.insert(allocation.id, index);
+		state.events.push(TraceEvent::Alloc {
+			layout: allocation.layout
+		});
+	}
+
+	fn on_dealloc(&self, allocation: &AllocationInfo) {
+		let mut state = self.lock();
+
+		// Allocations made before the recorder was registered are not known
+		if let Some(index) = state.indices.remove(&allocation.id) {
+			state.events.push(TraceEvent::Dealloc { index });
+		}
+	}
+
+	fn on_realloc(&self, _old: &AllocationInfo, new: &AllocationInfo) {
+		let mut state = self.lock();
+
+		if let Some(&index) = state.indices.get(&new.id) {
+			state.events.push(TraceEvent::Realloc {
+				index,
+				new_size: new.layout.size()
+			});
+		}
+	}
+}
+
+/// Gets the fraction of the address range spanned by the live allocations that is not used by them.
+fn fragmentation(live: &BTreeMap<usize, usize>, bytes: usize) -> f64 {
+	let (Some((&first, _)), Some((&last, &last_size))) =
+		(live.first_key_value(), live.last_key_value())
+	else {
+		return 0.0;
+	};
+
+	let span = last + last_size - first;
+
+	if span == 0 {
+		return 0.0;
+	}
+
+	1.0 - bytes as f64 / span as f64
+}
+
+/// Writes an unsigned LEB128 integer.
+fn write_varint(writer: &mut impl Write, mut value: u64) -> io::Result<()> {
+	loop {
+		let byte = (value & 0x7f) as u8;
+		value >>= 7;
+
+		if value == 0 {
+			return writer.write_all(&[byte]);
+		}
+
+		writer.write_all(&[byte | 0x80])?;
+	}
+}
+
+/// Reads an unsigned LEB128 integer.
+fn read_varint(reader: &mut impl Read) -> Result<u64, DumpError> {
+	let mut value = 0u64;
+
+	for shift in (0..64).step_by(7) {
+		let mut byte = [0; 1];
+		reader.read_exact(&mut byte)?;
+
+		value |= ((byte[0] & 0x7f) as u64) << shift;
+
+		if byte[0] & 0x80 == 0 {
+			return Ok(value);
+		}
+	}
+
+	Err(DumpError::InvalidFormat)
+}
+
+fn read_usize(reader: &mut impl Read) -> Result<usize, DumpError> {
+	usize::try_from(read_varint(reader)?).map_err(|_| DumpError::InvalidFormat)
+}
+
+/// Reads the index of an allocation, stored as its distance from the latest one.
+fn read_index(reader: &mut impl Read, count: usize) -> Result<usize, DumpError> {
+	let distance = read_usize(reader)?;
+	count.checked_sub(distance).ok_or(DumpError::InvalidFormat)
+}
@@ -0,0 +1,209 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+use core::any::Any;
+use core::ops::Deref;
+use core::ptr::NonNull;
+
+use crate::heap::{destroy, Allocation};
+use crate::{Allocatable, DefaultBackend, Memory, RawBackend};
+
+/// A type-erased [`restore`] function.
+type Restore = unsafe fn(NonNull<u8>, Box<dyn Any>) -> Box<dyn Any>;
+
+#[derive(Debug)]
+/// A previous state of a value, recorded in the undo journal of a [`Transaction`].
+enum Entry {
+	/// Bytes of a plain data value, copied before it was mutated in place
+	Bytes(Vec<u8>),
+
+	/// Value that was replaced (see [`HeapMutator::write`](crate::HeapMutator::write)), moved out of its allocation
+	Replaced {
+		/// Previous value
+		value: Box<dyn Any>,
+
+		/// Size of the value
+		size: usize,
+
+		/// Moves the previous value back into an allocation
+		restore: Restore
+	}
+}
+
+/// Moves the provided value of type `T` back into the allocation at `ptr`, returning the value it replaces.
+///
+/// # Safety
+///
+/// The allocation must hold a value of type `T`.
+unsafe fn restore<T: Allocatable>(ptr: NonNull<u8>, value: Box<dyn Any>) -> Box<dyn Any> {
+	let value = value
+		.downcast::<T>()
+		.expect("Journaled value has a different type");
+
+	Box::new(unsafe { core::ptr::replace(ptr.cast::<T>().as_ptr(), *value) })
+}
+
+#[derive(Debug)]
+/// The undo journal of an active [`Transaction`].
+pub(crate) struct Journal {
+	/// Identifiers of the allocations made on the memory within the transaction
+	allocated: BTreeSet<u64>,
+
+	/// Previous states of the mutated values, along with the identifiers of their allocations
+	entries: Vec<(u64, Entry)>
+}
+
+impl Journal {
+	/// Records the current contents of the provided allocation, which holds a plain data value.
+	pub(crate) fn record(&mut self, allocation: &Allocation) {
+		let bytes = unsafe {
+			core::slice::from_raw_parts(allocation.ptr.as_ptr(), allocation.layout.size())
+		};
+
+		self.entries
+			.push((allocation.id, Entry::Bytes(bytes.to_vec())));
+	}
+
+	/// Records the previous value of the provided allocation, which has just been replaced.
+	pub(crate) fn record_replaced<T: Allocatable>(&mut self, allocation: &Allocation, previous: T) {
+		let entry = Entry::Replaced {
+			value: Box::new(previous),
+			size: core::mem::size_of::<T>(),
+			restore: restore::<T>
+		};
+
+		self.entries.push((allocation.id, entry));
+	}
+
+	/// Records that the allocation with the provided identifier has been made within the transaction.
+	pub(crate) fn record_alloc(&mut self, id: u64) { self.allocated.insert(id); }
+
+	/// Stops treating the allocation with the provided identifier as made within the transaction
+	/// (e.g., since it holds a value that has been moved in from another memory).
+	pub(crate) fn forget_alloc(&mut self, id: u64) { self.allocated.remove(&id); }
+
+	/// Shows whether the allocation with the provided identifier has been made within the transaction.
+	pub(crate) fn is_allocated(&self, id: u64) -> bool { self.allocated.contains(&id) }
+}
+
+#[derive(Debug)]
+/// A transaction on [`Memory`], whose changes are undone if it fails.
+///
+/// Transactions are started with [`Memory::transaction`], and they dereference to the [`Memory`] they were started on.
+pub struct Transaction<'memory, B: RawBackend = DefaultBackend> {
+	/// Memory that the transaction was started on
+	memory: &'memory Memory<B>
+}
+
+impl<'memory, B: RawBackend> Transaction<'memory, B> {
+	/// Starts a transaction on the provided [`Memory`], watching the mutations of all of its values.
+	///
+	/// # Panics
+	///
+	/// Panics if there already is an active transaction on the memory.
+	pub(crate) fn begin(memory: &'memory Memory<B>) -> Self {
+		let mut heap = memory.get_heap();
+
+		if heap.journal.is_some() {
+			// Releasing the lock before panicking, so that the heap is not poisoned
+			drop(heap);
+			panic!("Transactions cannot be nested");
+		}
+
+		// Values without a slot have no mutators, so their slots are watched once they are allocated (see `Heap::slot`)
+		for slot in heap.records().filter_map(Allocation::slot) {
+			slot.watch();
+		}
+
+		heap.journal = Some(Journal {
+			allocated: BTreeSet::new(),
+			entries: vec![]
+		});
+
+		// The journal has to see every allocation right away
+		memory.cache.update(&heap);
+
+		Self { memory }
+	}
+
+	/// Gets the [`Memory`] that the transaction was started on.
+	pub fn memory(&self) -> &'memory Memory<B> { self.memory }
+
+	/// Keeps all of the changes made within the transaction, dropping the values that were replaced.
+	pub(crate) fn commit(self) {
+		let mut heap = self.memory.get_heap();
+		let journal = heap.journal.take();
+		heap.unwatch();
+		drop(heap);
+
+		// The replaced values are dropped along with the journal, once the heap is unlocked
+		drop(journal);
+	}
+
+	/// Restores the previous contents of the mutated values, and deallocates the values allocated within the transaction.
+	pub(crate) fn rollback(self) {
+		let mut heap = self.memory.get_heap();
+
+		let Some(journal) = heap.journal.take() else {
+			return;
+		};
+
+		heap.unwatch();
+
+		let Journal {
+			allocated, entries, ..
+		} = journal;
+		let mut replaced = Vec::new();
+
+		// Restoring the values in reverse order, in case any of them were recorded more than once
+		for (id, entry) in entries.into_iter().rev() {
+			let allocation = heap.records().find(|a| a.id == id);
+
+			match entry {
+				Entry::Bytes(bytes) => {
+					if let Some(allocation) = allocation.filter(|a| a.layout.size() == bytes.len())
+					{
+						unsafe {
+							core::ptr::copy_nonoverlapping(
+								bytes.as_ptr(),
+								allocation.ptr.as_ptr(),
+								bytes.len()
+							)
+						}
+					}
+				}
+				Entry::Replaced {
+					value,
+					size,
+					restore
+				} => {
+					// The allocation might have been cast to a different type within the transaction
+					let type_id = (*value).type_id();
+					let allocation = allocation.filter(|a| {
+						a.layout.size() == size && a.type_tag.is_none_or(|tag| tag.id == type_id)
+					});
+
+					match allocation {
+						Some(allocation) => {
+							replaced.push(unsafe { restore(allocation.ptr, value) })
+						}
+						None => replaced.push(value)
+					}
+				}
+			}
+		}
+
+		let allocations = heap.take_allocated(&allocated);
+		drop(heap);
+
+		// Dropping the replaced values once the heap is unlocked, since their destructors might use the memory
+		drop(replaced);
+		destroy(&self.memory.heap, allocations);
+	}
+}
+
+impl<B: RawBackend> Deref for Transaction<'_, B> {
+	type Target = Memory<B>;
+
+	fn deref(&self) -> &Self::Target { self.memory }
+}
@@ -0,0 +1,357 @@
+use alloc::vec::Vec;
+use core::alloc::Layout;
+use core::fmt::{self, Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ops::{Index, IndexMut};
+use core::ptr::NonNull;
+
+use crate::heap::TypeTag;
+use crate::{AllocError, Allocatable, Heap, HeapStats, RawBackend, SystemBackend};
+
+/// Count of bytes that each chunk of a [`TypedMemory`] is aimed to take
+const CHUNK_BYTES: usize = 16 * 1024;
+
+/// An arena of values of a single type, which stores them next to each other in chunks allocated from its own [`Heap`].
+///
+/// Values are referred to by [`Handle`]s, and can be iterated over in the order of their slots, which keeps the iteration cache-friendly.
+/// The slots of removed values are reused, and the handles of removed values never resolve to the values that replace them.
+/// All of the values are dropped, and all of the chunks deallocated, at once when the arena is dropped.
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::{Allocatable, TypedMemory};
+/// struct Position {
+///     x: f32,
+///     y: f32
+/// }
+///
+/// impl Allocatable for Position {}
+///
+/// let mut positions = TypedMemory::new();
+///
+/// let player = positions.insert(Position { x: 0.0, y: 0.0 });
+/// let enemy = positions.insert(Position { x: 5.0, y: 2.0 });
+///
+/// for position in positions.values_mut() {
+///     position.x += 1.0;
+/// }
+///
+/// assert_eq!(positions[player].x, 1.0);
+///
+/// positions.remove(enemy);
+///
+/// assert_eq!(positions.len(), 1);
+/// assert!(positions.get(enemy).is_none());
+/// ```
+pub struct TypedMemory<T: Allocatable, B: RawBackend = SystemBackend> {
+	/// Heap that the chunks are allocated from
+	heap: Heap<B>,
+
+	/// Allocated chunks, each holding [`CHUNK_LEN`](TypedMemory::CHUNK_LEN) slots
+	chunks: Vec<NonNull<T>>,
+
+	/// States of the slots, by their indices
+	entries: Vec<Entry>,
+
+	/// Indices of the vacant slots that have been occupied before
+	free: Vec<usize>,
+
+	/// Count of the stored values
+	len: usize,
+
+	/// Indicates that the arena owns values of type `T`
+	marker: PhantomData<T>
+}
+
+#[derive(Debug, Clone, Copy)]
+/// The state of a slot of a [`TypedMemory`].
+struct Entry {
+	/// Count of the values that have been removed from the slot
+	generation: u32,
+
+	/// Indicates whether the slot holds a value
+	occupied: bool
+}
+
+/// A reference to a value stored within a [`TypedMemory`].
+///
+/// Handles are only meaningful to the arena that created them.
+pub struct Handle<T> {
+	/// Index of the slot
+	index: usize,
+
+	/// Generation of the slot at the time of the insertion
+	generation: u32,
+
+	/// Indicates the type of the referred value
+	marker: PhantomData<fn() -> T>
+}
+
+impl<T: Allocatable> TypedMemory<T> {
+	/// Creates an empty arena that allocates from the global allocator.
+	pub fn new() -> Self { Self::with_backend(SystemBackend) }
+}
+
+impl<T: Allocatable, B: RawBackend> TypedMemory<T, B> {
+	/// Count of slots within each chunk.
+	pub const CHUNK_LEN: usize = match size_of::<T>() {
+		0 => CHUNK_BYTES,
+		size if size >= CHUNK_BYTES => 1,
+		size => CHUNK_BYTES / size
+	};
+
+	/// Creates an empty arena that allocates from the provided [`RawBackend`].
+	pub fn with_backend(backend: B) -> Self {
+		Self {
+			heap: Heap::with_backend(0, backend),
+			chunks: vec![],
+			entries: vec![],
+			free: vec![],
+			len: 0,
+			marker: PhantomData
+		}
+	}
+
+	/// Stores the provided value, returning the handle to it.
+	///
+	/// # Panics
+	///
+	/// Panics if the backend is exhausted (see [`try_insert`](TypedMemory::try_insert)).
+	#[track_caller]
+	pub fn insert(&mut self, value: T) -> Handle<T> {
+		self.try_insert(value)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Stores the provided value like [`insert`](TypedMemory::insert), failing if the backend is exhausted.
+	#[track_caller]
+	pub fn try_insert(&mut self, value: T) -> Result<Handle<T>, AllocError> {
+		let index = match self.free.pop() {
+			Some(index) => index,
+			None => {
+				if self.entries.len() == self.chunks.len() * Self::CHUNK_LEN {
+					self.grow()?;
+				}
+
+				self.entries.push(Entry {
+					generation: 0,
+					occupied: false
+				});
+
+				self.entries.len() - 1
+			}
+		};
+
+		unsafe { self.slot(index).write(value) };
+
+		let entry = &mut self.entries[index];
+		entry.occupied = true;
+		self.len += 1;
+
+		Ok(Handle {
+			index,
+			generation: entry.generation,
+			marker: PhantomData
+		})
+	}
+
+	/// Gets a reference to the value of the provided handle, unless it has been removed.
+	pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+		self.contains(handle)
+			.then(|| unsafe { self.slot(handle.index).as_ref() })
+	}
+
+	/// Gets a mutable reference to the value of the provided handle, unless it has been removed.
+	pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+		self.contains(handle)
+			.then(|| unsafe { self.slot(handle.index).as_mut() })
+	}
+
+	/// Shows whether the value of the provided handle is still stored.
+	pub fn contains(&self, handle: Handle<T>) -> bool {
+		self.entries
+			.get(handle.index)
+			.is_some_and(|entry| entry.occupied && entry.generation == handle.generation)
+	}
+
+	/// Removes and returns the value of the provided handle, unless it has already been removed.
+	pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+		if !self.contains(handle) {
+			return None;
+		}
+
+		let entry = &mut self.entries[handle.index];
+		entry.occupied = false;
+		entry.generation = entry.generation.wrapping_add(1);
+
+		self.free.push(handle.index);
+		self.len -= 1;
+
+		Some(unsafe { self.slot(handle.index).read() })
+	}
+
+	/// Drops all of the values, keeping the chunks for the values stored afterwards.
+	///
+	/// The handles of the dropped values no longer resolve.
+	pub fn clear(&mut self) {
+		for index in 0..self.entries.len() {
+			let entry = &mut self.entries[index];
+
+			if !entry.occupied {
+				continue;
+			}
+
+			entry.occupied = false;
+			entry.generation = entry.generation.wrapping_add(1);
+
+			self.free.push(index);
+			self.len -= 1;
+
+			unsafe { self.slot(index).drop_in_place() }
+		}
+	}
+
+	/// Gets an iterator over the handles and the values, in the order of their slots.
+	pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &T)> + '_ {
+		self.occupied()
+			.map(|(handle, ptr)| (handle, unsafe { ptr.as_ref() }))
+	}
+
+	/// Gets an iterator over the handles and the mutable values, in the order of their slots.
+	pub fn iter_mut(&mut self) -> impl Iterator<Item = (Handle<T>, &mut T)> + '_ {
+		// The values are only borrowed mutably once each, through the exclusive borrow of the arena
+		self.occupied()
+			.map(|(handle, mut ptr)| (handle, unsafe { ptr.as_mut() }))
+	}
+
+	/// Gets an iterator over the values, in the order of their slots.
+	pub fn values(&self) -> impl Iterator<Item = &T> + '_ { self.iter().map(|(_, value)| value) }
+
+	/// Gets an iterator over the mutable values, in the order of their slots.
+	pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> + '_ {
+		self.iter_mut().map(|(_, value)| value)
+	}
+
+	/// Gets the count of stored values.
+	pub fn len(&self) -> usize { self.len }
+
+	/// Shows whether there are no stored values.
+	pub fn is_empty(&self) -> bool { self.len == 0 }
+
+	/// Gets the count of slots that have been allocated, which is the count of values that can be stored without allocating.
+	pub fn capacity(&self) -> usize { self.chunks.len() * Self::CHUNK_LEN }
+
+	/// Gets the statistics of the heap that the chunks are allocated from.
+	pub fn stats(&self) -> HeapStats { self.heap.stats() }
+
+	/// Gets the handles and the pointers of the occupied slots.
+	fn occupied(&self) -> impl Iterator<Item = (Handle<T>, NonNull<T>)> + '_ {
+		self.entries
+			.iter()
+			.enumerate()
+			.filter(|(_, entry)| entry.occupied)
+			.map(|(index, entry)| {
+				let handle = Handle {
+					index,
+					generation: entry.generation,
+					marker: PhantomData
+				};
+
+				(handle, self.slot(index))
+			})
+	}
+
+	/// Gets the pointer to the slot with the provided index.
+	fn slot(&self, index: usize) -> NonNull<T> {
+		let chunk = self.chunks[index / Self::CHUNK_LEN];
+		unsafe { chunk.add(index % Self::CHUNK_LEN) }
+	}
+
+	/// Allocates another chunk.
+	#[track_caller]
+	fn grow(&mut self) -> Result<(), AllocError> {
+		let layout = Self::chunk_layout();
+		let chunk = self
+			.heap
+			.try_alloc_tagged(layout, Some(TypeTag::of::<[T]>()))?;
+
+		self.chunks.push(chunk.cast::<T>());
+		Ok(())
+	}
+
+	/// Gets the layout of a chunk.
+	fn chunk_layout() -> Layout {
+		Layout::array::<T>(Self::CHUNK_LEN).expect("Layout creation failed")
+	}
+}
+
+impl<T: Allocatable> Default for TypedMemory<T> {
+	fn default() -> Self { Self::new() }
+}
+
+impl<T: Allocatable + Debug, B: RawBackend> Debug for TypedMemory<T, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_list().entries(self.values()).finish()
+	}
+}
+
+impl<T: Allocatable, B: RawBackend> Index<Handle<T>> for TypedMemory<T, B> {
+	type Output = T;
+
+	fn index(&self, handle: Handle<T>) -> &Self::Output {
+		self.get(handle)
+			.expect("Value of the handle has been removed")
+	}
+}
+
+impl<T: Allocatable, B: RawBackend> IndexMut<Handle<T>> for TypedMemory<T, B> {
+	fn index_mut(&mut self, handle: Handle<T>) -> &mut Self::Output {
+		self.get_mut(handle)
+			.expect("Value of the handle has been removed")
+	}
+}
+
+impl<T: Allocatable, B: RawBackend> Drop for TypedMemory<T, B> {
+	fn drop(&mut self) {
+		self.clear();
+
+		let layout = Self::chunk_layout();
+
+		for chunk in self.chunks.drain(..) {
+			self.heap.dealloc(chunk.cast::<u8>(), layout);
+		}
+	}
+}
+
+impl<T> Clone for Handle<T> {
+	fn clone(&self) -> Self { *self }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.index == other.index && self.generation == other.generation
+	}
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+	fn hash<H: Hasher>(&self, state: &mut H) {
+		self.index.hash(state);
+		self.generation.hash(state);
+	}
+}
+
+impl<T> Debug for Handle<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Handle")
+			.field("index", &self.index)
+			.field("generation", &self.generation)
+			.finish()
+	}
+}
@@ -0,0 +1,527 @@
+use core::alloc::Layout;
+use core::borrow::{Borrow, BorrowMut};
+use core::fmt::{self, Debug, Formatter};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::{AllocError, DefaultBackend, Memory, RawBackend};
+
+/// Count of elements that a non-empty [`HVec`] is grown to at least
+const MIN_CAPACITY: usize = 4;
+
+/// A contiguous growable array like [`Vec`], whose buffer is allocated from a [`Memory`].
+///
+/// The whole buffer (including its unused capacity) is counted by [`Memory::size`] as a single allocation of [`Memory::count`],
+/// and the byte limit of the memory applies to its growth. Like the chunks of a [`Pool`](crate::Pool), the buffer is not recorded by the heap,
+/// so it is not visible to [`Memory::stats`], snapshots or observers, and it is left alone by [`Memory::reset`] and by failed transactions.
+///
+/// # Examples
+///
+/// ```
+/// # use halloc::{HVec, Memory};
+/// let memory = Memory::new();
+/// let mut scores = HVec::new_in(&memory);
+///
+/// scores.extend([3u32, 1, 2]);
+/// scores.sort();
+///
+/// assert_eq!(scores, [1, 2, 3]);
+/// assert_eq!(memory.size(), scores.capacity() * size_of::<u32>());
+/// assert_eq!(memory.count(), 1);
+///
+/// scores.clear();
+/// scores.shrink_to_fit();
+///
+/// assert_eq!(memory.size(), 0);
+/// assert_eq!(memory.count(), 0);
+/// ```
+pub struct HVec<'memory, T, B: RawBackend = DefaultBackend> {
+	/// Pointer to the buffer
+	ptr: NonNull<T>,
+
+	/// Count of elements that the buffer can hold
+	capacity: usize,
+
+	/// Count of the initialized elements at the start of the buffer
+	len: usize,
+
+	/// Memory that the buffer is allocated from
+	memory: &'memory Memory<B>,
+
+	/// Indicates that the vector owns values of type `T`
+	marker: PhantomData<T>
+}
+
+impl<'memory, T, B: RawBackend> HVec<'memory, T, B> {
+	/// Creates an empty vector on the provided [`Memory`], which does not allocate until elements are pushed to it.
+	pub fn new_in(memory: &'memory Memory<B>) -> Self {
+		Self {
+			ptr: NonNull::dangling(),
+			capacity: 0,
+			len: 0,
+			memory,
+			marker: PhantomData
+		}
+	}
+
+	/// Creates an empty vector on the provided [`Memory`], with space for at least `capacity` elements.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffer would exceed the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn with_capacity_in(capacity: usize, memory: &'memory Memory<B>) -> Self {
+		let mut vec = Self::new_in(memory);
+		vec.reserve_exact(capacity);
+
+		vec
+	}
+
+	/// Gets the [`Memory`] that the buffer is allocated from.
+	pub fn memory(&self) -> &'memory Memory<B> { self.memory }
+
+	/// Gets the count of elements.
+	pub fn len(&self) -> usize { self.len }
+
+	/// Shows whether there are no elements.
+	pub fn is_empty(&self) -> bool { self.len == 0 }
+
+	/// Gets the count of elements that the vector can hold without reallocating.
+	pub fn capacity(&self) -> usize {
+		// Zero-sized elements never need a buffer
+		match size_of::<T>() {
+			0 => usize::MAX,
+			_ => self.capacity
+		}
+	}
+
+	/// Reserves space for at least `additional` more elements, growing the buffer geometrically to keep pushes cheap.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffer would exceed the byte limit of the memory, or if the backend is exhausted (see [`try_reserve`](HVec::try_reserve)).
+	#[track_caller]
+	pub fn reserve(&mut self, additional: usize) {
+		self.try_reserve(additional)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Reserves space for at least `additional` more elements like [`reserve`](HVec::reserve),
+	/// failing if the buffer would exceed the byte limit of the memory, or if the backend is exhausted.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # use halloc::{AllocError, HVec, Memory};
+	/// let memory = Memory::with_limit(64);
+	/// let mut samples = HVec::<u64>::new_in(&memory);
+	///
+	/// assert!(samples.try_reserve(8).is_ok());
+	/// assert_eq!(
+	///     samples.try_reserve(9),
+	///     Err(AllocError::LimitExceeded { requested: 64, available: 0 })
+	/// );
+	/// ```
+	pub fn try_reserve(&mut self, additional: usize) -> Result<(), AllocError> {
+		let required = self.required(additional)?;
+
+		if required <= self.capacity() {
+			return Ok(());
+		}
+
+		let capacity = core::cmp::max(required, self.capacity.saturating_mul(2));
+		self.set_capacity(core::cmp::max(capacity, MIN_CAPACITY))
+	}
+
+	/// Reserves space for exactly `additional` more elements, unless there already is enough of it.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffer would exceed the byte limit of the memory, or if the backend is exhausted (see [`try_reserve_exact`](HVec::try_reserve_exact)).
+	#[track_caller]
+	pub fn reserve_exact(&mut self, additional: usize) {
+		self.try_reserve_exact(additional)
+			.unwrap_or_else(|error| panic!("{error}"))
+	}
+
+	/// Reserves space for exactly `additional` more elements like [`reserve_exact`](HVec::reserve_exact),
+	/// failing if the buffer would exceed the byte limit of the memory, or if the backend is exhausted.
+	pub fn try_reserve_exact(&mut self, additional: usize) -> Result<(), AllocError> {
+		let required = self.required(additional)?;
+
+		if required <= self.capacity() {
+			return Ok(());
+		}
+
+		self.set_capacity(required)
+	}
+
+	/// Shrinks the buffer to fit the elements, deallocating it if there are none.
+	pub fn shrink_to_fit(&mut self) {
+		if self.capacity > self.len {
+			// Shrinking only fails if the backend is exhausted, in which case the buffer is kept as it is
+			let _ = self.set_capacity(self.len);
+		}
+	}
+
+	/// Appends the provided element to the end.
+	///
+	/// # Panics
+	///
+	/// Panics if the buffer needs to grow beyond the byte limit of the memory, or if the backend is exhausted.
+	#[track_caller]
+	pub fn push(&mut self, value: T) {
+		if self.len == self.capacity() {
+			self.reserve(1);
+		}
+
+		unsafe { self.ptr.add(self.len).write(value) }
+		self.len += 1;
+	}
+
+	/// Removes and returns the last element, if there is one.
+	pub fn pop(&mut self) -> Option<T> {
+		if self.len == 0 {
+			return None;
+		}
+
+		self.len -= 1;
+		Some(unsafe { self.ptr.add(self.len).read() })
+	}
+
+	/// Inserts the provided element at the provided index, shifting the elements after it to the right.
+	///
+	/// # Panics
+	///
+	/// Panics if the index is greater than the length, or if the buffer cannot grow.
+	#[track_caller]
+	pub fn insert(&mut self, index: usize, value: T) {
+		let len = self.len;
+		assert!(
+			index <= len,
+			"Index {index} is out of bounds of length {len}"
+		);
+
+		if len == self.capacity() {
+			self.reserve(1);
+		}
+
+		unsafe {
+			let ptr = self.ptr.add(index);
+			ptr.copy_to(ptr.add(1), len - index);
+			ptr.write(value);
+		}
+
+		self.len += 1;
+	}
+
+	/// Removes and returns the element at the provided index, shifting the elements after it to the left.
+	///
+	/// # Panics
+	///
+	/// Panics if the index is out of bounds.
+	#[track_caller]
+	pub fn remove(&mut self, index: usize) -> T {
+		let len = self.len;
+		assert!(
+			index < len,
+			"Index {index} is out of bounds of length {len}"
+		);
+
+		self.len -= 1;
+
+		unsafe {
+			let ptr = self.ptr.add(index);
+			let value = ptr.read();
+			ptr.add(1).copy_to(ptr, len - index - 1);
+
+			value
+		}
+	}
+
+	/// Removes and returns the element at the provided index, replacing it with the last element.
+	///
+	/// # Panics
+	///
+	/// Panics if the index is out of bounds.
+	#[track_caller]
+	pub fn swap_remove(&mut self, index: usize) -> T {
+		let len = self.len;
+		assert!(
+			index < len,
+			"Index {index} is out of bounds of length {len}"
+		);
+
+		self.len -= 1;
+
+		unsafe {
+			let ptr = self.ptr.add(index);
+			let value = ptr.read();
+			self.ptr.add(len - 1).copy_to(ptr, 1);
+
+			value
+		}
+	}
+
+	/// Drops the elements after the first `len` ones, keeping the buffer.
+	pub fn truncate(&mut self, len: usize) {
+		if len >= self.len {
+			return;
+		}
+
+		// Updating the length first, so that a panicking destructor leaks the rest of the elements instead of dropping them twice
+		let tail = core::ptr::slice_from_raw_parts_mut(
+			unsafe { self.ptr.add(len) }.as_ptr(),
+			self.len - len
+		);
+		self.len = len;
+
+		unsafe { tail.drop_in_place() }
+	}
+
+	/// Drops all of the elements, keeping the buffer.
+	pub fn clear(&mut self) { self.truncate(0) }
+
+	/// Keeps only the elements for which `f` returns `true`, in their order.
+	pub fn retain(&mut self, mut f: impl FnMut(&T) -> bool) {
+		let len = self.len;
+		let mut kept = 0;
+
+		// Updating the length first, so that a panic within `f` leaks the elements instead of dropping them twice
+		self.len = 0;
+
+		for index in 0..len {
+			let ptr = unsafe { self.ptr.add(index) };
+
+			if f(unsafe { ptr.as_ref() }) {
+				unsafe { ptr.copy_to(self.ptr.add(kept), 1) }
+				kept += 1;
+			} else {
+				unsafe { ptr.drop_in_place() }
+			}
+		}
+
+		self.len = kept;
+	}
+
+	/// Resizes the vector to `len` elements, filling the new ones with clones of the provided value.
+	#[track_caller]
+	pub fn resize(&mut self, len: usize, value: T)
+	where
+		T: Clone {
+		if len <= self.len {
+			self.truncate(len);
+			return;
+		}
+
+		self.reserve(len - self.len);
+
+		while self.len < len {
+			self.push(value.clone());
+		}
+	}
+
+	/// Appends clones of the elements of the provided slice to the end.
+	#[track_caller]
+	pub fn extend_from_slice(&mut self, slice: &[T])
+	where
+		T: Clone {
+		self.reserve(slice.len());
+
+		for value in slice {
+			self.push(value.clone());
+		}
+	}
+
+	/// Gets a slice of all of the elements.
+	pub fn as_slice(&self) -> &[T] {
+		unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+	}
+
+	/// Gets a mutable slice of all of the elements.
+	pub fn as_mut_slice(&mut self) -> &mut [T] {
+		unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+	}
+
+	/// Gets the count of elements that the vector needs to hold `additional` more of them.
+	fn required(&self, additional: usize) -> Result<usize, AllocError> {
+		self.len
+			.checked_add(additional)
+			.ok_or(AllocError::OutOfMemory {
+				requested: usize::MAX
+			})
+	}
+
+	/// Gets the layout of a buffer of the provided capacity.
+	fn layout(capacity: usize) -> Result<Layout, AllocError> {
+		Layout::array::<T>(capacity).map_err(|_| AllocError::OutOfMemory {
+			requested: capacity.saturating_mul(size_of::<T>())
+		})
+	}
+
+	/// Moves the elements into a buffer of the provided capacity (which is at least the length),
+	/// charging the budget of the memory for the difference.
+	fn set_capacity(&mut self, capacity: usize) -> Result<(), AllocError> {
+		debug_assert!(capacity >= self.len, "Elements must fit into the buffer");
+
+		let layout = Self::layout(self.capacity)?;
+		let new_layout = Self::layout(capacity)?;
+
+		let mut heap = self.memory.heap.lock().expect("Heap lock failed");
+
+		// The buffer is counted as a single allocation while it holds any memory
+		let (was_allocated, allocated) = (layout.size() != 0, new_layout.size() != 0);
+		let growth = new_layout.size().saturating_sub(layout.size());
+		heap.budget
+			.try_charge(growth, usize::from(allocated && !was_allocated))?;
+
+		let ptr = match unsafe {
+			heap.reallocate_unrecorded(self.ptr.cast::<u8>(), layout, new_layout.size())
+		} {
+			Ok(ptr) => ptr,
+			Err(error) => {
+				heap.budget
+					.release(growth, usize::from(allocated && !was_allocated));
+				return Err(error);
+			}
+		};
+
+		heap.budget.release(
+			layout.size().saturating_sub(new_layout.size()),
+			usize::from(was_allocated && !allocated)
+		);
+
+		let crossed = heap.budget.crossed();
+		drop(heap);
+
+		self.ptr = ptr.cast::<T>();
+		self.capacity = capacity;
+		crossed.call();
+
+		Ok(())
+	}
+}
+
+impl<T, B: RawBackend> Deref for HVec<'_, T, B> {
+	type Target = [T];
+
+	fn deref(&self) -> &Self::Target { self.as_slice() }
+}
+
+impl<T, B: RawBackend> DerefMut for HVec<'_, T, B> {
+	fn deref_mut(&mut self) -> &mut Self::Target { self.as_mut_slice() }
+}
+
+impl<T, B: RawBackend> AsRef<[T]> for HVec<'_, T, B> {
+	fn as_ref(&self) -> &[T] { self }
+}
+
+impl<T, B: RawBackend> AsMut<[T]> for HVec<'_, T, B> {
+	fn as_mut(&mut self) -> &mut [T] { self }
+}
+
+impl<T, B: RawBackend> Borrow<[T]> for HVec<'_, T, B> {
+	fn borrow(&self) -> &[T] { self }
+}
+
+impl<T, B: RawBackend> BorrowMut<[T]> for HVec<'_, T, B> {
+	fn borrow_mut(&mut self) -> &mut [T] { self }
+}
+
+impl<T, B: RawBackend> Extend<T> for HVec<'_, T, B> {
+	#[track_caller]
+	fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+		let iter = iter.into_iter();
+		self.reserve(iter.size_hint().0);
+
+		for value in iter {
+			self.push(value);
+		}
+	}
+}
+
+impl<'value, T: Copy + 'value, B: RawBackend> Extend<&'value T> for HVec<'_, T, B> {
+	#[track_caller]
+	fn extend<I: IntoIterator<Item = &'value T>>(&mut self, iter: I) {
+		self.extend(iter.into_iter().copied())
+	}
+}
+
+impl<'vec, T, B: RawBackend> IntoIterator for &'vec HVec<'_, T, B> {
+	type IntoIter = core::slice::Iter<'vec, T>;
+	type Item = &'vec T;
+
+	fn into_iter(self) -> Self::IntoIter { self.iter() }
+}
+
+impl<'vec, T, B: RawBackend> IntoIterator for &'vec mut HVec<'_, T, B> {
+	type IntoIter = core::slice::IterMut<'vec, T>;
+	type Item = &'vec mut T;
+
+	fn into_iter(self) -> Self::IntoIter { self.iter_mut() }
+}
+
+impl<T: Clone, B: RawBackend> Clone for HVec<'_, T, B> {
+	/// Clones the vector into a new buffer on the same [`Memory`].
+	fn clone(&self) -> Self {
+		let mut clone = Self::with_capacity_in(self.len, self.memory);
+		clone.extend_from_slice(self);
+
+		clone
+	}
+}
+
+impl<T: Debug, B: RawBackend> Debug for HVec<'_, T, B> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { Debug::fmt(self.as_slice(), f) }
+}
+
+impl<T: PartialEq<U>, U, B: RawBackend, C: RawBackend> PartialEq<HVec<'_, U, C>>
+	for HVec<'_, T, B>
+{
+	fn eq(&self, other: &HVec<'_, U, C>) -> bool { self.as_slice() == other.as_slice() }
+}
+
+impl<T: PartialEq<U>, U, B: RawBackend> PartialEq<[U]> for HVec<'_, T, B> {
+	fn eq(&self, other: &[U]) -> bool { self.as_slice() == other }
+}
+
+impl<T: PartialEq<U>, U, B: RawBackend> PartialEq<&[U]> for HVec<'_, T, B> {
+	fn eq(&self, other: &&[U]) -> bool { self.as_slice() == *other }
+}
+
+impl<T: PartialEq<U>, U, B: RawBackend, const N: usize> PartialEq<[U; N]> for HVec<'_, T, B> {
+	fn eq(&self, other: &[U; N]) -> bool { self.as_slice() == other }
+}
+
+impl<T: Eq, B: RawBackend> Eq for HVec<'_, T, B> {}
+
+impl<T: Hash, B: RawBackend> Hash for HVec<'_, T, B> {
+	fn hash<H: Hasher>(&self, state: &mut H) { self.as_slice().hash(state) }
+}
+
+impl<T, B: RawBackend> Drop for HVec<'_, T, B> {
+	fn drop(&mut self) {
+		self.clear();
+
+		let layout = Self::layout(self.capacity).expect("Layout creation failed");
+
+		if layout.size() == 0 {
+			return;
+		}
+
+		let mut heap = match self.memory.heap.lock() {
+			Ok(lock) => lock,
+			Err(_) => {
+				#[cfg(feature = "std")]
+				eprintln!("Heap lock failed");
+				return;
+			}
+		};
+
+		unsafe { heap.deallocate_unrecorded(self.ptr.cast::<u8>(), layout) }
+		heap.budget.release(layout.size(), 1);
+	}
+}